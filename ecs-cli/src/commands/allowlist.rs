@@ -0,0 +1,127 @@
+//! Edit the systems golt.toml allows to write each component
+//!
+//! These commands only edit golt.toml and send no transactions. The on-chain
+//! allowlist changes when its admin sends the component's `AddSystem` /
+//! `RemoveSystem` instruction, built with the generated TypeScript.
+
+use anyhow::Result;
+use heck::ToUpperCamelCase;
+
+use crate::config::GoltConfig;
+
+pub fn allow(component: &str, system: &str) -> Result<()> {
+    let (mut config, project_root) = GoltConfig::find_config()?;
+    check_system(&config, system)?;
+
+    let comp = find_component(&mut config, component)?;
+    if comp.allowed_systems.iter().any(|s| s == system) {
+        println!("System '{}' is already allowed to write '{}'", system, component);
+        return Ok(());
+    }
+    comp.allowed_systems.push(system.to_string());
+    config.save(&project_root.join("golt.toml"))?;
+
+    println!("Allowed system '{}' to write '{}'", system, component);
+    print_sync_hint(component, "AddSystem");
+
+    Ok(())
+}
+
+pub fn disallow(component: &str, system: &str) -> Result<()> {
+    let (mut config, project_root) = GoltConfig::find_config()?;
+
+    let comp = find_component(&mut config, component)?;
+    let before = comp.allowed_systems.len();
+    comp.allowed_systems.retain(|s| s != system);
+    if comp.allowed_systems.len() == before {
+        anyhow::bail!("System '{}' is not allowed to write '{}'", system, component);
+    }
+    config.save(&project_root.join("golt.toml"))?;
+
+    println!("Removed system '{}' from '{}'", system, component);
+    print_sync_hint(component, "RemoveSystem");
+
+    Ok(())
+}
+
+pub fn allowed(component: Option<&str>) -> Result<()> {
+    let (config, _) = GoltConfig::find_config()?;
+
+    if let Some(name) = component {
+        if !config.components.iter().any(|c| c.name == name) {
+            anyhow::bail!("Component '{}' not found in golt.toml", name);
+        }
+    }
+
+    for comp in &config.components {
+        if component.is_some_and(|name| name != comp.name) {
+            continue;
+        }
+        println!("{}:", comp.name);
+        if comp.allowed_systems.is_empty() {
+            println!("  (none)");
+        }
+        for system in &comp.allowed_systems {
+            let id = config
+                .systems
+                .iter()
+                .find(|s| &s.name == system)
+                .and_then(|s| s.program_id.as_deref())
+                .unwrap_or("(no keypair)");
+            println!("  - {} -> {}", system, id);
+        }
+    }
+
+    Ok(())
+}
+
+fn find_component<'a>(
+    config: &'a mut GoltConfig,
+    name: &str,
+) -> Result<&'a mut crate::config::ComponentConfig> {
+    config
+        .components
+        .iter_mut()
+        .find(|c| c.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Component '{}' not found in golt.toml", name))
+}
+
+fn check_system(config: &GoltConfig, name: &str) -> Result<()> {
+    if !config.systems.iter().any(|s| s.name == name) {
+        anyhow::bail!(
+            "System '{}' not found in golt.toml. Available systems: {}",
+            name,
+            config.systems.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn print_sync_hint(component: &str, instruction: &str) {
+    println!();
+    println!("Only golt.toml was updated; the on-chain allowlist is unchanged. To apply it:");
+    println!("  1. Run `golt generate ts`");
+    println!(
+        "  2. Send {}(admin, allowlistPda, systemProgramId), signed by the admin",
+        builder_name(component, instruction)
+    );
+}
+
+/// Name of the generated TypeScript builder for a component instruction
+fn builder_name(component: &str, instruction: &str) -> String {
+    format!("create{}{}Instruction", component.to_upper_camel_case(), instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_names_match_generated_typescript() {
+        assert_eq!(builder_name("health", "AddSystem"), "createHealthAddSystemInstruction");
+        assert_eq!(
+            builder_name("player_stats", "RemoveSystem"),
+            "createPlayerStatsRemoveSystemInstruction"
+        );
+    }
+}
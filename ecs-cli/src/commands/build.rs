@@ -4,9 +4,11 @@ use anyhow::Result;
 use std::process::{Command, Stdio};
 
 use crate::config::GoltConfig;
+use crate::discriminator;
 
 pub fn run(sbf: bool) -> Result<()> {
     let (config, project_root) = GoltConfig::find_config()?;
+    discriminator::check_collisions(&config, &project_root)?;
 
     println!("Building Golt project: {}", config.project.name);
 
@@ -0,0 +1,136 @@
+//! Decode program error codes
+
+use anyhow::Result;
+use golt_registry::RegistryError;
+use golt_runtime::{ErrorCode, ErrorInfo, GoltError};
+use std::path::Path;
+
+use crate::config::GoltConfig;
+use crate::idl::{self, Idl};
+use crate::parser::ParsedErrorVariant;
+
+/// Error codes of one program
+pub struct ErrorTable {
+    pub program: String,
+    pub offset: u32,
+    pub errors: Vec<ParsedErrorVariant>,
+}
+
+impl ErrorTable {
+    fn from_info(program: &str, offset: u32, table: &[ErrorInfo]) -> Self {
+        Self {
+            program: program.to_string(),
+            offset,
+            errors: table
+                .iter()
+                .map(|info| ParsedErrorVariant {
+                    name: info.name.to_string(),
+                    code: info.code,
+                    message: info.message.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// One past the last code in use
+    fn end(&self) -> u32 {
+        self.offset + self.errors.len() as u32
+    }
+}
+
+/// Error tables of the runtime, the entity registry and, inside a project,
+/// every component and system
+pub fn collect_tables(project: Option<(&GoltConfig, &Path)>) -> Vec<ErrorTable> {
+    match project {
+        Some((config, project_root)) => tables_from_idls(&idl::load_project(config, project_root)),
+        None => tables_from_idls(&[]),
+    }
+}
+
+/// Error tables of the runtime, the entity registry and the programs
+/// described by `idls`
+pub fn tables_from_idls(idls: &[Idl]) -> Vec<ErrorTable> {
+    let mut tables = vec![
+        ErrorTable::from_info("golt-runtime", GoltError::OFFSET, GoltError::ERROR_TABLE),
+        ErrorTable::from_info("golt-registry", RegistryError::OFFSET, RegistryError::ERROR_TABLE),
+    ];
+
+    for program in idls {
+        let Some(offset) = program.error_offset else {
+            continue;
+        };
+        tables.push(ErrorTable {
+            program: program.name.clone(),
+            offset,
+            errors: program
+                .errors
+                .iter()
+                .map(|e| ParsedErrorVariant {
+                    name: e.name.clone(),
+                    code: e.code,
+                    message: e.message.clone(),
+                })
+                .collect(),
+        });
+    }
+
+    tables
+}
+
+/// Pairs of programs whose error codes overlap
+pub fn find_overlaps(tables: &[ErrorTable]) -> Vec<(&str, &str)> {
+    let mut overlaps = Vec::new();
+    for (i, a) in tables.iter().enumerate() {
+        for b in &tables[i + 1..] {
+            if a.offset < b.end() && b.offset < a.end() {
+                overlaps.push((a.program.as_str(), b.program.as_str()));
+            }
+        }
+    }
+    overlaps
+}
+
+pub fn run(code: Option<&str>) -> Result<()> {
+    let project = GoltConfig::find_config().ok();
+    let tables = collect_tables(project.as_ref().map(|(c, root)| (c, root.as_path())));
+
+    for (a, b) in find_overlaps(&tables) {
+        println!("Warning: error codes of '{}' and '{}' overlap", a, b);
+    }
+
+    let Some(code) = code else {
+        for table in &tables {
+            println!("{} ({}-{}):", table.program, table.offset, table.end().saturating_sub(1));
+            for error in &table.errors {
+                println!("  {:>6}  {}: {}", error.code, error.name, error.message);
+            }
+        }
+        return Ok(());
+    };
+
+    let value = parse_code(code)?;
+    let found = tables.iter().find_map(|table| {
+        table
+            .errors
+            .iter()
+            .find(|e| e.code == value)
+            .map(|e| (table, e))
+    });
+
+    match found {
+        Some((table, error)) => {
+            println!("{} {} ({}): {}", error.code, error.name, table.program, error.message);
+            Ok(())
+        }
+        None => anyhow::bail!("Unknown error code {} (0x{:x})", value, value),
+    }
+}
+
+/// Parse a decimal code or a hex code as printed in transaction logs (`0x7d0`)
+fn parse_code(code: &str) -> Result<u32> {
+    let parsed = match code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => code.parse(),
+    };
+    parsed.map_err(|_| anyhow::anyhow!("Invalid error code: {}", code))
+}
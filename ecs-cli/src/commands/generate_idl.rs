@@ -0,0 +1,28 @@
+//! Generate JSON IDLs
+
+use anyhow::Result;
+use std::fs;
+
+use crate::config::GoltConfig;
+use crate::idl;
+
+pub fn run(output_dir: &str) -> Result<()> {
+    let (config, project_root) = GoltConfig::find_config()?;
+
+    println!("Generating IDLs to: {}", output_dir);
+
+    let output_path = project_root.join(output_dir);
+    fs::create_dir_all(&output_path)?;
+
+    for program in idl::load_project(&config, &project_root) {
+        let filename = format!("{}.json", program.name);
+        let content = serde_json::to_string_pretty(&program)? + "\n";
+        fs::write(output_path.join(&filename), content)?;
+        println!("  Generated: {}", filename);
+    }
+
+    println!();
+    println!("IDLs generated successfully!");
+
+    Ok(())
+}
@@ -1,11 +1,13 @@
 //! Generate TypeScript bindings
 
 use anyhow::Result;
-use heck::{ToLowerCamelCase, ToUpperCamelCase};
+use heck::{ToLowerCamelCase, ToShoutySnakeCase, ToUpperCamelCase};
 use std::fs;
 
-use crate::config::GoltConfig;
-use crate::parser::{self, ParsedComponent, ParsedField, ParsedInstruction};
+use crate::commands::error::{self, ErrorTable};
+use crate::config::{GoltConfig, SystemConfig};
+use crate::idl::{self, Idl, IdlArg, IdlComponent, IdlEvent, IdlField, IdlInstruction, ProgramKind};
+use crate::parser;
 
 pub fn run(output_dir: &str) -> Result<()> {
     let (config, project_root) = GoltConfig::find_config()?;
@@ -17,54 +19,35 @@ pub fn run(output_dir: &str) -> Result<()> {
 
     let mut exports = Vec::new();
 
-    // Generate component bindings
-    for component in &config.components {
-        let component_dir = project_root
-            .join(&config.project.components_dir)
-            .join(&component.name);
-
-        let state_path = component_dir.join("src/state.rs");
-        let instruction_path = component_dir.join("src/instruction.rs");
-
-        let parsed = if state_path.exists() {
-            parser::parse_component_state(&state_path).ok()
-        } else {
-            None
-        };
-
-        let instructions = if instruction_path.exists() {
-            parser::parse_component_instructions(&instruction_path).unwrap_or_default()
-        } else {
-            Vec::new()
+    // Generate component and system bindings from their IDLs
+    let programs = idl::load_project(&config, &project_root);
+    for program in &programs {
+        let content = match (program.kind, &program.component) {
+            (ProgramKind::Component, Some(component)) => {
+                let allowed_systems = config
+                    .components
+                    .iter()
+                    .find(|c| c.name == program.name)
+                    .map_or(&[][..], |c| &c.allowed_systems);
+                generate_component_ts(program, component, allowed_systems, &config.systems)
+            }
+            _ => generate_system_ts(program),
         };
 
-        let filename = format!("{}.ts", component.name);
-        let content = generate_component_ts(component, parsed.as_ref(), &instructions);
+        let filename = format!("{}.ts", program.name);
         fs::write(output_path.join(&filename), content)?;
-        exports.push(format!("export * from './{}';", component.name));
+        exports.push(format!("export * from './{}';", program.name));
         println!("  Generated: {}", filename);
     }
 
-    // Generate system bindings
-    for system in &config.systems {
-        let system_dir = project_root
-            .join(&config.project.systems_dir)
-            .join(&system.name);
-
-        let instruction_path = system_dir.join("src/instruction.rs");
-
-        let instructions = if instruction_path.exists() {
-            parser::parse_component_instructions(&instruction_path).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
-
-        let filename = format!("{}.ts", system.name);
-        let content = generate_system_ts(system, &instructions);
-        fs::write(output_path.join(&filename), content)?;
-        exports.push(format!("export * from './{}';", system.name));
-        println!("  Generated: {}", filename);
+    // Generate error table
+    let error_tables = error::tables_from_idls(&programs);
+    for (a, b) in error::find_overlaps(&error_tables) {
+        println!("  Warning: error codes of '{}' and '{}' overlap", a, b);
     }
+    fs::write(output_path.join("errors.ts"), generate_errors_ts(&error_tables))?;
+    exports.push("export * from './errors';".to_string());
+    println!("  Generated: errors.ts");
 
     // Generate index.ts
     exports.push("export * from './common';".to_string());
@@ -85,26 +68,27 @@ pub fn run(output_dir: &str) -> Result<()> {
 }
 
 fn generate_component_ts(
-    component: &crate::config::ComponentConfig,
-    parsed: Option<&ParsedComponent>,
-    instructions: &[ParsedInstruction],
+    program: &Idl,
+    component: &IdlComponent,
+    allowed_systems: &[String],
+    systems: &[SystemConfig],
 ) -> String {
-    let pascal_name = component.name.to_upper_camel_case();
-    let upper_name = component.name.to_uppercase();
+    let pascal_name = program.name.to_upper_camel_case();
+    let upper_name = program.name.to_uppercase();
 
-    let program_id = component
+    let program_id = program
         .program_id
         .as_deref()
         .unwrap_or("11111111111111111111111111111111");
 
-    let discriminator = seed_to_discriminator(&component.seed);
+    let has_layout = !component.fields.is_empty();
 
     // Generate interface fields
-    let interface_fields = if let Some(p) = parsed {
-        p.fields
+    let interface_fields = if has_layout {
+        component
+            .fields
             .iter()
-            .filter(|f| !f.is_discriminator)
-            .map(|f| format!("  {}: {};", f.name, f.ts_type))
+            .map(|f| format!("  {}: {};", f.name, field_ts_type(f)))
             .collect::<Vec<_>>()
             .join("\n")
     } else {
@@ -112,26 +96,55 @@ fn generate_component_ts(
     };
 
     // Generate parse function body
-    let parse_body = if let Some(p) = parsed {
-        generate_parse_body(&p.fields)
+    let parse_body = if has_layout {
+        generate_parse_body(&component.fields)
     } else {
         "  throw new Error('Not implemented - update based on state.rs');".to_string()
     };
 
-    // Generate size constant
-    let size = if let Some(p) = parsed {
-        p.fields.iter().map(|f| f.size).sum::<usize>()
+    // Generate serialize function body
+    let serialize_body = if has_layout {
+        let version = component
+            .version
+            .map(|v| format!("  data.writeUInt8({}, 8); // Layout version\n", v))
+            .unwrap_or_default();
+        version + &generate_serialize_body(&component.fields)
     } else {
-        0
+        "  throw new Error('Not implemented - update based on state.rs');".to_string()
     };
 
     // Generate instruction builders
-    let instruction_builders = instructions
+    let instruction_builders = program
+        .instructions
         .iter()
         .map(|i| generate_instruction_builder(&pascal_name, &upper_name, i))
         .collect::<Vec<_>>()
         .join("\n\n");
 
+    // Systems allowed to write this component (from golt.toml)
+    let allowed_systems = allowed_systems
+        .iter()
+        .map(|name| {
+            let id = systems
+                .iter()
+                .find(|s| &s.name == name)
+                .and_then(|s| s.program_id.as_deref());
+            match id {
+                Some(id) => format!("  new PublicKey('{}'), // {}", id, name),
+                None => format!("  // {}: no program ID yet, run `golt generate keypair {}`", name, name),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let field_readers = if has_layout {
+        generate_field_readers(&pascal_name, &upper_name, &component.fields)
+    } else {
+        String::new()
+    };
+
+    let events_ts = generate_events_ts(&pascal_name, &upper_name, &program.events);
+
     format!(
         r#"// Auto-generated by Golt - DO NOT EDIT
 import {{
@@ -141,7 +154,13 @@ import {{
   Connection,
   AccountInfo,
 }} from '@solana/web3.js';
-import {{ readU8, readU16, readU32, readU64, readI64, readPubkey, writeU8, writeU16, writeU32, writeU64, writePubkey }} from './common';
+import {{
+  readU8, readU16, readU32, readU64, readI8, readI16, readI32, readI64, readU128, readI128, readF32, readF64,
+  readPubkey, readFixed, readString, readVec, readBits,
+  writeU8, writeU16, writeU32, writeU64, writeI8, writeI16, writeI32, writeI64, writeU128, writeI128, writeF32, writeF64,
+  writeBool, writePubkey, writeFixed, writeBytes, writeString, writeVec, writeBits, encodeBytes, writeLenPrefixed,
+  deriveAllowlistPDA, checkDiscriminator, getProgramDataLogs,
+}} from './common';
 
 // Program ID
 export const {upper_name}_PROGRAM_ID = new PublicKey('{program_id}');
@@ -167,6 +186,17 @@ export function derive{pascal_name}PDA(
   );
 }}
 
+// System allowlist
+export const {upper_name}_ALLOWED_SYSTEMS: PublicKey[] = [
+{allowed_systems}
+];
+
+export function derive{pascal_name}AllowlistPDA(
+  programId: PublicKey = {upper_name}_PROGRAM_ID
+): [PublicKey, number] {{
+  return deriveAllowlistPDA(programId);
+}}
+
 // Parse component from account data
 export function parse{pascal_name}(data: Buffer | Uint8Array): {pascal_name} {{
   const buf = Buffer.from(data);
@@ -180,6 +210,14 @@ export function parse{pascal_name}(data: Buffer | Uint8Array): {pascal_name} {{
 {parse_body}
 }}
 
+// Serialize component to account data (discriminator included)
+export function serialize{pascal_name}(value: {pascal_name}): Buffer {{
+  const data = Buffer.alloc({upper_name}_SIZE);
+  Buffer.from({upper_name}_DISCRIMINATOR).copy(data, 0);
+{serialize_body}
+  return data;
+}}
+
 // Fetch component from chain
 export async function fetch{pascal_name}(
   connection: Connection,
@@ -199,55 +237,59 @@ export async function fetch{pascal_name}ByEntity(
   const [pda] = derive{pascal_name}PDA(entity, programId);
   return fetch{pascal_name}(connection, pda);
 }}
-
+{field_readers}
 // Instruction builders
 {instruction_builders}
-"#,
+{events_ts}"#,
         upper_name = upper_name,
         pascal_name = pascal_name,
         program_id = program_id,
         seed = component.seed,
-        discriminator = discriminator,
-        size = size,
+        discriminator = component.discriminator,
+        size = component.size,
         interface_fields = interface_fields,
         parse_body = parse_body,
+        serialize_body = serialize_body,
+        field_readers = field_readers,
         instruction_builders = instruction_builders,
+        allowed_systems = allowed_systems,
+        events_ts = events_ts,
     )
 }
 
-fn generate_parse_body(fields: &[ParsedField]) -> String {
-    let mut lines = Vec::new();
-    let mut offset = 8usize; // Skip discriminator
+/// TypeScript type of a field; nested `ComponentField` types are raw bytes
+fn field_ts_type(field: &IdlField) -> String {
+    if is_raw_bytes(&field.ty) && field.max_len.is_none() {
+        return format!("Uint8Array /* {}, {} bytes */", field.ty, field.size);
+    }
+    parser::rust_type_to_ts(&field.ty)
+}
 
-    for field in fields {
-        if field.is_discriminator {
-            continue;
-        }
+/// TypeScript type of an instruction argument
+fn arg_ts_type(arg: &IdlArg) -> String {
+    match (arg.ty.as_str(), arg.size) {
+        // Instruction data carries exactly the bytes given, not an array
+        ("Vec<u8>", _) => "Uint8Array".to_string(),
+        (ty, Some(size)) if is_raw_bytes(ty) => format!("Uint8Array /* {}, {} bytes */", ty, size),
+        (ty, _) => parser::rust_type_to_ts(ty),
+    }
+}
 
-        let read_expr = match field.rust_type.as_str() {
-            "u8" => format!("readU8(buf, {})", offset),
-            "u16" => format!("readU16(buf, {})", offset),
-            "u32" => format!("readU32(buf, {})", offset),
-            "u64" => format!("readU64(buf, {})", offset),
-            "i64" => format!("readI64(buf, {})", offset),
-            "bool" => format!("readU8(buf, {}) !== 0", offset),
-            "Pubkey" | "[u8;32]" => format!("readPubkey(buf, {})", offset),
-            s if s.starts_with("[u8;") => {
-                format!("buf.subarray({}, {})", offset, offset + field.size)
-            }
-            _ => format!("buf.subarray({}, {}) /* {} */", offset, offset + field.size, field.rust_type),
-        };
+/// Whether a type has no TypeScript equivalent and is passed through as bytes
+fn is_raw_bytes(rust_type: &str) -> bool {
+    parser::rust_type_to_ts(rust_type) == "unknown"
+}
+
+fn generate_parse_body(fields: &[IdlField]) -> String {
+    let mut lines = Vec::new();
 
+    for field in fields {
+        let read_expr = field_read_expr(field, &field.offset.to_string());
         lines.push(format!("  const {} = {};", field.name, read_expr));
-        offset += field.size;
     }
 
     // Return statement
-    let field_names: Vec<_> = fields
-        .iter()
-        .filter(|f| !f.is_discriminator)
-        .map(|f| format!("    {},", f.name))
-        .collect();
+    let field_names: Vec<_> = fields.iter().map(|f| format!("    {},", f.name)).collect();
 
     lines.push(String::new());
     lines.push("  return {".to_string());
@@ -257,19 +299,201 @@ fn generate_parse_body(fields: &[ParsedField]) -> String {
     lines.join("\n")
 }
 
+/// Expression reading `field` from `buf`, with its region starting at `offset`
+fn field_read_expr(field: &IdlField, offset: &str) -> String {
+    if let Some(bits) = field.bits {
+        let read = format!(
+            "readBits(buf, {}, {}, {}, {})",
+            offset, field.size, bits.shift, bits.width
+        );
+        return match parser::rust_type_to_ts(&field.ty).as_str() {
+            "boolean" => format!("{} !== 0n", read),
+            "number" => format!("Number({})", read),
+            _ => read,
+        };
+    }
+    match field.max_len {
+        Some(max_len) => match parser::vec_elem(&field.ty) {
+            Some(elem) => {
+                let elem_size = parser::var_len_elem_size(&field.ty);
+                format!(
+                    "readVec(buf, {}, {}, {}, (o) => {})",
+                    offset,
+                    max_len,
+                    elem_size,
+                    read_expr(elem, "o", elem_size),
+                )
+            }
+            None => format!("readString(buf, {}, {})", offset, max_len),
+        },
+        None => read_expr(&field.ty, offset, field.size),
+    }
+}
+
+/// `{NAME}_LAYOUT` plus a `read{Name}{Field}` / `fetch{Name}{Field}` pair per field
+///
+/// `fetch` asks the RPC for the field's bytes only (`dataSlice`), so it skips
+/// the discriminator check that `read` and `parse{Name}` do.
+fn generate_field_readers(pascal_name: &str, upper_name: &str, fields: &[IdlField]) -> String {
+    let layout = fields
+        .iter()
+        .map(|f| match f.bits {
+            Some(bits) => format!(
+                "  {}: {{ offset: {}, size: {}, shift: {}, width: {} }},",
+                f.name, f.offset, f.size, bits.shift, bits.width
+            ),
+            None => format!("  {}: {{ offset: {}, size: {} }},", f.name, f.offset, f.size),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let readers = fields
+        .iter()
+        .map(|f| {
+            let field_name = f.name.to_upper_camel_case();
+            let ts_type = field_ts_type(f);
+            format!(
+                r#"export function read{pascal_name}{field_name}(data: Buffer | Uint8Array): {ts_type} {{
+  const buf = Buffer.from(data);
+  if (!checkDiscriminator(buf, {upper_name}_DISCRIMINATOR)) {{
+    throw new Error('Invalid discriminator for {pascal_name}');
+  }}
+  return {read};
+}}
+
+export async function fetch{pascal_name}{field_name}(
+  connection: Connection,
+  address: PublicKey
+): Promise<{ts_type} | null> {{
+  const account = await connection.getAccountInfo(address, {{
+    dataSlice: {{ offset: {offset}, length: {size} }},
+  }});
+  if (!account) return null;
+  const buf = Buffer.from(account.data);
+  return {sliced_read};
+}}"#,
+                offset = f.offset,
+                size = f.size,
+                read = field_read_expr(f, &f.offset.to_string()),
+                sliced_read = field_read_expr(f, "0"),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    format!(
+        r#"
+// Field offsets and sizes in the account data; bit fields also give their
+// bits within the word
+export const {upper_name}_LAYOUT = {{
+{layout}
+}} as const;
+
+// Single-field reads, without parsing the whole account
+{readers}
+"#
+    )
+}
+
+/// Body of `serialize{Name}`, writing every field of `value` into `data`
+fn generate_serialize_body(fields: &[IdlField]) -> String {
+    let mut lines = Vec::new();
+
+    for field in fields {
+        let offset = field.offset;
+        let value = format!("value.{}", field.name);
+        // Bit fields share their word, which writeBits updates in place
+        if let Some(bits) = field.bits {
+            lines.push(format!(
+                "  writeBits(data, {}, {}, {}, {}, {});",
+                offset, field.size, bits.shift, bits.width, value
+            ));
+            continue;
+        }
+        let write_stmt = match field.max_len {
+            Some(max_len) => match parser::vec_elem(&field.ty) {
+                Some(elem) => format!(
+                    "writeVec(data, {}, {}, {}, {}, (o, v) => {});",
+                    offset,
+                    max_len,
+                    parser::var_len_elem_size(&field.ty),
+                    value,
+                    write_expr(elem, "o", "v"),
+                ),
+                None => format!("writeString(data, {}, {}, {});", offset, max_len, value),
+            },
+            // Nested ComponentField types are passed through as raw bytes
+            None if is_raw_bytes(&field.ty) => {
+                format!("writeBytes(data, {}, {});", offset, value)
+            }
+            None => format!("{};", write_expr(&field.ty, &offset.to_string(), &value)),
+        };
+
+        lines.push(format!("  {}", write_stmt));
+    }
+
+    lines.join("\n")
+}
+
+/// Expression reading a fixed-size value of `rust_type` from `buf` at `offset`
+fn read_expr(rust_type: &str, offset: &str, size: usize) -> String {
+    match rust_type {
+        "u8" => format!("readU8(buf, {})", offset),
+        "u16" => format!("readU16(buf, {})", offset),
+        "u32" => format!("readU32(buf, {})", offset),
+        "u64" => format!("readU64(buf, {})", offset),
+        "i8" => format!("readI8(buf, {})", offset),
+        "i16" => format!("readI16(buf, {})", offset),
+        "i32" => format!("readI32(buf, {})", offset),
+        "i64" => format!("readI64(buf, {})", offset),
+        "u128" => format!("readU128(buf, {})", offset),
+        "i128" => format!("readI128(buf, {})", offset),
+        "f32" => format!("readF32(buf, {})", offset),
+        "f64" => format!("readF64(buf, {})", offset),
+        "bool" => format!("readU8(buf, {}) !== 0", offset),
+        "Pubkey" | "[u8;32]" => format!("readPubkey(buf, {})", offset),
+        s if parser::is_fixed(s) => format!("readFixed(buf, {})", offset),
+        s if s.starts_with("[u8;") => format!("buf.subarray({}, {} + {})", offset, offset, size),
+        _ => format!("buf.subarray({}, {} + {}) /* {} */", offset, offset, size, rust_type),
+    }
+}
+
+/// Expression writing `value` of `rust_type` into `data` at `offset`
+fn write_expr(rust_type: &str, offset: &str, value: &str) -> String {
+    match rust_type {
+        "u8" => format!("writeU8(data, {}, {})", offset, value),
+        "u16" => format!("writeU16(data, {}, {})", offset, value),
+        "u32" => format!("writeU32(data, {}, {})", offset, value),
+        "u64" => format!("writeU64(data, {}, {})", offset, value),
+        "i8" => format!("writeI8(data, {}, {})", offset, value),
+        "i16" => format!("writeI16(data, {}, {})", offset, value),
+        "i32" => format!("writeI32(data, {}, {})", offset, value),
+        "i64" => format!("writeI64(data, {}, {})", offset, value),
+        "u128" => format!("writeU128(data, {}, {})", offset, value),
+        "i128" => format!("writeI128(data, {}, {})", offset, value),
+        "f32" => format!("writeF32(data, {}, {})", offset, value),
+        "f64" => format!("writeF64(data, {}, {})", offset, value),
+        "bool" => format!("writeBool(data, {}, {})", offset, value),
+        "Pubkey" | "[u8;32]" => format!("writePubkey(data, {}, {})", offset, value),
+        s if parser::is_fixed(s) => format!("writeFixed(data, {}, {})", offset, value),
+        s if s.starts_with("[u8;") => format!("writeBytes(data, {}, {})", offset, value),
+        _ => format!("void 0 /* TODO: write {} ({}) at {} */", value, rust_type, offset),
+    }
+}
+
 fn generate_instruction_builder(
     pascal_name: &str,
     upper_name: &str,
-    instruction: &ParsedInstruction,
+    instruction: &IdlInstruction,
 ) -> String {
-    let fn_name = instruction.name.to_lower_camel_case();
+    let fn_name = instruction.name.to_upper_camel_case();
     let tag = instruction.tag;
 
     // Generate parameters
     let params: Vec<String> = instruction
-        .params
+        .args
         .iter()
-        .map(|p| format!("{}: {}", p.name, p.ts_type))
+        .map(|a| format!("{}: {}", a.name, arg_ts_type(a)))
         .collect();
 
     // Generate account params
@@ -291,8 +515,7 @@ fn generate_instruction_builder(
     };
 
     // Generate data buffer
-    let data_size = 1 + instruction.params.iter().map(|p| param_size(&p.rust_type)).sum::<usize>();
-    let data_writes = generate_data_writes(&instruction.params);
+    let (encodes, data_size, data_writes) = generate_data_writes(&instruction.args);
 
     // Generate keys array
     let keys: Vec<String> = instruction
@@ -301,7 +524,7 @@ fn generate_instruction_builder(
         .map(|a| {
             format!(
                 "    {{ pubkey: {}, isSigner: {}, isWritable: {} }}",
-                a.name, a.is_signer, a.is_writable
+                a.name, a.signer, a.writable
             )
         })
         .collect();
@@ -315,7 +538,7 @@ fn generate_instruction_builder(
         r#"export function create{pascal_name}{fn_name}Instruction(
   {params_str}
 ): TransactionInstruction {{
-  const data = Buffer.alloc({data_size});
+{encodes}  const data = Buffer.alloc({data_size});
   data.writeUInt8({tag}, 0);
 {data_writes}
   return new TransactionInstruction({{
@@ -327,6 +550,7 @@ fn generate_instruction_builder(
         pascal_name = pascal_name,
         fn_name = fn_name,
         params_str = params_str,
+        encodes = encodes,
         data_size = data_size,
         tag = tag,
         data_writes = data_writes,
@@ -334,60 +558,60 @@ fn generate_instruction_builder(
     )
 }
 
-fn generate_data_writes(params: &[crate::parser::ParsedParam]) -> String {
+/// Statements encoding the variable-length parameters, the data size
+/// expression, and the writes of every parameter after the tag byte
+///
+/// `Vec<u8>` and `String` parameters are a u32 length followed by the bytes,
+/// so every offset after one depends on its length.
+fn generate_data_writes(args: &[IdlArg]) -> (String, String, String) {
+    let mut encodes = String::new();
     let mut lines = Vec::new();
-    let mut offset = 1usize; // Skip tag byte
-
-    for param in params {
-        let write_expr = match param.rust_type.as_str() {
-            "u8" => format!("  data.writeUInt8({}, {});", param.name, offset),
-            "u16" => format!("  data.writeUInt16LE({}, {});", param.name, offset),
-            "u32" => format!("  data.writeUInt32LE({}, {});", param.name, offset),
-            "u64" => format!("  data.writeBigUInt64LE(BigInt({}), {});", param.name, offset),
-            "i64" => format!("  data.writeBigInt64LE(BigInt({}), {});", param.name, offset),
-            "bool" => format!("  data.writeUInt8({} ? 1 : 0, {});", param.name, offset),
-            "Pubkey" | "[u8;32]" => format!("  {}.toBuffer().copy(data, {});", param.name, offset),
-            _ => format!("  // TODO: write {} at offset {}", param.name, offset),
-        };
-        lines.push(write_expr);
-        offset += param_size(&param.rust_type);
-    }
+    let mut fixed = 1usize; // Skip tag byte
+    let mut lengths: Vec<String> = Vec::new();
 
-    lines.join("\n")
-}
+    for arg in args {
+        let offset = std::iter::once(fixed.to_string())
+            .chain(lengths.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" + ");
+
+        let Some(size) = arg.size else {
+            let bytes = format!("{}Bytes", arg.name);
+            encodes.push_str(&format!("  const {} = encodeBytes({});\n", bytes, arg.name));
+            lines.push(format!("  writeLenPrefixed(data, {}, {});", offset, bytes));
+            fixed += 4;
+            lengths.push(format!("{}.length", bytes));
+            continue;
+        };
 
-fn param_size(rust_type: &str) -> usize {
-    match rust_type {
-        "u8" | "i8" | "bool" => 1,
-        "u16" | "i16" => 2,
-        "u32" | "i32" => 4,
-        "u64" | "i64" => 8,
-        "u128" | "i128" => 16,
-        "Pubkey" | "[u8;32]" => 32,
-        _ => 0,
+        let write = if is_raw_bytes(&arg.ty) {
+            format!("writeBytes(data, {}, {})", offset, arg.name)
+        } else {
+            write_expr(&arg.ty, &offset, &arg.name)
+        };
+        lines.push(format!("  {};", write));
+        fixed += size;
     }
-}
 
-fn seed_to_discriminator(seed: &str) -> Vec<u8> {
-    let mut disc = seed.as_bytes().to_vec();
-    disc.resize(8, 0);
-    disc
+    let data_size = std::iter::once(fixed.to_string())
+        .chain(lengths)
+        .collect::<Vec<_>>()
+        .join(" + ");
+    (encodes, data_size, lines.join("\n"))
 }
 
-fn generate_system_ts(
-    system: &crate::config::SystemConfig,
-    instructions: &[ParsedInstruction],
-) -> String {
-    let pascal_name = system.name.to_upper_camel_case();
-    let upper_name = system.name.to_uppercase();
+fn generate_system_ts(program: &Idl) -> String {
+    let pascal_name = program.name.to_upper_camel_case();
+    let upper_name = program.name.to_uppercase();
 
-    let program_id = system
+    let program_id = program
         .program_id
         .as_deref()
         .unwrap_or("11111111111111111111111111111111");
 
     // Generate instruction builders
-    let instruction_builders = instructions
+    let instruction_builders = program
+        .instructions
         .iter()
         .map(|i| generate_instruction_builder(&pascal_name, &upper_name, i))
         .collect::<Vec<_>>()
@@ -399,6 +623,8 @@ fn generate_system_ts(
         instruction_builders
     };
 
+    let events_ts = generate_events_ts(&pascal_name, &upper_name, &program.events);
+
     format!(
         r#"// Auto-generated by Golt - DO NOT EDIT
 import {{
@@ -406,17 +632,161 @@ import {{
   TransactionInstruction,
   SystemProgram,
 }} from '@solana/web3.js';
-import {{ writeU8, writeU16, writeU32, writeU64, writePubkey }} from './common';
+import {{
+  readU8, readU16, readU32, readU64, readI8, readI16, readI32, readI64, readU128, readI128, readF32, readF64,
+  readPubkey, readFixed, readString, readVec,
+  writeU8, writeU16, writeU32, writeU64, writeI8, writeI16, writeI32, writeI64, writeU128, writeI128, writeF32, writeF64,
+  writeBool, writePubkey, writeFixed, writeBytes, encodeBytes, writeLenPrefixed,
+  checkDiscriminator, getProgramDataLogs,
+}} from './common';
 
 // Program ID
 export const {upper_name}_PROGRAM_ID = new PublicKey('{program_id}');
 
 // Instruction builders
 {instruction_builders}
-"#,
+{events_ts}"#,
         upper_name = upper_name,
         program_id = program_id,
         instruction_builders = instruction_builders,
+        events_ts = events_ts,
+    )
+}
+
+/// Interfaces, decoders and a log parser for a program's `#[derive(Event)]` structs
+fn generate_events_ts(pascal_name: &str, upper_name: &str, events: &[IdlEvent]) -> String {
+    if events.is_empty() {
+        return String::new();
+    }
+
+    let mut sections = Vec::new();
+    let mut variants = Vec::new();
+    let mut matchers = Vec::new();
+
+    for event in events {
+        let name = &event.name;
+        let event_upper = event.name.to_shouty_snake_case();
+        let var = event.name.to_lower_camel_case();
+        let size = event.size;
+
+        let interface_fields = event
+            .fields
+            .iter()
+            .map(|f| format!("  {}: {};", f.name, field_ts_type(f)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        sections.push(format!(
+            r#"export const {event_upper}_EVENT_DISCRIMINATOR = new Uint8Array({discriminator:?});
+export const {event_upper}_EVENT_SIZE = {size};
+
+export interface {name}Event {{
+{interface_fields}
+}}
+
+// Decode {name} event data, or null if it is a different event
+export function decode{name}Event(data: Buffer | Uint8Array): {name}Event | null {{
+  const buf = Buffer.from(data);
+  if (buf.length < {event_upper}_EVENT_SIZE || !checkDiscriminator(buf, {event_upper}_EVENT_DISCRIMINATOR)) {{
+    return null;
+  }}
+
+{parse_body}
+}}"#,
+            discriminator = event.discriminator,
+            parse_body = generate_parse_body(&event.fields),
+        ));
+
+        variants.push(format!("  | {{ name: '{name}'; data: {name}Event }}"));
+        matchers.push(format!(
+            r#"    const {var} = decode{name}Event(data);
+    if ({var}) {{
+      events.push({{ name: '{name}', data: {var} }});
+      continue;
+    }}"#
+        ));
+    }
+
+    format!(
+        r#"
+// Events
+{sections}
+
+export type {pascal_name}Event =
+{variants};
+
+// Decode this program's events from transaction logs
+export function parse{pascal_name}Events(
+  logs: string[],
+  programId: PublicKey = {upper_name}_PROGRAM_ID
+): {pascal_name}Event[] {{
+  const events: {pascal_name}Event[] = [];
+  for (const data of getProgramDataLogs(logs, programId)) {{
+{matchers}
+  }}
+  return events;
+}}
+"#,
+        sections = sections.join("\n\n"),
+        variants = variants.join("\n"),
+        matchers = matchers.join("\n"),
+    )
+}
+
+fn generate_errors_ts(tables: &[ErrorTable]) -> String {
+    let entries = tables
+        .iter()
+        .flat_map(|table| {
+            table.errors.iter().map(move |e| {
+                format!(
+                    "  {code}: {{ code: {code}, name: '{name}', message: {message}, program: '{program}' }},",
+                    code = e.code,
+                    name = e.name,
+                    message = serde_json::to_string(&e.message).unwrap_or_default(),
+                    program = table.program,
+                )
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"// Auto-generated by Golt - DO NOT EDIT
+
+export interface GoltErrorInfo {{
+  code: number;
+  name: string;
+  message: string;
+  program: string;
+}}
+
+// Known error codes: golt-runtime, golt-registry and this project's programs
+export const GOLT_ERRORS: Record<number, GoltErrorInfo> = {{
+{entries}
+}};
+
+// Look up a custom program error code
+export function decodeGoltError(code: number): GoltErrorInfo | undefined {{
+  return GOLT_ERRORS[code];
+}}
+
+// Extract the custom error code from a transaction error or its message
+export function getCustomErrorCode(err: unknown): number | undefined {{
+  const instructionError = (err as any)?.InstructionError;
+  if (Array.isArray(instructionError) && typeof instructionError[1]?.Custom === 'number') {{
+    return instructionError[1].Custom;
+  }}
+  const text = err instanceof Error ? err.message : String(err);
+  const match = text.match(/custom program error: (0x[0-9a-fA-F]+|\d+)/);
+  return match ? Number(match[1]) : undefined;
+}}
+
+// Decode the error of a failed transaction, if it is a known Golt error
+export function parseGoltError(err: unknown): GoltErrorInfo | undefined {{
+  const code = getCustomErrorCode(err);
+  return code === undefined ? undefined : decodeGoltError(code);
+}}
+"#
     )
 }
 
@@ -458,6 +828,22 @@ export function readI64(data: Buffer, offset: number): bigint {
   return data.readBigInt64LE(offset);
 }
 
+export function readU128(data: Buffer, offset: number): bigint {
+  return data.readBigUInt64LE(offset) | (data.readBigUInt64LE(offset + 8) << 64n);
+}
+
+export function readI128(data: Buffer, offset: number): bigint {
+  return data.readBigUInt64LE(offset) | (data.readBigInt64LE(offset + 8) << 64n);
+}
+
+export function readF32(data: Buffer, offset: number): number {
+  return data.readFloatLE(offset);
+}
+
+export function readF64(data: Buffer, offset: number): number {
+  return data.readDoubleLE(offset);
+}
+
 export function readBool(data: Buffer, offset: number): boolean {
   return data.readUInt8(offset) !== 0;
 }
@@ -504,6 +890,24 @@ export function writeI64(data: Buffer, offset: number, value: bigint | number):
   data.writeBigInt64LE(BigInt(value), offset);
 }
 
+export function writeU128(data: Buffer, offset: number, value: bigint | number): void {
+  const v = BigInt.asUintN(128, BigInt(value));
+  data.writeBigUInt64LE(v & 0xffffffffffffffffn, offset);
+  data.writeBigUInt64LE(v >> 64n, offset + 8);
+}
+
+export function writeI128(data: Buffer, offset: number, value: bigint | number): void {
+  writeU128(data, offset, BigInt.asUintN(128, BigInt(value)));
+}
+
+export function writeF32(data: Buffer, offset: number, value: number): void {
+  data.writeFloatLE(value, offset);
+}
+
+export function writeF64(data: Buffer, offset: number, value: number): void {
+  data.writeDoubleLE(value, offset);
+}
+
 export function writeBool(data: Buffer, offset: number, value: boolean): void {
   data.writeUInt8(value ? 1 : 0, offset);
 }
@@ -516,6 +920,154 @@ export function writeBytes(data: Buffer, offset: number, value: Uint8Array): voi
   Buffer.from(value).copy(data, offset);
 }
 
+// Variable-length fields (`#[max_len(N)]`): a u32 length prefix followed by
+// room for N elements, unused slots zeroed
+
+export function readString(data: Buffer, offset: number, maxLen: number): string {
+  const len = data.readUInt32LE(offset);
+  if (len > maxLen) throw new Error(`String length ${len} exceeds capacity ${maxLen}`);
+  return data.toString('utf8', offset + 4, offset + 4 + len);
+}
+
+export function writeString(data: Buffer, offset: number, maxLen: number, value: string): void {
+  const bytes = Buffer.from(value, 'utf8');
+  if (bytes.length > maxLen) throw new Error(`String length ${bytes.length} exceeds capacity ${maxLen}`);
+  data.writeUInt32LE(bytes.length, offset);
+  data.fill(0, offset + 4, offset + 4 + maxLen);
+  bytes.copy(data, offset + 4);
+}
+
+export function readVec<T>(
+  data: Buffer,
+  offset: number,
+  maxLen: number,
+  elemSize: number,
+  read: (offset: number) => T
+): T[] {
+  const len = data.readUInt32LE(offset);
+  if (len > maxLen) throw new Error(`Vec length ${len} exceeds capacity ${maxLen}`);
+  return Array.from({ length: len }, (_, i) => read(offset + 4 + i * elemSize));
+}
+
+export function writeVec<T>(
+  data: Buffer,
+  offset: number,
+  maxLen: number,
+  elemSize: number,
+  items: T[],
+  write: (offset: number, item: T) => void
+): void {
+  if (items.length > maxLen) throw new Error(`Vec length ${items.length} exceeds capacity ${maxLen}`);
+  data.writeUInt32LE(items.length, offset);
+  data.fill(0, offset + 4, offset + 4 + maxLen * elemSize);
+  items.forEach((item, i) => write(offset + 4 + i * elemSize, item));
+}
+
+// Bit fields (`#[bits(N)]`, `#[flags]`): `width` bits at `shift` of a
+// little-endian word of `size` bytes shared with neighbouring bit fields
+
+export function readBits(data: Buffer, offset: number, size: number, shift: number, width: number): bigint {
+  let word = 0n;
+  for (let i = size - 1; i >= 0; i--) word = (word << 8n) | BigInt(data[offset + i]);
+  return (word >> BigInt(shift)) & ((1n << BigInt(width)) - 1n);
+}
+
+export function writeBits(
+  data: Buffer,
+  offset: number,
+  size: number,
+  shift: number,
+  width: number,
+  value: bigint | number | boolean
+): void {
+  const mask = ((1n << BigInt(width)) - 1n) << BigInt(shift);
+  let word = 0n;
+  for (let i = size - 1; i >= 0; i--) word = (word << 8n) | BigInt(data[offset + i]);
+  word = (word & ~mask) | ((BigInt(value) << BigInt(shift)) & mask);
+  for (let i = 0; i < size; i++) {
+    data[offset + i] = Number(word & 0xffn);
+    word >>= 8n;
+  }
+}
+
+// Variable-length instruction parameters (`Vec<u8>`, `String`): a u32 length
+// prefix followed by exactly that many bytes
+
+export function encodeBytes(value: Uint8Array | string): Buffer {
+  return typeof value === 'string' ? Buffer.from(value, 'utf8') : Buffer.from(value);
+}
+
+export function writeLenPrefixed(data: Buffer, offset: number, bytes: Uint8Array): void {
+  data.writeUInt32LE(bytes.length, offset);
+  Buffer.from(bytes).copy(data, offset + 4);
+}
+
+// Q32.32 fixed-point (golt_runtime::Fixed), as a JS number
+// Use readI64 / writeI64 for the exact raw bits
+
+export const FIXED_ONE = 4294967296; // 2^32
+
+export function readFixed(data: Buffer, offset: number): number {
+  return Number(data.readBigInt64LE(offset)) / FIXED_ONE;
+}
+
+export function writeFixed(data: Buffer, offset: number, value: number): void {
+  data.writeBigInt64LE(BigInt(Math.round(value * FIXED_ONE)), offset);
+}
+
+// System allowlists
+
+export const ALLOWLIST_SEED = 'allowlist';
+export const SYSTEM_SIGNER_SEED = 'system_signer';
+
+// Allowlist PDA of a component program
+export function deriveAllowlistPDA(programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from(ALLOWLIST_SEED)], programId);
+}
+
+// ProgramData account of an upgradeable program; InitAllowlist requires its
+// upgrade authority as the admin
+export const BPF_LOADER_UPGRADEABLE_ID = new PublicKey('BPFLoaderUpgradeab1e11111111111111111111111');
+
+export function deriveProgramDataAddress(programId: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync([programId.toBuffer()], BPF_LOADER_UPGRADEABLE_ID)[0];
+}
+
+// PDA a system signs with when writing components
+export function deriveSystemSignerPDA(systemProgramId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from(SYSTEM_SIGNER_SEED)], systemProgramId);
+}
+
+// Event logs
+
+// Collect `Program data:` payloads from transaction logs, optionally only
+// those emitted directly by `programId` (not by programs it calls)
+export function getProgramDataLogs(logs: string[], programId?: PublicKey): Buffer[] {
+  const target = programId?.toBase58();
+  const stack: string[] = [];
+  const payloads: Buffer[] = [];
+
+  for (const log of logs) {
+    const invoke = log.match(/^Program (\w+) invoke \[\d+\]$/);
+    if (invoke) {
+      stack.push(invoke[1]);
+      continue;
+    }
+    if (/^Program \w+ (success|failed)/.test(log)) {
+      stack.pop();
+      continue;
+    }
+    if (log.startsWith('Program data: ')) {
+      if (target && stack[stack.length - 1] !== target) continue;
+      for (const chunk of log.slice('Program data: '.length).split(' ')) {
+        payloads.push(Buffer.from(chunk, 'base64'));
+      }
+    }
+  }
+
+  return payloads;
+}
+
 // Utility to check discriminator
 export function checkDiscriminator(
   data: Buffer | Uint8Array,
@@ -5,8 +5,9 @@ use std::fs;
 use std::path::Path;
 
 use crate::config::{GoltConfig, ProjectConfig};
+use crate::discriminator::DiscriminatorScheme;
 
-pub fn run(name: &str) -> Result<()> {
+pub fn run(name: &str, hashed_discriminators: bool) -> Result<()> {
     println!("Initializing new Golt project: {}", name);
 
     let project_dir = Path::new(name);
@@ -29,6 +30,11 @@ pub fn run(name: &str) -> Result<()> {
             components_dir: "programs/components".to_string(),
             systems_dir: "programs/systems".to_string(),
             keypairs_dir: "keypairs".to_string(),
+            discriminators: if hashed_discriminators {
+                DiscriminatorScheme::Hashed
+            } else {
+                DiscriminatorScheme::Padded
+            },
         },
         components: vec![],
         systems: vec![],
@@ -52,6 +58,7 @@ license = "MIT"
 pinocchio = "0.8"
 pinocchio-pubkey = "0.2"
 pinocchio-system = "0.2"
+ephemeral-rollups-pinocchio = "0.7"
 golt-runtime = {{ git = "https://github.com/gstohl/golt" }}
 golt-macros = {{ git = "https://github.com/gstohl/golt" }}
 
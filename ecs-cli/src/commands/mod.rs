@@ -4,8 +4,11 @@ pub mod init;
 pub mod new_component;
 pub mod new_system;
 pub mod generate_ts;
+pub mod generate_idl;
 pub mod generate_keypair;
 pub mod build;
 pub mod list;
 pub mod test;
 pub mod deploy;
+pub mod allowlist;
+pub mod error;
@@ -6,7 +6,7 @@ use std::fs;
 use std::path::Path;
 
 use crate::config::{ComponentConfig, GoltConfig};
-use crate::templates;
+use crate::{discriminator, templates};
 
 pub fn run(name: &str, seed: Option<&str>) -> Result<()> {
     let (mut config, project_root) = GoltConfig::find_config()?;
@@ -22,6 +22,17 @@ pub fn run(name: &str, seed: Option<&str>) -> Result<()> {
         anyhow::bail!("Component '{}' already exists", snake_name);
     }
 
+    let error_offset = config.next_error_offset();
+    config.components.push(ComponentConfig {
+        name: snake_name.clone(),
+        seed: seed.to_string(),
+        program_id: None,
+        fields: vec![],
+        allowed_systems: vec![],
+        error_offset: Some(error_offset),
+    });
+    discriminator::check_collisions(&config, &project_root)?;
+
     // Create component directory
     let component_dir = project_root
         .join(&config.project.components_dir)
@@ -57,17 +68,19 @@ pub fn run(name: &str, seed: Option<&str>) -> Result<()> {
     let entrypoint_rs = templates::component_entrypoint_rs();
     fs::write(component_dir.join("src/entrypoint.rs"), entrypoint_rs)?;
 
-    // Generate src/error.rs
-    let error_rs = templates::component_error_rs(&pascal_name);
+    // Generate src/error.rs with the next free error code range
+    let error_rs = templates::component_error_rs(&pascal_name, error_offset);
     fs::write(component_dir.join("src/error.rs"), error_rs)?;
 
-    // Add to config
-    config.components.push(ComponentConfig {
-        name: snake_name.clone(),
-        seed: seed.to_string(),
-        program_id: None,
-        fields: vec![],
-    });
+    // Generate a cargo-fuzz target for the processor
+    fs::create_dir_all(component_dir.join("fuzz/fuzz_targets"))?;
+    let fuzz_toml = templates::component_fuzz_cargo_toml(&snake_name);
+    fs::write(component_dir.join("fuzz/Cargo.toml"), fuzz_toml)?;
+    let fuzz_target = templates::component_fuzz_target_rs(&snake_name, &pascal_name);
+    fs::write(component_dir.join("fuzz/fuzz_targets/process_instruction.rs"), fuzz_target)?;
+    fs::write(component_dir.join("fuzz/.gitignore"), templates::component_fuzz_gitignore())?;
+
+    // Save the config with the new component
     config.save(&project_root.join("golt.toml"))?;
 
     // Update workspace Cargo.toml
@@ -83,6 +96,7 @@ pub fn run(name: &str, seed: Option<&str>) -> Result<()> {
     println!("  2. Edit {}/src/instruction.rs to define instructions", component_dir.display());
     println!("  3. Run `golt generate keypair {}` to generate a keypair", snake_name);
     println!("  4. Run `golt build` to build");
+    println!("  5. Run `cargo fuzz run process_instruction` in {}/fuzz to fuzz the processor", component_dir.display());
 
     Ok(())
 }
@@ -155,11 +169,10 @@ fn update_core_lib(project_root: &Path, config: &GoltConfig) -> Result<()> {
             upper, comp.seed
         ));
 
-        // Create 8-byte discriminator padded with zeros
-        let mut disc_bytes = [0u8; 8];
-        let seed_bytes = comp.seed.as_bytes();
-        let len = seed_bytes.len().min(8);
-        disc_bytes[..len].copy_from_slice(&seed_bytes[..len]);
+        let disc_bytes = config
+            .project
+            .discriminators
+            .discriminator("component", &comp.seed);
 
         discriminators.push_str(&format!(
             "    pub const {}: [u8; 8] = {:?};\n",
@@ -51,14 +51,16 @@ pub fn run(name: &str) -> Result<()> {
     let entrypoint_rs = templates::component_entrypoint_rs(); // Same as component
     fs::write(system_dir.join("src/entrypoint.rs"), entrypoint_rs)?;
 
-    // Generate src/error.rs
-    let error_rs = templates::system_error_rs(&pascal_name);
+    // Generate src/error.rs with the next free error code range
+    let error_offset = config.next_error_offset();
+    let error_rs = templates::system_error_rs(&pascal_name, error_offset);
     fs::write(system_dir.join("src/error.rs"), error_rs)?;
 
     // Add to config
     config.systems.push(SystemConfig {
         name: snake_name.clone(),
         program_id: None,
+        error_offset: Some(error_offset),
     });
     config.save(&project_root.join("golt.toml"))?;
 
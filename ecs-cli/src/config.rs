@@ -3,6 +3,9 @@
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use anyhow::{Context, Result};
+use golt_runtime::{PROGRAM_ERROR_OFFSET, PROGRAM_ERROR_RANGE};
+
+use crate::discriminator::DiscriminatorScheme;
 
 /// Golt project configuration (golt.toml)
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,6 +28,9 @@ pub struct ProjectConfig {
     pub systems_dir: String,
     #[serde(default)]
     pub keypairs_dir: String,
+    /// How component and event discriminators are derived
+    #[serde(default, skip_serializing_if = "DiscriminatorScheme::is_padded")]
+    pub discriminators: DiscriminatorScheme,
 }
 
 fn default_version() -> String {
@@ -39,6 +45,12 @@ pub struct ComponentConfig {
     pub program_id: Option<String>,
     #[serde(default)]
     pub fields: Vec<FieldConfig>,
+    /// Systems allowed to write this component (by name)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_systems: Vec<String>,
+    /// First code of this component's error enum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_offset: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -54,6 +66,9 @@ pub struct SystemConfig {
     pub name: String,
     #[serde(default)]
     pub program_id: Option<String>,
+    /// First code of this system's error enum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_offset: Option<u32>,
 }
 
 impl GoltConfig {
@@ -82,6 +97,16 @@ impl GoltConfig {
             }
         }
     }
+
+    /// Next free error code range for a new component or system
+    pub fn next_error_offset(&self) -> u32 {
+        self.components
+            .iter()
+            .filter_map(|c| c.error_offset)
+            .chain(self.systems.iter().filter_map(|s| s.error_offset))
+            .max()
+            .map_or(PROGRAM_ERROR_OFFSET, |offset| offset + PROGRAM_ERROR_RANGE)
+    }
 }
 
 impl Default for GoltConfig {
@@ -93,6 +118,7 @@ impl Default for GoltConfig {
                 components_dir: "programs/components".to_string(),
                 systems_dir: "programs/systems".to_string(),
                 keypairs_dir: "keypairs".to_string(),
+                discriminators: DiscriminatorScheme::default(),
             },
             components: vec![],
             systems: vec![],
@@ -0,0 +1,152 @@
+//! Component and event discriminators, derived the same way as in `golt-macros`
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::config::{ComponentConfig, GoltConfig};
+use crate::parser::{self, ParsedComponent};
+
+/// How 8-byte discriminators are derived from names (`discriminators` in golt.toml)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscriminatorScheme {
+    /// The name padded with zeros or truncated to 8 bytes
+    #[default]
+    Padded,
+    /// First 8 bytes of `sha256("{namespace}:{name}")`, like `#[component(hashed)]`
+    Hashed,
+}
+
+impl DiscriminatorScheme {
+    pub fn is_padded(&self) -> bool {
+        *self == Self::Padded
+    }
+
+    /// Discriminator of `name`, e.g. `("component", seed)` or `("event", name)`
+    pub fn discriminator(self, namespace: &str, name: &str) -> [u8; 8] {
+        match self {
+            Self::Padded => golt_layout::padded_discriminator(name),
+            Self::Hashed => golt_layout::hashed_discriminator(namespace, name),
+        }
+    }
+}
+
+/// Discriminator of a component: from its `#[derive(Component)]` attributes
+/// if `parsed` has them, else from its seed with golt.toml's scheme
+pub fn component_discriminator(
+    config: &GoltConfig,
+    component: &ComponentConfig,
+    parsed: Option<&ParsedComponent>,
+) -> [u8; 8] {
+    parsed.and_then(|p| p.discriminator).unwrap_or_else(|| {
+        config
+            .project
+            .discriminators
+            .discriminator("component", &component.seed)
+    })
+}
+
+/// Pairs of components with the same discriminator
+///
+/// Components whose state.rs under `project_root` derives `Component` use
+/// the discriminator its attributes give.
+pub fn find_collisions<'a>(config: &'a GoltConfig, project_root: &Path) -> Vec<(&'a str, &'a str)> {
+    let discriminators: Vec<_> = config
+        .components
+        .iter()
+        .map(|c| {
+            let state_path = project_root
+                .join(&config.project.components_dir)
+                .join(&c.name)
+                .join("src/state.rs");
+            let parsed = parser::parse_component_state(&state_path).ok();
+            (c.name.as_str(), component_discriminator(config, c, parsed.as_ref()))
+        })
+        .collect();
+
+    let mut collisions = Vec::new();
+    for (i, (a, disc_a)) in discriminators.iter().enumerate() {
+        for (b, disc_b) in &discriminators[i + 1..] {
+            if disc_a == disc_b {
+                collisions.push((*a, *b));
+            }
+        }
+    }
+    collisions
+}
+
+/// Pairs of events of one program with the same discriminator, with the
+/// program's name
+///
+/// Event discriminators are hashed, so these events share a snake_case name
+/// or `#[event(discriminator = "...")]`.
+pub fn find_event_collisions<'a>(
+    config: &'a GoltConfig,
+    project_root: &Path,
+) -> Vec<(&'a str, String, String)> {
+    let components_dir = project_root.join(&config.project.components_dir);
+    let systems_dir = project_root.join(&config.project.systems_dir);
+    let programs = config
+        .components
+        .iter()
+        .map(|c| (c.name.as_str(), components_dir.join(&c.name)))
+        .chain(
+            config
+                .systems
+                .iter()
+                .map(|s| (s.name.as_str(), systems_dir.join(&s.name))),
+        );
+
+    let mut collisions = Vec::new();
+    for (program, dir) in programs {
+        let events = parser::parse_events(&dir.join("src")).unwrap_or_default();
+        for (i, a) in events.iter().enumerate() {
+            for b in &events[i + 1..] {
+                if a.discriminator == b.discriminator {
+                    collisions.push((program, a.name.clone(), b.name.clone()));
+                }
+            }
+        }
+    }
+    collisions
+}
+
+/// Fail if two components share a discriminator, since either could then be
+/// loaded as the other, or two events of one program do
+pub fn check_collisions(config: &GoltConfig, project_root: &Path) -> Result<()> {
+    let events = find_event_collisions(config, project_root);
+    if !events.is_empty() {
+        let pairs: Vec<_> = events
+            .iter()
+            .map(|(program, a, b)| format!("'{}' and '{}' in '{}'", a, b, program))
+            .collect();
+        anyhow::bail!(
+            "Event discriminators collide: {} (rename one, or give it \
+             `#[event(discriminator = \"...\")]`)",
+            pairs.join(", ")
+        );
+    }
+
+    let collisions = find_collisions(config, project_root);
+    if collisions.is_empty() {
+        return Ok(());
+    }
+
+    let pairs: Vec<_> = collisions
+        .iter()
+        .map(|(a, b)| format!("'{}' and '{}'", a, b))
+        .collect();
+    let hint = match config.project.discriminators {
+        DiscriminatorScheme::Padded => {
+            " (seeds sharing their first 8 bytes collide; set `discriminators = \"hashed\"` \
+             under [project] in golt.toml, and `#[component(hashed)]` on derived components)"
+        }
+        DiscriminatorScheme::Hashed => "",
+    };
+    anyhow::bail!(
+        "Component discriminators collide: {}{}",
+        pairs.join(", "),
+        hint
+    )
+}
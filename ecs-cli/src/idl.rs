@@ -0,0 +1,350 @@
+//! Program IDL: a JSON description of each component and system
+//!
+//! Built from the program sources with the same layout rules as
+//! `golt-macros`: an 8-byte discriminator (plus a version byte for versioned
+//! components), then the fields in order. A field starts right after the
+//! previous one unless `#[padding(N)]` skips bytes first or `#[offset(N)]`
+//! places it; consecutive `#[bits(N)]` / `#[flags]` fields share one
+//! little-endian word, recorded as `bits` on each field. Nested
+//! `ComponentField` types take their packed size, `#[max_len(N)]` fields take
+//! a `u32` length plus room for `N` elements, and `#[component(reserved = N)]`
+//! adds free bytes to the account size. Every field carries its `offset`, so
+//! readers never need to repeat these rules. The code generators work from
+//! this description rather than the parser's output.
+
+use heck::ToUpperCamelCase;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::{ComponentConfig, GoltConfig, SystemConfig};
+use crate::discriminator::component_discriminator;
+use crate::parser::{self, ParsedInstruction, PlacedField};
+
+/// Description of one program
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Idl {
+    pub name: String,
+    pub version: String,
+    pub kind: ProgramKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program_id: Option<String>,
+    /// The component's account layout, for component programs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<IdlComponent>,
+    pub instructions: Vec<IdlInstruction>,
+    pub events: Vec<IdlEvent>,
+    /// `#[error_offset(N)]` of the program's error enum: its first code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_offset: Option<u32>,
+    pub errors: Vec<IdlError>,
+    /// Packed sizes of the `#[derive(ComponentField)]` types used above
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<IdlType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgramKind {
+    Component,
+    System,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlComponent {
+    /// Rust struct name
+    pub name: String,
+    pub seed: String,
+    pub discriminator: [u8; 8],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u8>,
+    /// Account size in bytes, including the discriminator
+    pub size: usize,
+    /// Empty if state.rs couldn't be parsed
+    pub fields: Vec<IdlField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlField {
+    pub name: String,
+    /// Rust type, e.g. `u64`, `[u8;32]` or `Vec<u16>`
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// Byte offset from the start of the account or event data
+    pub offset: usize,
+    pub size: usize,
+    /// Capacity of a `#[max_len(N)]` `Vec<T>` / `String` field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<usize>,
+    /// Whether the field holds the PDA bump
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub bump: bool,
+    /// Bits of the `size`-byte little-endian word holding a bit field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bits: Option<IdlBits>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct IdlBits {
+    /// Bits below the field
+    pub shift: u32,
+    pub width: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlInstruction {
+    pub name: String,
+    pub tag: u8,
+    pub args: Vec<IdlArg>,
+    pub accounts: Vec<IdlAccount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlArg {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// Encoded size; `None` for length-prefixed `Vec<u8>` / `String` arguments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlAccount {
+    pub name: String,
+    pub signer: bool,
+    pub writable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlEvent {
+    pub name: String,
+    pub discriminator: [u8; 8],
+    /// Event data size in bytes, including the discriminator
+    pub size: usize,
+    pub fields: Vec<IdlField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlError {
+    pub name: String,
+    pub code: u32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlType {
+    pub name: String,
+    pub size: usize,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// IDLs of every component and system in the project
+pub fn load_project(config: &GoltConfig, project_root: &Path) -> Vec<Idl> {
+    let components = config.components.iter().map(|component| {
+        let dir = project_root
+            .join(&config.project.components_dir)
+            .join(&component.name);
+        load_component(config, component, &dir)
+    });
+    let systems = config.systems.iter().map(|system| {
+        let dir = project_root
+            .join(&config.project.systems_dir)
+            .join(&system.name);
+        load_system(config, system, &dir)
+    });
+    components.chain(systems).collect()
+}
+
+fn load_component(config: &GoltConfig, component: &ComponentConfig, dir: &Path) -> Idl {
+    let src = dir.join("src");
+    let field_types = parser::parse_field_types(&src).unwrap_or_default();
+    let (error_offset, errors) = load_errors(&src);
+
+    let state_path = src.join("state.rs");
+    let parsed = match parser::parse_component_state(&state_path) {
+        Ok(mut parsed) => {
+            parser::resolve_field_types(&mut parsed.fields, &field_types);
+            Some(parsed)
+        }
+        Err(e) => {
+            eprintln!("Warning: no layout for component '{}': {:#}", component.name, e);
+            None
+        }
+    };
+
+    let version = parsed.as_ref().and_then(|p| p.version);
+    let fields = parsed.as_ref().map_or_else(Vec::new, |p| layout_fields(p.layout()));
+    let size = parsed
+        .as_ref()
+        .map_or(golt_layout::DISCRIMINATOR_SIZE, |p| p.size());
+    let discriminator = component_discriminator(config, component, parsed.as_ref());
+    let layout = IdlComponent {
+        name: parsed.map_or_else(|| component.name.to_upper_camel_case(), |p| p.name),
+        seed: component.seed.clone(),
+        discriminator,
+        version,
+        size,
+        fields,
+    };
+
+    Idl {
+        name: component.name.clone(),
+        version: config.project.version.clone(),
+        kind: ProgramKind::Component,
+        program_id: component.program_id.clone(),
+        component: Some(layout),
+        instructions: load_instructions(&src, &field_types),
+        events: load_events(&src, &field_types),
+        error_offset,
+        errors,
+        types: idl_types(&field_types),
+    }
+}
+
+fn load_system(config: &GoltConfig, system: &SystemConfig, dir: &Path) -> Idl {
+    let src = dir.join("src");
+    let field_types = parser::parse_field_types(&src).unwrap_or_default();
+    let (error_offset, errors) = load_errors(&src);
+
+    Idl {
+        name: system.name.clone(),
+        version: config.project.version.clone(),
+        kind: ProgramKind::System,
+        program_id: system.program_id.clone(),
+        component: None,
+        instructions: load_system_instructions(&src, &field_types),
+        events: load_events(&src, &field_types),
+        error_offset,
+        errors,
+        types: idl_types(&field_types),
+    }
+}
+
+/// IDL fields from `parser::field_offsets` / `ParsedComponent::layout`
+fn layout_fields(fields: Vec<PlacedField>) -> Vec<IdlField> {
+    fields
+        .into_iter()
+        .map(|p| IdlField {
+            name: p.field.name.clone(),
+            ty: p.field.rust_type.clone(),
+            offset: p.offset,
+            size: p.size,
+            max_len: p.field.max_len,
+            bump: p.field.is_bump,
+            bits: p.bits.map(|(shift, width)| IdlBits { shift, width }),
+        })
+        .collect()
+}
+
+fn load_instructions(src: &Path, field_types: &HashMap<String, usize>) -> Vec<IdlInstruction> {
+    let path = src.join("instruction.rs");
+    if !path.exists() {
+        return Vec::new();
+    }
+    let instructions = parser::parse_component_instructions(&path).unwrap_or_default();
+    idl_instructions(instructions, field_types)
+}
+
+/// The instruction enum's instructions, or the `#[system]` function's
+fn load_system_instructions(src: &Path, field_types: &HashMap<String, usize>) -> Vec<IdlInstruction> {
+    match parser::parse_system_fns(src) {
+        Ok(instructions) if !instructions.is_empty() => idl_instructions(instructions, field_types),
+        _ => load_instructions(src, field_types),
+    }
+}
+
+fn idl_instructions(
+    mut instructions: Vec<ParsedInstruction>,
+    field_types: &HashMap<String, usize>,
+) -> Vec<IdlInstruction> {
+    parser::resolve_param_types(&mut instructions, field_types);
+
+    instructions
+        .into_iter()
+        .map(|instruction| IdlInstruction {
+            name: instruction.name,
+            tag: instruction.tag,
+            args: instruction
+                .params
+                .into_iter()
+                .map(|p| IdlArg {
+                    size: (!p.is_var_len()).then_some(p.size),
+                    name: p.name,
+                    ty: p.rust_type,
+                })
+                .collect(),
+            accounts: instruction
+                .accounts
+                .into_iter()
+                .map(|a| IdlAccount {
+                    name: a.name,
+                    signer: a.is_signer,
+                    writable: a.is_writable,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn load_events(src: &Path, field_types: &HashMap<String, usize>) -> Vec<IdlEvent> {
+    let mut events = parser::parse_events(src).unwrap_or_default();
+    for event in &mut events {
+        parser::resolve_field_types(&mut event.fields, field_types);
+    }
+
+    events
+        .into_iter()
+        .map(|event| {
+            let start = golt_layout::DISCRIMINATOR_SIZE;
+            let fields = layout_fields(parser::field_offsets(&event.fields, start));
+            IdlEvent {
+                name: event.name,
+                discriminator: event.discriminator,
+                size: fields.last().map_or(start, |f| f.offset + f.size),
+                fields,
+            }
+        })
+        .collect()
+}
+
+/// The error enum's offset and codes, if error.rs has one
+fn load_errors(src: &Path) -> (Option<u32>, Vec<IdlError>) {
+    let path = src.join("error.rs");
+    if !path.exists() {
+        return (None, Vec::new());
+    }
+    let parsed = match parser::parse_errors(&path) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => return (None, Vec::new()),
+        Err(e) => {
+            eprintln!("Warning: skipping errors in {}: {:#}", path.display(), e);
+            return (None, Vec::new());
+        }
+    };
+    let errors = parsed
+        .variants
+        .into_iter()
+        .map(|e| IdlError {
+            name: e.name,
+            code: e.code,
+            message: e.message,
+        })
+        .collect();
+    (Some(parsed.offset), errors)
+}
+
+fn idl_types(field_types: &HashMap<String, usize>) -> Vec<IdlType> {
+    let mut types: Vec<_> = field_types
+        .iter()
+        .map(|(name, size)| IdlType {
+            name: name.clone(),
+            size: *size,
+        })
+        .collect();
+    types.sort_by(|a, b| a.name.cmp(&b.name));
+    types
+}
@@ -5,15 +5,20 @@
 //! - `golt new component <name>` - Create a new component
 //! - `golt new system <name>` - Create a new system
 //! - `golt generate ts` - Generate TypeScript bindings
+//! - `golt generate idl` - Generate a JSON IDL per program
 //! - `golt build` - Build all programs
 //! - `golt test` - Run tests for all programs
 //! - `golt deploy <name>` - Deploy a program to Solana
+//! - `golt config allow <component> <system>` - Allow a system to write a component in golt.toml
+//! - `golt error <code>` - Decode a `Custom(n)` error code
 
 use clap::{Parser, Subcommand};
 use anyhow::Result;
 
 mod commands;
 mod config;
+mod discriminator;
+mod idl;
 mod generators;
 mod parser;
 mod templates;
@@ -34,6 +39,9 @@ enum Commands {
     Init {
         /// Project name
         name: String,
+        /// Derive discriminators from sha256 of the name instead of padding it
+        #[arg(long)]
+        hashed_discriminators: bool,
     },
 
     /// Create new components or systems
@@ -75,6 +83,46 @@ enum Commands {
         #[arg(long)]
         keypair: Option<String>,
     },
+
+    /// Edit which systems golt.toml allows to write a component
+    ///
+    /// Sends no transactions: apply changes on-chain with the component's
+    /// generated AddSystem / RemoveSystem instruction builders.
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+
+    /// Decode an error code, or list all error codes
+    Error {
+        /// Error code, decimal or hex (e.g. 6001 or 0x1771)
+        code: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Allow a system to write a component
+    Allow {
+        /// Component name
+        component: String,
+        /// System name
+        system: String,
+    },
+
+    /// Remove a system from a component's allowed systems
+    Disallow {
+        /// Component name
+        component: String,
+        /// System name
+        system: String,
+    },
+
+    /// Show allowed systems
+    Allowed {
+        /// Component name (defaults to all components)
+        component: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -104,6 +152,13 @@ enum GenerateCommands {
         output: String,
     },
 
+    /// Generate a JSON IDL per program
+    Idl {
+        /// Output directory
+        #[arg(short, long, default_value = "generated/idl")]
+        output: String,
+    },
+
     /// Generate a keypair for a program
     Keypair {
         /// Program name
@@ -115,7 +170,10 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Init { name } => commands::init::run(&name),
+        Commands::Init {
+            name,
+            hashed_discriminators,
+        } => commands::init::run(&name, hashed_discriminators),
         Commands::New { entity_type } => match entity_type {
             NewCommands::Component { name, seed } => {
                 commands::new_component::run(&name, seed.as_deref())
@@ -124,6 +182,7 @@ fn main() -> Result<()> {
         },
         Commands::Generate { gen_type } => match gen_type {
             GenerateCommands::Ts { output } => commands::generate_ts::run(&output),
+            GenerateCommands::Idl { output } => commands::generate_idl::run(&output),
             GenerateCommands::Keypair { name } => commands::generate_keypair::run(&name),
         },
         Commands::Build { sbf } => commands::build::run(sbf),
@@ -132,5 +191,17 @@ fn main() -> Result<()> {
         Commands::Deploy { name, url, keypair } => {
             commands::deploy::run(&name, &url, keypair.as_deref())
         }
+        Commands::Config { action } => match action {
+            ConfigCommands::Allow { component, system } => {
+                commands::allowlist::allow(&component, &system)
+            }
+            ConfigCommands::Disallow { component, system } => {
+                commands::allowlist::disallow(&component, &system)
+            }
+            ConfigCommands::Allowed { component } => {
+                commands::allowlist::allowed(component.as_deref())
+            }
+        },
+        Commands::Error { code } => commands::error::run(code.as_deref()),
     }
 }
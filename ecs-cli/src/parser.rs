@@ -3,15 +3,43 @@
 #![allow(dead_code)]
 
 use anyhow::{Context, Result};
+use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+use std::collections::HashMap;
 use std::path::Path;
 use syn::{Attribute, Field, Fields, Item, Type};
 
+use golt_layout::FieldSpec;
+
+use crate::discriminator::DiscriminatorScheme;
+
 /// Parsed component information
 #[derive(Debug, Clone)]
 pub struct ParsedComponent {
     pub name: String,
     pub fields: Vec<ParsedField>,
     pub seed: Option<String>,
+    /// `#[component(version = N)]`, stored in the byte after the discriminator
+    pub version: Option<u8>,
+    /// `#[component(reserved = N)]` bytes kept free after the last field
+    pub reserved: usize,
+    /// Discriminator set by `#[derive(Component)]` attributes; `None` for the
+    /// template, which uses golt.toml's `discriminators` scheme
+    pub discriminator: Option<[u8; 8]>,
+}
+
+impl ParsedComponent {
+    /// Fields with their byte offsets in the account data, starting after
+    /// the discriminator and version byte (see `field_offsets`)
+    pub fn layout(&self) -> Vec<PlacedField<'_>> {
+        field_offsets(&self.fields, golt_layout::header_size(self.version.is_some()))
+    }
+
+    /// Account size: the end of the last field plus the reserved bytes
+    pub fn size(&self) -> usize {
+        let header = golt_layout::header_size(self.version.is_some());
+        let end = self.layout().last().map_or(header, |p| p.offset + p.size);
+        end + self.reserved
+    }
 }
 
 /// Parsed field information
@@ -23,6 +51,25 @@ pub struct ParsedField {
     pub size: usize,
     pub is_discriminator: bool,
     pub is_bump: bool,
+    /// Capacity of a `#[max_len(N)]` `Vec<T>` / `String` field
+    pub max_len: Option<usize>,
+    /// `#[offset(N)]`: absolute byte offset in the account data
+    pub offset: Option<usize>,
+    /// `#[padding(N)]`: bytes left before the field
+    pub padding: Option<usize>,
+    /// Width of a `#[bits(N)]` / `#[flags]` field
+    pub bits: Option<u32>,
+}
+
+/// A field at its place in account or event data
+#[derive(Debug, Clone)]
+pub struct PlacedField<'a> {
+    pub field: &'a ParsedField,
+    pub offset: usize,
+    /// The field's size, or the shared word's for bit fields
+    pub size: usize,
+    /// `(shift, width)` of a bit field within its word
+    pub bits: Option<(u32, u32)>,
 }
 
 /// Parsed instruction information
@@ -34,11 +81,43 @@ pub struct ParsedInstruction {
     pub accounts: Vec<ParsedAccount>,
 }
 
+/// Parsed `#[derive(Event)]` struct
+#[derive(Debug, Clone)]
+pub struct ParsedEvent {
+    pub name: String,
+    pub discriminator: [u8; 8],
+    pub fields: Vec<ParsedField>,
+}
+
+/// Parsed `#[derive(ErrorCode)]` enum
+#[derive(Debug, Clone)]
+pub struct ParsedErrorEnum {
+    pub name: String,
+    pub offset: u32,
+    pub variants: Vec<ParsedErrorVariant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParsedErrorVariant {
+    pub name: String,
+    pub code: u32,
+    pub message: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ParsedParam {
     pub name: String,
     pub rust_type: String,
     pub ts_type: String,
+    /// Encoded size, 0 for length-prefixed `Vec<u8>` / `String` parameters
+    pub size: usize,
+}
+
+impl ParsedParam {
+    /// Whether the parameter is a length-prefixed `Vec<u8>` or `String`
+    pub fn is_var_len(&self) -> bool {
+        self.rust_type == "String" || self.rust_type == "Vec<u8>"
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -54,26 +133,40 @@ pub fn parse_component_state(path: &Path) -> Result<ParsedComponent> {
     let content = std::fs::read_to_string(path)
         .context(format!("Failed to read {}", path.display()))?;
 
-    let file = syn::parse_file(&content)
-        .context(format!("Failed to parse {}", path.display()))?;
+    parse_component_source(&content).context(format!("Failed to parse {}", path.display()))
+}
 
-    // Find the main struct (usually the one with repr(C))
+fn parse_component_source(content: &str) -> Result<ParsedComponent> {
+    let file = syn::parse_file(content)?;
+
+    // Find the main struct: #[derive(Component)] or the template's #[repr(C)]
     for item in &file.items {
         if let Item::Struct(s) = item {
-            let has_repr_c = s.attrs.iter().any(|a| is_repr_c(a));
-            if has_repr_c {
+            let is_component = s
+                .attrs
+                .iter()
+                .any(|a| is_repr_c(a) || derives(a, "Component"));
+            if is_component {
                 let seed = extract_seed_from_attrs(&s.attrs);
                 let fields = parse_struct_fields(&s.fields)?;
+                let discriminator = if s.attrs.iter().any(|a| derives(a, "Component")) {
+                    component_discriminator(&s.attrs)
+                } else {
+                    None
+                };
                 return Ok(ParsedComponent {
                     name: s.ident.to_string(),
                     fields,
                     seed,
+                    version: extract_attr_int(&s.attrs, "component", "version"),
+                    reserved: extract_attr_int(&s.attrs, "component", "reserved").unwrap_or(0),
+                    discriminator,
                 });
             }
         }
     }
 
-    anyhow::bail!("No #[repr(C)] struct found in {}", path.display())
+    anyhow::bail!("No component struct found")
 }
 
 /// Parse a component's instruction.rs file
@@ -85,18 +178,26 @@ pub fn parse_component_instructions(path: &Path) -> Result<Vec<ParsedInstruction
         .context(format!("Failed to parse {}", path.display()))?;
 
     let mut instructions = Vec::new();
+    let tag_consts = parse_u8_consts(&file);
 
     // Find the instruction enum
     for item in &file.items {
         if let Item::Enum(e) = item {
             if e.ident.to_string().ends_with("Instruction") {
-                for (tag, variant) in e.variants.iter().enumerate() {
+                for (index, variant) in e.variants.iter().enumerate() {
                     let params = parse_variant_fields(&variant.fields);
                     let accounts = extract_accounts_from_docs(&variant.attrs);
 
+                    // Prefer an explicit `{VARIANT}_DISCRIMINATOR` const over the variant index
+                    let const_name = format!(
+                        "{}_DISCRIMINATOR",
+                        variant.ident.to_string().to_shouty_snake_case()
+                    );
+                    let tag = tag_consts.get(&const_name).copied().unwrap_or(index as u8);
+
                     instructions.push(ParsedInstruction {
                         name: variant.ident.to_string(),
-                        tag: tag as u8,
+                        tag,
                         params,
                         accounts,
                     });
@@ -108,6 +209,450 @@ pub fn parse_component_instructions(path: &Path) -> Result<Vec<ParsedInstruction
     Ok(instructions)
 }
 
+/// Parse the `#[system]` functions in a system's source directory
+///
+/// Each is one instruction (tag 0) named after the function. Its accounts
+/// follow the generated processor: the entity, one per `&C` / `&mut C`
+/// argument, then the system signer and each `&mut` component's allowlist
+/// and program. With a `&mut` component the data starts with the system
+/// signer's bump.
+pub fn parse_system_fns(src_dir: &Path) -> Result<Vec<ParsedInstruction>> {
+    let mut instructions = Vec::new();
+    let mut files = Vec::new();
+    collect_rs_files(src_dir, &mut files)?;
+    files.sort();
+
+    for path in files {
+        let content = std::fs::read_to_string(&path)
+            .context(format!("Failed to read {}", path.display()))?;
+        let file = syn::parse_file(&content)
+            .context(format!("Failed to parse {}", path.display()))?;
+
+        for item in &file.items {
+            let Item::Fn(f) = item else {
+                continue;
+            };
+            let is_system = f.attrs.iter().any(|a| {
+                a.path()
+                    .segments
+                    .last()
+                    .is_some_and(|seg| seg.ident == "system")
+            });
+            if !is_system {
+                continue;
+            }
+
+            let account = |name: String, is_writable: bool, description: String| ParsedAccount {
+                name,
+                is_signer: false,
+                is_writable,
+                description,
+            };
+            let mut params = Vec::new();
+            let mut accounts = vec![account("entity".into(), false, "Entity".into())];
+            let mut write_backs = Vec::new();
+            for arg in &f.sig.inputs {
+                let syn::FnArg::Typed(pat_type) = arg else {
+                    continue;
+                };
+                let syn::Pat::Ident(pat) = &*pat_type.pat else {
+                    continue;
+                };
+                let name = pat.ident.to_string();
+                match &*pat_type.ty {
+                    Type::Reference(reference) => {
+                        let is_mut = reference.mutability.is_some();
+                        let component = type_to_string(&reference.elem);
+                        accounts.push(account(name.clone(), is_mut, component.clone()));
+                        if is_mut {
+                            write_backs.push(account(
+                                format!("{}_allowlist", name),
+                                false,
+                                format!("{} allowlist PDA", component),
+                            ));
+                            write_backs.push(account(
+                                format!("{}_program", name),
+                                false,
+                                format!("{} program", component),
+                            ));
+                        }
+                    }
+                    ty => params.push(parse_param(name, ty)),
+                }
+            }
+            if !write_backs.is_empty() {
+                accounts.push(account("system_signer".into(), false, "System signer PDA".into()));
+                accounts.extend(write_backs);
+                params.insert(0, parse_param("system_signer_bump".into(), &syn::parse_quote!(u8)));
+            }
+
+            instructions.push(ParsedInstruction {
+                name: f.sig.ident.to_string().to_upper_camel_case(),
+                tag: 0,
+                params,
+                accounts,
+            });
+        }
+    }
+
+    Ok(instructions)
+}
+
+/// Collect `const NAME: u8 = N;` items
+fn parse_u8_consts(file: &syn::File) -> HashMap<String, u8> {
+    let mut consts = HashMap::new();
+    for item in &file.items {
+        if let Item::Const(c) = item {
+            if let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(lit_int),
+                ..
+            }) = &*c.expr
+            {
+                if let Ok(value) = lit_int.base10_parse::<u8>() {
+                    consts.insert(c.ident.to_string(), value);
+                }
+            }
+        }
+    }
+    consts
+}
+
+/// Parse all `#[derive(Event)]` structs in a program's source directory
+pub fn parse_events(src_dir: &Path) -> Result<Vec<ParsedEvent>> {
+    let mut events = Vec::new();
+    let mut files = Vec::new();
+    collect_rs_files(src_dir, &mut files)?;
+    files.sort();
+
+    for path in files {
+        let content = std::fs::read_to_string(&path)
+            .context(format!("Failed to read {}", path.display()))?;
+        let file = syn::parse_file(&content)
+            .context(format!("Failed to parse {}", path.display()))?;
+
+        for item in &file.items {
+            if let Item::Struct(s) = item {
+                if !s.attrs.iter().any(|a| derives(a, "Event")) {
+                    continue;
+                }
+                let name = s.ident.to_string();
+                let disc_str = extract_attr_string(&s.attrs, "event", "discriminator")
+                    .unwrap_or_else(|| name.to_snake_case());
+                // `#[derive(Event)]` always hashes
+                let discriminator = DiscriminatorScheme::Hashed.discriminator("event", &disc_str);
+
+                events.push(ParsedEvent {
+                    name,
+                    discriminator,
+                    fields: parse_struct_fields(&s.fields)?,
+                });
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// Packed sizes of the `#[derive(ComponentField)]` structs and enums in a
+/// program's source directory, keyed by type name
+pub fn parse_field_types(src_dir: &Path) -> Result<HashMap<String, usize>> {
+    let mut paths = Vec::new();
+    collect_rs_files(src_dir, &mut paths)?;
+
+    let mut files = Vec::new();
+    for path in paths {
+        let content = std::fs::read_to_string(&path)
+            .context(format!("Failed to read {}", path.display()))?;
+        let file = syn::parse_file(&content)
+            .context(format!("Failed to parse {}", path.display()))?;
+        files.push(file);
+    }
+    Ok(field_type_sizes(&files))
+}
+
+/// Packed sizes of the `#[derive(ComponentField)]` types in `files`
+fn field_type_sizes(files: &[syn::File]) -> HashMap<String, usize> {
+    // Each type's field types; one list per enum variant
+    let mut layouts: Vec<(String, bool, Vec<Vec<String>>)> = Vec::new();
+    for file in files {
+        for item in &file.items {
+            let field_types =
+                |fields: &Fields| fields.iter().map(|f| type_to_string(&f.ty)).collect::<Vec<_>>();
+            match item {
+                Item::Struct(s) if s.attrs.iter().any(|a| derives(a, "ComponentField")) => {
+                    layouts.push((s.ident.to_string(), false, vec![field_types(&s.fields)]));
+                }
+                Item::Enum(e) if e.attrs.iter().any(|a| derives(a, "ComponentField")) => {
+                    let variants = e.variants.iter().map(|v| field_types(&v.fields)).collect();
+                    layouts.push((e.ident.to_string(), true, variants));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Types can nest, so resolve until nothing changes
+    let mut sizes = HashMap::new();
+    loop {
+        let mut progress = false;
+        for (name, is_enum, variants) in &layouts {
+            if sizes.contains_key(name) {
+                continue;
+            }
+            let variant_sizes: Option<Vec<usize>> = variants
+                .iter()
+                .map(|types| types.iter().map(|ty| field_type_size(ty, &sizes)).sum())
+                .collect();
+            if let Some(variant_sizes) = variant_sizes {
+                let size = if *is_enum {
+                    golt_layout::enum_size(variant_sizes)
+                } else {
+                    variant_sizes.into_iter().sum()
+                };
+                sizes.insert(name.clone(), size);
+                progress = true;
+            }
+        }
+        if !progress {
+            return sizes;
+        }
+    }
+}
+
+/// Size of a field type, given the sizes of known `ComponentField` types
+fn field_type_size(rust_type: &str, field_types: &HashMap<String, usize>) -> Option<usize> {
+    match estimate_type_size(rust_type) {
+        0 => {}
+        size => return Some(size),
+    }
+    if let Some(inner) = rust_type.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        let (elem, len) = inner.rsplit_once(';')?;
+        return Some(field_type_size(elem, field_types)? * len.parse::<usize>().ok()?);
+    }
+    let name = rust_type.rsplit("::").next().unwrap_or(rust_type);
+    field_types.get(name).copied()
+}
+
+/// Fields with their byte offsets, packed from `start` by
+/// `golt_layout::place_fields` as the derive macros lay them out
+///
+/// The template's explicit `discriminator` field is left out. Nested field
+/// sizes must be filled in by `resolve_field_types` first.
+pub fn field_offsets(fields: &[ParsedField], start: usize) -> Vec<PlacedField<'_>> {
+    let fields: Vec<_> = fields.iter().filter(|f| !f.is_discriminator).collect();
+    let specs: Vec<_> = fields
+        .iter()
+        .map(|field| FieldSpec {
+            size: field.size,
+            offset: field.offset,
+            padding: field.padding,
+            bits: field.bits,
+        })
+        .collect();
+    fields
+        .into_iter()
+        .zip(golt_layout::place_fields(&specs, start))
+        .map(|(field, placement)| PlacedField {
+            field,
+            offset: placement.offset,
+            size: placement.size,
+            bits: placement.bits,
+        })
+        .collect()
+}
+
+/// Fill in the size of fields whose type is a `#[derive(ComponentField)]`
+/// type; TypeScript sees them as raw bytes
+pub fn resolve_field_types(fields: &mut [ParsedField], field_types: &HashMap<String, usize>) {
+    for field in fields.iter_mut() {
+        if field.size != 0 || field.max_len.is_some() {
+            continue;
+        }
+        if let Some(size) = field_type_size(&field.rust_type, field_types) {
+            field.size = size;
+            field.ts_type = format!("Uint8Array /* {}, {} bytes */", field.rust_type, size);
+        }
+    }
+}
+
+/// Like `resolve_field_types`, for instruction parameters
+pub fn resolve_param_types(
+    instructions: &mut [ParsedInstruction],
+    field_types: &HashMap<String, usize>,
+) {
+    let params = instructions.iter_mut().flat_map(|i| i.params.iter_mut());
+    for param in params {
+        if param.size != 0 || param.is_var_len() {
+            continue;
+        }
+        if let Some(size) = field_type_size(&param.rust_type, field_types) {
+            param.size = size;
+            param.ts_type = format!("Uint8Array /* {}, {} bytes */", param.rust_type, size);
+        }
+    }
+}
+
+/// Parse the `#[derive(ErrorCode)]` enum of a program's error.rs
+pub fn parse_errors(path: &Path) -> Result<Option<ParsedErrorEnum>> {
+    let content = std::fs::read_to_string(path)
+        .context(format!("Failed to read {}", path.display()))?;
+    parse_errors_source(&content).context(format!("Failed to parse {}", path.display()))
+}
+
+/// Parse the `#[derive(ErrorCode)]` enum of error.rs source code
+fn parse_errors_source(content: &str) -> Result<Option<ParsedErrorEnum>> {
+    let file = syn::parse_file(content)?;
+
+    for item in &file.items {
+        if let Item::Enum(e) = item {
+            if !e.attrs.iter().any(|a| derives(a, "ErrorCode")) {
+                continue;
+            }
+            let offset = extract_error_offset(&e.attrs)
+                .context(format!("{} is missing #[golt_error(offset = ...)]", e.ident))?;
+
+            let variants = e
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let name = v.ident.to_string();
+                    let message = extract_attr_string(&v.attrs, "golt_error", "msg")
+                        .or_else(|| extract_doc(&v.attrs))
+                        .unwrap_or_else(|| name.clone());
+                    ParsedErrorVariant {
+                        name,
+                        code: offset + i as u32,
+                        message,
+                    }
+                })
+                .collect();
+
+            return Ok(Some(ParsedErrorEnum {
+                name: e.ident.to_string(),
+                offset,
+                variants,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+fn extract_error_offset(attrs: &[Attribute]) -> Option<u32> {
+    let mut offset = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("golt_error")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("offset") {
+                let lit: syn::LitInt = meta.value()?.parse()?;
+                offset = Some(lit.base10_parse()?);
+            }
+            Ok(())
+        });
+    }
+    offset
+}
+
+/// Doc comment lines joined with spaces
+fn extract_doc(attrs: &[Attribute]) -> Option<String> {
+    let doc = attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|a| match &a.meta.require_name_value().ok()?.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) => Some(s.value().trim().to_string()),
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn collect_rs_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_rs_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `attr` is a `#[derive(...)]` listing `name`
+fn derives(attr: &Attribute, name: &str) -> bool {
+    if !attr.path().is_ident("derive") {
+        return false;
+    }
+    attr.parse_args_with(
+        syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+    )
+    .map(|paths| {
+        paths
+            .iter()
+            .any(|p| p.segments.last().is_some_and(|seg| seg.ident == name))
+    })
+    .unwrap_or(false)
+}
+
+/// Read `key = "value"` from `#[attr_name(...)]`
+fn extract_attr_string(attrs: &[Attribute], attr_name: &str, key: &str) -> Option<String> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident(attr_name)) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                value = Some(lit.value());
+            } else if meta.input.peek(syn::Token![=]) {
+                let _: syn::Expr = meta.value()?.parse()?;
+            }
+            Ok(())
+        });
+    }
+    value
+}
+
+/// Read `key = N` from `#[attr_name(...)]`
+fn extract_attr_int<N>(attrs: &[Attribute], attr_name: &str, key: &str) -> Option<N>
+where
+    N: std::str::FromStr,
+    N::Err: std::fmt::Display,
+{
+    let mut value = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident(attr_name)) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                let lit: syn::LitInt = meta.value()?.parse()?;
+                value = lit.base10_parse().ok();
+            } else if meta.input.peek(syn::Token![=]) {
+                let _: syn::Expr = meta.value()?.parse()?;
+            }
+            Ok(())
+        });
+    }
+    value
+}
+
+/// Whether `#[attr_name(..., flag, ...)]` is present
+fn has_attr_flag(attrs: &[Attribute], attr_name: &str, flag: &str) -> bool {
+    let mut found = false;
+    for attr in attrs.iter().filter(|a| a.path().is_ident(attr_name)) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(flag) && !meta.input.peek(syn::Token![=]) {
+                found = true;
+            } else if meta.input.peek(syn::Token![=]) {
+                let _: syn::Expr = meta.value()?.parse()?;
+            }
+            Ok(())
+        });
+    }
+    found
+}
+
 fn is_repr_c(attr: &Attribute) -> bool {
     if attr.path().is_ident("repr") {
         if let Ok(meta) = attr.parse_args::<syn::Ident>() {
@@ -117,6 +662,19 @@ fn is_repr_c(attr: &Attribute) -> bool {
     false
 }
 
+/// Discriminator from `#[component(seed, discriminator, hashed)]`, derived
+/// as in golt-macros: `discriminator` (else `seed`), padded or hashed
+fn component_discriminator(attrs: &[Attribute]) -> Option<[u8; 8]> {
+    let name = extract_attr_string(attrs, "component", "discriminator")
+        .or_else(|| extract_attr_string(attrs, "component", "seed"))?;
+    let scheme = if has_attr_flag(attrs, "component", "hashed") {
+        DiscriminatorScheme::Hashed
+    } else {
+        DiscriminatorScheme::Padded
+    };
+    Some(scheme.discriminator("component", &name))
+}
+
 fn extract_seed_from_attrs(attrs: &[Attribute]) -> Option<String> {
     for attr in attrs {
         if attr.path().is_ident("component") {
@@ -143,10 +701,18 @@ fn parse_struct_fields(fields: &Fields) -> Result<Vec<ParsedField>> {
 
     if let Fields::Named(named) = fields {
         for field in &named.named {
+            // `#[skip]` fields aren't stored
+            if field.attrs.iter().any(|a| a.path().is_ident("skip")) {
+                continue;
+            }
             let name = field.ident.as_ref().unwrap().to_string();
             let rust_type = type_to_string(&field.ty);
             let ts_type = rust_type_to_ts(&rust_type);
-            let size = estimate_type_size(&rust_type);
+            let max_len = extract_field_int(field, "max_len");
+            let size = match max_len {
+                Some(n) => golt_layout::var_len_size(n, var_len_elem_size(&rust_type)),
+                None => estimate_type_size(&rust_type),
+            };
             let is_discriminator = name == "discriminator";
             let is_bump = name == "bump" || has_bump_attr(field);
 
@@ -157,6 +723,13 @@ fn parse_struct_fields(fields: &Fields) -> Result<Vec<ParsedField>> {
                 size,
                 is_discriminator,
                 is_bump,
+                max_len,
+                offset: extract_field_int(field, "offset"),
+                padding: extract_field_int(field, "padding"),
+                bits: extract_field_int(field, "bits").map(|bits| bits as u32).or_else(|| {
+                    let is_flag = field.attrs.iter().any(|a| a.path().is_ident("flags"));
+                    is_flag.then_some(1)
+                }),
             });
         }
     }
@@ -164,41 +737,57 @@ fn parse_struct_fields(fields: &Fields) -> Result<Vec<ParsedField>> {
     Ok(parsed)
 }
 
+/// Value of a field attribute like `#[max_len(N)]`
+fn extract_field_int(field: &Field, attr_name: &str) -> Option<usize> {
+    field
+        .attrs
+        .iter()
+        .find(|a| a.path().is_ident(attr_name))
+        .and_then(|a| a.parse_args::<syn::LitInt>().ok())
+        .and_then(|lit| lit.base10_parse().ok())
+}
+
+/// Element type of a `Vec<T>`, e.g. `u64` for `Vec<u64>`
+pub fn vec_elem(rust_type: &str) -> Option<&str> {
+    rust_type.strip_prefix("Vec<")?.strip_suffix('>')
+}
+
+/// Size of one element of a variable-length field (a byte for `String`)
+pub fn var_len_elem_size(rust_type: &str) -> usize {
+    vec_elem(rust_type).map(estimate_type_size).unwrap_or(1)
+}
+
 fn has_bump_attr(field: &Field) -> bool {
     field.attrs.iter().any(|a| a.path().is_ident("pda_bump"))
 }
 
 fn parse_variant_fields(fields: &Fields) -> Vec<ParsedParam> {
-    let mut params = Vec::new();
-
-    match fields {
-        Fields::Named(named) => {
-            for field in &named.named {
-                let name = field.ident.as_ref().unwrap().to_string();
-                let rust_type = type_to_string(&field.ty);
-                let ts_type = rust_type_to_ts(&rust_type);
-                params.push(ParsedParam {
-                    name,
-                    rust_type,
-                    ts_type,
-                });
-            }
-        }
-        Fields::Unnamed(unnamed) => {
-            for (i, field) in unnamed.unnamed.iter().enumerate() {
-                let rust_type = type_to_string(&field.ty);
-                let ts_type = rust_type_to_ts(&rust_type);
-                params.push(ParsedParam {
-                    name: format!("arg{}", i),
-                    rust_type,
-                    ts_type,
-                });
-            }
-        }
-        Fields::Unit => {}
-    }
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let name = match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => format!("arg{}", i),
+            };
+            parse_param(name, &field.ty)
+        })
+        .collect()
+}
 
-    params
+fn parse_param(name: String, ty: &Type) -> ParsedParam {
+    let rust_type = type_to_string(ty);
+    // Instruction data carries exactly the bytes given, not an array
+    let ts_type = match rust_type.as_str() {
+        "Vec<u8>" => "Uint8Array".to_string(),
+        _ => rust_type_to_ts(&rust_type),
+    };
+    ParsedParam {
+        name,
+        size: estimate_type_size(&rust_type),
+        rust_type,
+        ts_type,
+    }
 }
 
 fn extract_accounts_from_docs(attrs: &[Attribute]) -> Vec<ParsedAccount> {
@@ -265,12 +854,26 @@ fn type_to_string(ty: &Type) -> String {
     quote::quote!(#ty).to_string().replace(' ', "")
 }
 
-fn rust_type_to_ts(rust_type: &str) -> String {
+/// Whether a parsed type is `golt_runtime::Fixed` (Q32.32, 8 bytes)
+///
+/// Follows the derives: other types named `Fixed` are nested fields.
+pub fn is_fixed(rust_type: &str) -> bool {
+    let segments: Vec<&str> = rust_type.trim_start_matches("::").split("::").collect();
+    golt_layout::is_fixed_path(&segments)
+}
+
+pub fn rust_type_to_ts(rust_type: &str) -> String {
     match rust_type {
+        s if is_fixed(s) => "number".to_string(),
         "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "f32" | "f64" => "number".to_string(),
         "u64" | "i64" | "u128" | "i128" => "bigint".to_string(),
         "bool" => "boolean".to_string(),
+        "String" => "string".to_string(),
         "Pubkey" | "[u8;32]" => "PublicKey".to_string(),
+        s if s.starts_with("Vec<") => match rust_type_to_ts(vec_elem(s).unwrap_or_default()) {
+            ts if ts.starts_with("Uint8Array") => "Uint8Array[]".to_string(),
+            ts => format!("{}[]", ts),
+        },
         s if s.starts_with("[u8;") => {
             let size = s
                 .trim_start_matches("[u8;")
@@ -284,13 +887,12 @@ fn rust_type_to_ts(rust_type: &str) -> String {
 }
 
 fn estimate_type_size(rust_type: &str) -> usize {
+    let name = rust_type.rsplit("::").next().unwrap_or(rust_type);
+    if let Some(size) = golt_layout::primitive_size(name) {
+        return size;
+    }
     match rust_type {
-        "u8" | "i8" | "bool" => 1,
-        "u16" | "i16" => 2,
-        "u32" | "i32" | "f32" => 4,
-        "u64" | "i64" | "f64" => 8,
-        "u128" | "i128" => 16,
-        "Pubkey" | "[u8;32]" => 32,
+        s if is_fixed(s) => golt_layout::FIXED_SIZE,
         s if s.starts_with("[u8;") => s
             .trim_start_matches("[u8;")
             .trim_end_matches(']')
@@ -299,3 +901,180 @@ fn estimate_type_size(rust_type: &str) -> usize {
         _ => 0,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use golt_runtime::Component;
+
+    use super::*;
+
+    /// Expands the items and keeps their source in `SOURCE`, so the parser
+    /// sees exactly what the derive compiled
+    macro_rules! golden {
+        ($($item:item)*) => {
+            $($item)*
+
+            pub const SOURCE: &str = stringify!($($item)*);
+        };
+    }
+
+    /// The CLI's layout of the component in `source` must match the derive's
+    /// `LAYOUT` and `SIZE`
+    fn check_layout<C: Component>(source: &str) {
+        let mut parsed = parse_component_source(source).unwrap();
+        let field_types = field_type_sizes(&[syn::parse_file(source).unwrap()]);
+        resolve_field_types(&mut parsed.fields, &field_types);
+
+        let layout: Vec<_> = parsed
+            .layout()
+            .into_iter()
+            .map(|p| (p.field.name.clone(), p.offset, p.size, p.bits))
+            .collect();
+        let expected: Vec<_> = C::LAYOUT
+            .iter()
+            .map(|f| {
+                let bits = f.bits.map(|b| (b.shift as u32, b.width as u32));
+                (f.name.to_string(), f.offset, f.size, bits)
+            })
+            .collect();
+        assert_eq!(layout, expected);
+        assert_eq!(parsed.size(), C::SIZE);
+    }
+
+    mod plain {
+        use golt_macros::Component;
+        use golt_runtime::Component;
+
+        golden! {
+            #[derive(Component, Clone, Debug, PartialEq)]
+            #[component(seed = "plain")]
+            pub struct Plain {
+                #[entity]
+                pub entity: [u8; 32],
+                pub hp: u32,
+                pub owner: golt_runtime::pinocchio::pubkey::Pubkey,
+                pub speed: golt_runtime::Fixed,
+                pub big: u128,
+                pub ratio: f64,
+                #[skip]
+                pub cached: u64,
+                pub bump: u8,
+            }
+        }
+    }
+
+    mod explicit {
+        use golt_macros::Component;
+        use golt_runtime::Component;
+
+        golden! {
+            #[derive(Component, Clone, Debug, PartialEq)]
+            #[component(seed = "explicit", version = 2, reserved = 16)]
+            pub struct Explicit {
+                #[entity]
+                pub entity: [u8; 32],
+                #[offset(48)]
+                pub level: u16,
+                #[padding(2)]
+                pub xp: u32,
+                #[max_len(4)]
+                pub scores: Vec<u64>,
+                #[max_len(10)]
+                pub name: String,
+            }
+        }
+    }
+
+    mod bits {
+        use golt_macros::Component;
+        use golt_runtime::Component;
+
+        golden! {
+            #[derive(Component, Clone, Debug, PartialEq)]
+            #[component(seed = "bits")]
+            pub struct Bits {
+                #[entity]
+                pub entity: [u8; 32],
+                #[flags]
+                pub alive: bool,
+                #[bits(3)]
+                pub class: u8,
+                #[bits(12)]
+                pub level: u16,
+                pub hp: u16,
+                #[bits(40)]
+                pub xp: u64,
+                #[bits(30)]
+                pub gold: u32,
+                #[padding(1)]
+                #[bits(4)]
+                pub rank: u8,
+                #[skip]
+                pub dirty: bool,
+                #[bits(4)]
+                pub tier: u8,
+            }
+        }
+    }
+
+    mod nested {
+        use golt_macros::{Component, ComponentField};
+        use golt_runtime::Component;
+
+        golden! {
+            #[derive(Clone, Copy, Debug, PartialEq, ComponentField)]
+            pub struct Stats {
+                pub strength: u16,
+                pub agility: u16,
+            }
+
+            #[derive(Clone, Copy, Debug, PartialEq, ComponentField)]
+            pub enum Status {
+                Idle,
+                Stunned { turns: u8 },
+                Poisoned(u32, golt_runtime::Fixed),
+                Buffed(Stats),
+            }
+
+            /// Not `golt_runtime::Fixed`, so packed as a nested field
+            #[derive(Clone, Copy, Debug, PartialEq, ComponentField)]
+            pub struct Fixed {
+                pub whole: i64,
+                pub frac: u32,
+            }
+
+            #[derive(Component, Clone, Debug, PartialEq)]
+            #[component(seed = "hero")]
+            pub struct Hero {
+                #[entity]
+                pub entity: [u8; 32],
+                pub stats: Stats,
+                pub status: Status,
+                pub party: [Stats; 3],
+                pub scale: self::Fixed,
+                pub speed: golt_runtime::math::Fixed,
+                pub gold: u64,
+            }
+        }
+    }
+
+    #[test]
+    fn layout_matches_derive_for_primitives() {
+        check_layout::<plain::Plain>(plain::SOURCE);
+    }
+
+    #[test]
+    fn layout_matches_derive_for_offset_padding_and_max_len() {
+        check_layout::<explicit::Explicit>(explicit::SOURCE);
+    }
+
+    #[test]
+    fn layout_matches_derive_for_bit_fields() {
+        check_layout::<bits::Bits>(bits::SOURCE);
+    }
+
+    #[test]
+    fn layout_matches_derive_for_component_fields() {
+        check_layout::<nested::Hero>(nested::SOURCE);
+    }
+}
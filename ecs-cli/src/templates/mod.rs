@@ -14,6 +14,7 @@ pinocchio.workspace = true
 pinocchio-pubkey.workspace = true
 pinocchio-system.workspace = true
 ephemeral-rollups-pinocchio.workspace = true
+golt-runtime.workspace = true
 ecs-core = {{ path = "../../core" }}
 
 [lib]
@@ -21,6 +22,8 @@ crate-type = ["cdylib", "lib"]
 
 [features]
 no-entrypoint = []
+# `Arbitrary` impls and round-trip tests for `#[derive(Component)]` types
+arbitrary = ["golt-runtime/arbitrary"]
 "#,
         name = name
     )
@@ -45,6 +48,11 @@ pub use state::*;
 
 // TODO: Replace with actual program ID after running `golt generate keypair {snake_name}`
 pinocchio_pubkey::declare_id!("11111111111111111111111111111111");
+
+/// Program ID of the entity registry whose entities own this component
+// TODO: Replace with the program ID of your deployed golt-registry
+pub const REGISTRY_ID: pinocchio::pubkey::Pubkey =
+    pinocchio_pubkey::pubkey!("11111111111111111111111111111111");
 "#,
         pascal_name = pascal_name,
         snake_name = snake_name
@@ -59,7 +67,8 @@ pub fn component_state_rs(snake_name: &str, pascal_name: &str, seed: &str) -> St
         r#"//! {pascal_name} component state
 
 use ecs_core::discriminators;
-use pinocchio::pubkey::{{find_program_address, Pubkey}};
+use golt_runtime::{{derive_pda, verify_pda_with_bump}};
+use pinocchio::pubkey::Pubkey;
 
 /// {pascal_name} component size
 /// Discriminator (8) + entity (32) + ... + bump (1)
@@ -114,7 +123,25 @@ impl {pascal_name} {{
 
 /// Derive {pascal_name} PDA
 pub fn derive_{snake_name}_pda(entity: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {{
-    find_program_address(&[ecs_core::seeds::{upper_name}, entity.as_ref()], program_id)
+    derive_pda(&[ecs_core::seeds::{upper_name}, entity.as_ref()], program_id)
+}}
+
+/// Verify a {pascal_name} PDA using its stored bump
+///
+/// Much cheaper on-chain than `derive_{snake_name}_pda`, which searches for the bump.
+pub fn verify_{snake_name}_pda(
+    address: &Pubkey,
+    entity: &Pubkey,
+    bump: u8,
+    program_id: &Pubkey,
+) -> bool {{
+    verify_pda_with_bump(
+        address,
+        &[ecs_core::seeds::{upper_name}, entity.as_ref()],
+        bump,
+        program_id,
+    )
+    .is_ok()
 }}
 "#,
         pascal_name = pascal_name,
@@ -131,12 +158,24 @@ pub fn component_instruction_rs(pascal_name: &str) -> String {
 
 use pinocchio::program_error::ProgramError;
 
+/// Instruction discriminator for system writes
+pub const WRITE_DISCRIMINATOR: u8 = 248;
+/// Instruction discriminator for creating the system allowlist
+pub const INIT_ALLOWLIST_DISCRIMINATOR: u8 = 249;
+/// Instruction discriminator for allowing a system
+pub const ADD_SYSTEM_DISCRIMINATOR: u8 = 250;
+/// Instruction discriminator for removing a system
+pub const REMOVE_SYSTEM_DISCRIMINATOR: u8 = 251;
+/// Instruction discriminator for close
+pub const CLOSE_DISCRIMINATOR: u8 = 252;
 /// Instruction discriminator for delegate (used by templates)
 pub const DELEGATE_DISCRIMINATOR: u8 = 253;
+/// Instruction discriminator for undelegate
+pub const UNDELEGATE_DISCRIMINATOR: u8 = 254;
 /// Instruction discriminator for undelegate callback
 pub const UNDELEGATE_CALLBACK_DISCRIMINATOR: u8 = 0xc4;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum {pascal_name}Instruction {{
     /// Initialize a new {pascal_name} component
     ///
@@ -148,6 +187,57 @@ pub enum {pascal_name}Instruction {{
     Init,
 
     // TODO: Add more instructions here
+    // Instructions that write the component must take the allowlist and the
+    // system's signer PDA and call `require_system_caller`, like `Write`
+
+    /// Overwrite the component, called by an allowlisted system
+    ///
+    /// `component` is the packed component; its entity and bump cannot change.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` {pascal_name} PDA
+    /// 1. `[]` Allowlist PDA
+    /// 2. `[signer]` System Signer PDA
+    Write {{
+        component: Vec<u8>,
+    }},
+
+    /// Create the allowlist of systems that may write this component
+    ///
+    /// Accounts:
+    /// 0. `[signer, writable]` Payer
+    /// 1. `[signer]` Admin (the program's upgrade authority)
+    /// 2. `[writable]` Allowlist PDA
+    /// 3. `[]` ProgramData account of this program
+    /// 4. `[]` System Program
+    InitAllowlist,
+
+    /// Allow a system to write this component
+    ///
+    /// Accounts:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Allowlist PDA
+    AddSystem {{
+        system: [u8; 32],
+    }},
+
+    /// Remove a system from the allowlist
+    ///
+    /// Accounts:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Allowlist PDA
+    RemoveSystem {{
+        system: [u8; 32],
+    }},
+
+    /// Close the component and reclaim its rent
+    ///
+    /// Accounts:
+    /// 0. `[signer]` Owner of the entity
+    /// 1. `[]` Entity (registry PDA)
+    /// 2. `[writable]` {pascal_name} PDA
+    /// 3. `[writable]` Recipient
+    Close,
 
     /// Delegate component to Ephemeral Rollup
     ///
@@ -183,6 +273,32 @@ impl {pascal_name}Instruction {{
             0 => Ok(Self::Init),
             // TODO: Add more cases
 
+            WRITE_DISCRIMINATOR => {{
+                let len = rest.get(0..4).ok_or(ProgramError::InvalidInstructionData)?;
+                let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+                let component = rest
+                    .get(4..4 + len)
+                    .ok_or(ProgramError::InvalidInstructionData)?
+                    .to_vec();
+                Ok(Self::Write {{ component }})
+            }}
+
+            INIT_ALLOWLIST_DISCRIMINATOR => Ok(Self::InitAllowlist),
+            ADD_SYSTEM_DISCRIMINATOR | REMOVE_SYSTEM_DISCRIMINATOR => {{
+                let system: [u8; 32] = rest
+                    .get(0..32)
+                    .ok_or(ProgramError::InvalidInstructionData)?
+                    .try_into()
+                    .unwrap();
+                if tag == ADD_SYSTEM_DISCRIMINATOR {{
+                    Ok(Self::AddSystem {{ system }})
+                }} else {{
+                    Ok(Self::RemoveSystem {{ system }})
+                }}
+            }}
+
+            CLOSE_DISCRIMINATOR => Ok(Self::Close),
+
             // Delegate instruction
             DELEGATE_DISCRIMINATOR => {{
                 if rest.len() < 36 {{
@@ -197,7 +313,7 @@ impl {pascal_name}Instruction {{
             }}
 
             // Undelegate instruction (explicit call)
-            254 => Ok(Self::Undelegate),
+            UNDELEGATE_DISCRIMINATOR => Ok(Self::Undelegate),
 
             // Undelegate callback from delegation program
             UNDELEGATE_CALLBACK_DISCRIMINATOR if data.len() >= 8 => Ok(Self::Undelegate),
@@ -210,6 +326,24 @@ impl {pascal_name}Instruction {{
         match self {{
             Self::Init => vec![0],
             // TODO: Add more cases
+            Self::Write {{ component }} => {{
+                let mut data = vec![WRITE_DISCRIMINATOR];
+                data.extend_from_slice(&(component.len() as u32).to_le_bytes());
+                data.extend_from_slice(component);
+                data
+            }}
+            Self::InitAllowlist => vec![INIT_ALLOWLIST_DISCRIMINATOR],
+            Self::AddSystem {{ system }} => {{
+                let mut data = vec![ADD_SYSTEM_DISCRIMINATOR];
+                data.extend_from_slice(system);
+                data
+            }}
+            Self::RemoveSystem {{ system }} => {{
+                let mut data = vec![REMOVE_SYSTEM_DISCRIMINATOR];
+                data.extend_from_slice(system);
+                data
+            }}
+            Self::Close => vec![CLOSE_DISCRIMINATOR],
             Self::Delegate {{
                 commit_frequency_ms,
                 validator,
@@ -220,10 +354,92 @@ impl {pascal_name}Instruction {{
                 data[5..37].copy_from_slice(validator);
                 data
             }}
-            Self::Undelegate => vec![254],
+            Self::Undelegate => vec![UNDELEGATE_DISCRIMINATOR],
         }}
     }}
 }}
+
+/// CPI helpers for calling this component from systems
+///
+/// Each instruction has `name(accounts, ..)` and `name_signed(accounts, .., signers)`,
+/// which build the `Instruction` and account metas for you.
+pub mod cpi {{
+    use pinocchio::{{
+        account_info::AccountInfo,
+        instruction::{{AccountMeta, Instruction, Signer}},
+        program::invoke_signed,
+        ProgramResult,
+    }};
+
+    use super::{pascal_name}Instruction;
+
+    fn call<const N: usize>(
+        instruction: {pascal_name}Instruction,
+        metas: &[AccountMeta; N],
+        accounts: &[&AccountInfo; N],
+        signers: &[Signer],
+    ) -> ProgramResult {{
+        let data = instruction.pack();
+        let instruction = Instruction {{
+            program_id: &crate::ID,
+            data: &data,
+            accounts: metas,
+        }};
+        invoke_signed(&instruction, accounts, signers)
+    }}
+
+    /// Initialize a {pascal_name} component: `[payer, entity, {pascal_name} PDA, system program]`
+    pub fn init(accounts: &[&AccountInfo; 4]) -> ProgramResult {{
+        init_signed(accounts, &[])
+    }}
+
+    /// `init`, signing with PDA seeds
+    pub fn init_signed(accounts: &[&AccountInfo; 4], signers: &[Signer]) -> ProgramResult {{
+        let metas = [
+            AccountMeta::writable_signer(accounts[0].key()),
+            AccountMeta::readonly(accounts[1].key()),
+            AccountMeta::writable(accounts[2].key()),
+            AccountMeta::readonly(accounts[3].key()),
+        ];
+        call({pascal_name}Instruction::Init, &metas, accounts, signers)
+    }}
+
+    /// Write a packed {pascal_name} as a system: `[{pascal_name} PDA, allowlist, system signer PDA]`
+    ///
+    /// `signers` must include the system signer PDA's seeds.
+    pub fn write_signed(
+        accounts: &[&AccountInfo; 3],
+        data: &[u8],
+        signers: &[Signer],
+    ) -> ProgramResult {{
+        let metas = [
+            AccountMeta::writable(accounts[0].key()),
+            AccountMeta::readonly(accounts[1].key()),
+            AccountMeta::readonly_signer(accounts[2].key()),
+        ];
+        let instruction = {pascal_name}Instruction::Write {{ component: data.to_vec() }};
+        call(instruction, &metas, accounts, signers)
+    }}
+
+    // TODO: Add CPI helpers for your instructions, e.g.
+    // pub fn update_signed(accounts: &[&AccountInfo; 3], value: u64, signers: &[Signer]) -> ProgramResult
+
+    /// Close a {pascal_name} component: `[entity owner, entity, {pascal_name} PDA, recipient]`
+    pub fn close(accounts: &[&AccountInfo; 4]) -> ProgramResult {{
+        close_signed(accounts, &[])
+    }}
+
+    /// `close`, signing with PDA seeds (e.g. a PDA that owns the entity)
+    pub fn close_signed(accounts: &[&AccountInfo; 4], signers: &[Signer]) -> ProgramResult {{
+        let metas = [
+            AccountMeta::readonly_signer(accounts[0].key()),
+            AccountMeta::readonly(accounts[1].key()),
+            AccountMeta::writable(accounts[2].key()),
+            AccountMeta::writable(accounts[3].key()),
+        ];
+        call({pascal_name}Instruction::Close, &metas, accounts, signers)
+    }}
+}}
 "#,
         pascal_name = pascal_name
     )
@@ -236,23 +452,29 @@ pub fn component_processor_rs(snake_name: &str, pascal_name: &str) -> String {
     format!(
         r#"//! {pascal_name} processor
 
-use ecs_core::{{require_keys_eq, require_signer, require_writable, EcsError}};
 use ephemeral_rollups_pinocchio::{{instruction::delegate_account, types::DelegateConfig}};
+use golt_runtime::authority::{{
+    add_allowed_system, init_allowlist, remove_allowed_system, require_allowed_system,
+}};
+use golt_runtime::entity::require_entity_owner;
+use golt_runtime::{{rent, require_keys_eq, require_signer, require_writable, GoltError}};
 use pinocchio::{{
     account_info::AccountInfo,
     instruction::{{Seed, Signer}},
     program_error::ProgramError,
     pubkey::Pubkey,
-    sysvars::{{rent::Rent, Sysvar}},
     ProgramResult,
 }};
 use pinocchio_system::instructions::CreateAccount;
 
 use crate::{{
     instruction::{pascal_name}Instruction,
-    state::{{derive_{snake_name}_pda, {pascal_name}, {upper_name}_SIZE}},
+    state::{{derive_{snake_name}_pda, verify_{snake_name}_pda, {pascal_name}, {upper_name}_SIZE}},
 }};
 
+/// Discriminator written into closed accounts (matches `golt_runtime::CLOSED_ACCOUNT_DISCRIMINATOR`)
+const CLOSED_DISCRIMINATOR: [u8; 8] = [0xff; 8];
+
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -263,6 +485,17 @@ pub fn process_instruction(
     match instruction {{
         {pascal_name}Instruction::Init => process_init(program_id, accounts),
         // TODO: Add more cases
+        {pascal_name}Instruction::Write {{ component }} => {{
+            process_write(program_id, accounts, &component)
+        }}
+        {pascal_name}Instruction::InitAllowlist => process_init_allowlist(program_id, accounts),
+        {pascal_name}Instruction::AddSystem {{ system }} => {{
+            process_add_system(program_id, accounts, &system)
+        }}
+        {pascal_name}Instruction::RemoveSystem {{ system }} => {{
+            process_remove_system(program_id, accounts, &system)
+        }}
+        {pascal_name}Instruction::Close => process_close(program_id, accounts),
         {pascal_name}Instruction::Delegate {{
             commit_frequency_ms,
             validator,
@@ -279,14 +512,13 @@ fn process_init(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult
     let _system_program = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
 
     require_signer!(payer);
-    require_writable!(component_account, EcsError::AccountNotWritable);
+    require_writable!(component_account, GoltError::AccountNotWritable);
 
     // Derive and verify PDA
     let (expected_pda, bump) = derive_{snake_name}_pda(entity.key(), program_id);
-    require_keys_eq!(*component_account.key(), expected_pda, EcsError::InvalidAccountData);
+    require_keys_eq!(*component_account.key(), expected_pda, GoltError::InvalidAccountData);
 
-    let rent = Rent::get()?;
-    let lamports = rent.minimum_balance({upper_name}_SIZE);
+    let lamports = rent()?.minimum_balance({upper_name}_SIZE);
 
     let bump_bytes = [bump];
     let signer_seeds: &[Seed] = &[
@@ -307,12 +539,136 @@ fn process_init(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult
 
     // Initialize component
     let mut data = component_account.try_borrow_mut_data()?;
+    if data.len() < {upper_name}_SIZE {{
+        return Err(ProgramError::AccountDataTooSmall);
+    }}
     let component = {pascal_name}::new(*entity.key(), bump);
     component.pack(&mut data);
 
     Ok(())
 }}
 
+/// Require the caller to be an allowlisted system
+///
+/// Call this in instructions that systems use to write the component.
+pub fn require_system_caller(
+    program_id: &Pubkey,
+    allowlist: &AccountInfo,
+    system_signer: &AccountInfo,
+) -> ProgramResult {{
+    require_allowed_system(allowlist, system_signer, program_id)
+}}
+
+/// Overwrite the component with data from an allowlisted system
+fn process_write(program_id: &Pubkey, accounts: &[AccountInfo], new_data: &[u8]) -> ProgramResult {{
+    let mut iter = accounts.iter();
+    let component_account = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let allowlist = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let system_signer = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    require_writable!(component_account, GoltError::AccountNotWritable);
+    require_system_caller(program_id, allowlist, system_signer)?;
+
+    // Verify account is owned by this program
+    if unsafe {{ component_account.owner() }} != program_id {{
+        return Err(ProgramError::IllegalOwner);
+    }}
+
+    let mut data = component_account.try_borrow_mut_data()?;
+    let current = {pascal_name}::unpack(&data).ok_or(GoltError::NotInitialized)?;
+    let new = {pascal_name}::unpack(new_data).ok_or(GoltError::InvalidAccountData)?;
+
+    // Systems may change the fields, not which entity's PDA this is
+    if new.entity != current.entity || new.bump != current.bump {{
+        return Err(GoltError::InvalidAccountData.into());
+    }}
+
+    new.pack(&mut data);
+    Ok(())
+}}
+
+/// Create the system allowlist, administered by the program's upgrade authority
+fn process_init_allowlist(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {{
+    let mut iter = accounts.iter();
+    let payer = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let admin = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let allowlist = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let program_data = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let _system_program = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    require_signer!(payer);
+    require_signer!(admin);
+    require_writable!(allowlist, GoltError::AccountNotWritable);
+
+    // Checks that `admin` is the upgrade authority recorded in `program_data`
+    init_allowlist(payer, allowlist, admin, program_data, program_id)
+}}
+
+/// Allow a system to write this component
+fn process_add_system(program_id: &Pubkey, accounts: &[AccountInfo], system: &Pubkey) -> ProgramResult {{
+    let mut iter = accounts.iter();
+    let admin = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let allowlist = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    add_allowed_system(allowlist, admin, system, program_id)
+}}
+
+/// Remove a system from the allowlist
+fn process_remove_system(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    system: &Pubkey,
+) -> ProgramResult {{
+    let mut iter = accounts.iter();
+    let admin = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let allowlist = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    remove_allowed_system(allowlist, admin, system, program_id)
+}}
+
+/// Close the component and send its rent to the recipient
+fn process_close(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {{
+    let mut iter = accounts.iter();
+    let owner = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let entity = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let component_account = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let recipient = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    // Entities are registry PDAs and can't sign, so their owner does
+    require_entity_owner(entity, owner, &crate::REGISTRY_ID)?;
+    require_writable!(component_account, GoltError::AccountNotWritable);
+    require_writable!(recipient, GoltError::AccountNotWritable);
+
+    // Verify account is owned by this program
+    if unsafe {{ component_account.owner() }} != program_id {{
+        return Err(ProgramError::IllegalOwner);
+    }}
+
+    let mut data = component_account.try_borrow_mut_data()?;
+    let component = {pascal_name}::unpack(&data).ok_or(GoltError::NotInitialized)?;
+
+    // Only the entity's own component can be closed
+    if !verify_{snake_name}_pda(component_account.key(), entity.key(), component.bump, program_id) {{
+        return Err(GoltError::InvalidAccountData.into());
+    }}
+
+    // Zero the data and mark it closed so it can't be re-initialised
+    // before the runtime reclaims it at the end of the transaction
+    data.fill(0);
+    data[0..8].copy_from_slice(&CLOSED_DISCRIMINATOR);
+    drop(data);
+
+    // Move the rent to the recipient
+    let lamports = component_account.lamports();
+    let mut recipient_lamports = recipient.try_borrow_mut_lamports()?;
+    *recipient_lamports = recipient_lamports
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    *component_account.try_borrow_mut_lamports()? = 0;
+
+    Ok(())
+}}
+
 /// Delegate component to Ephemeral Rollup
 fn process_delegate(
     program_id: &Pubkey,
@@ -331,10 +687,10 @@ fn process_delegate(
     let _delegation_program = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
 
     require_signer!(payer);
-    require_writable!(component_account, EcsError::AccountNotWritable);
+    require_writable!(component_account, GoltError::AccountNotWritable);
 
     // Verify owner program matches this program
-    require_keys_eq!(*owner_program.key(), *program_id, EcsError::InvalidProgramId);
+    require_keys_eq!(*owner_program.key(), *program_id, GoltError::InvalidProgramId);
 
     // Verify account is owned by this program
     if unsafe {{ component_account.owner() }} != program_id {{
@@ -343,11 +699,16 @@ fn process_delegate(
 
     // Get component data to extract entity and bump
     let data = component_account.try_borrow_data()?;
-    let component = {pascal_name}::unpack(&data).ok_or(EcsError::NotInitialized)?;
+    let component = {pascal_name}::unpack(&data).ok_or(GoltError::NotInitialized)?;
     let bump = component.bump;
     let entity_key = component.entity;
     drop(data);
 
+    // Verify the PDA with the stored bump
+    if !verify_{snake_name}_pda(component_account.key(), &entity_key, bump, program_id) {{
+        return Err(GoltError::InvalidAccountData.into());
+    }}
+
     // Build seeds for PDA signing
     let seeds: &[&[u8]] = &[ecs_core::seeds::{upper_name}, entity_key.as_ref()];
 
@@ -382,7 +743,7 @@ fn process_undelegate(accounts: &[AccountInfo]) -> ProgramResult {{
     let component_account = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
     let _buffer = iter.next().ok_or(ProgramError::NotEnoughAccountKeys)?;
 
-    require_writable!(component_account, EcsError::AccountNotWritable);
+    require_writable!(component_account, GoltError::AccountNotWritable);
 
     // The delegation program has already restored the account data from the buffer.
     // This callback is for any post-undelegation cleanup if needed.
@@ -428,29 +789,166 @@ fn process_instruction(
 }
 
 /// Generate error.rs for a component
-pub fn component_error_rs(pascal_name: &str) -> String {
+pub fn component_error_rs(pascal_name: &str, error_offset: u32) -> String {
     format!(
         r#"//! {pascal_name} errors
+//!
+//! Codes start at {error_offset} (`error_offset` in golt.toml). Variants are
+//! numbered in order and the doc comment is the message clients see.
 
-use pinocchio::program_error::ProgramError;
+use golt_runtime::ErrorCode;
 
-#[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, ErrorCode)]
+#[golt_error(offset = {error_offset})]
 pub enum {pascal_name}Error {{
     // TODO: Add your custom errors here
-    InvalidState = 6000,
+    /// Invalid component state
+    InvalidState,
 }}
+"#,
+        pascal_name = pascal_name,
+        error_offset = error_offset,
+    )
+}
+
+/// Generate fuzz/Cargo.toml for a component
+///
+/// The fuzz crate is its own workspace, so the project workspace never
+/// builds it for Solana.
+pub fn component_fuzz_cargo_toml(name: &str) -> String {
+    format!(
+        r#"[package]
+name = "{name}-fuzz"
+version = "0.0.0"
+publish = false
+edition = "2021"
+
+[package.metadata]
+cargo-fuzz = true
+
+[dependencies]
+libfuzzer-sys = "0.4"
+{name} = {{ path = "..", features = ["no-entrypoint", "arbitrary"] }}
+golt-runtime = {{ git = "https://github.com/gstohl/golt", features = ["arbitrary"] }}
+
+[workspace]
+members = ["."]
+
+[[bin]]
+name = "process_instruction"
+path = "fuzz_targets/process_instruction.rs"
+test = false
+doc = false
+bench = false
+"#,
+        name = name
+    )
+}
+
+/// Generate fuzz/fuzz_targets/process_instruction.rs for a component
+pub fn component_fuzz_target_rs(snake_name: &str, pascal_name: &str) -> String {
+    let upper_name = snake_name.to_uppercase();
 
-impl From<{pascal_name}Error> for ProgramError {{
-    fn from(e: {pascal_name}Error) -> Self {{
-        ProgramError::Custom(e as u32)
+    format!(
+        r#"//! Processor fuzzer for the {pascal_name} program
+//!
+//! Builds accounts and instruction data from the fuzz input and calls
+//! `process_instruction` with them. Accounts are mostly a valid {pascal_name},
+//! its entity or the allowlist, or use this program's IDs and PDAs, so inputs
+//! get past the account checks into the handlers. Off-chain, CPIs do nothing
+//! and return `Ok`. Run with `cargo fuzz run process_instruction` from the
+//! `fuzz` directory.
+
+#![no_main]
+
+use golt_runtime::arbitrary::Unstructured;
+use golt_runtime::fuzz::{{FuzzAccounts, KnownAccount}};
+use golt_runtime::{{
+    derive_allowlist_pda, derive_system_signer, program_ids, Allowlist, ALLOWLIST_SIZE,
+    ENTITY_DISCRIMINATOR, REGISTRY_ENTITY_OWNER_OFFSET,
+}};
+use libfuzzer_sys::fuzz_target;
+
+use {snake_name}::instruction::{pascal_name}Instruction;
+use {snake_name}::state::{{derive_{snake_name}_pda, {pascal_name}, {upper_name}_SIZE}};
+use {snake_name}::{{ID, REGISTRY_ID}};
+
+/// Size of golt-registry entity accounts
+const REGISTRY_ENTITY_SIZE: usize = 56;
+
+fuzz_target!(|input: &[u8]| {{
+    let mut u = Unstructured::new(input);
+    let Ok([entity, owner, system]) = u.arbitrary::<[[u8; 32]; 3]>() else {{
+        return;
+    }};
+    let (component, bump) = derive_{snake_name}_pda(&entity, &ID);
+    let (allowlist, allowlist_bump) = derive_allowlist_pda(&ID);
+    let (system_signer, _) = derive_system_signer(&system);
+
+    // A {pascal_name}, the active entity it belongs to and an allowlist,
+    // administered by the entity's owner, holding one system
+    let mut component_data = vec![0; {upper_name}_SIZE];
+    {pascal_name}::new(entity, bump).pack(&mut component_data);
+    let mut entity_data = vec![0; REGISTRY_ENTITY_SIZE];
+    entity_data[..8].copy_from_slice(&ENTITY_DISCRIMINATOR);
+    entity_data[REGISTRY_ENTITY_OWNER_OFFSET..REGISTRY_ENTITY_OWNER_OFFSET + 32]
+        .copy_from_slice(&owner);
+    entity_data[48] = 1;
+    let mut allowlist_state = Allowlist::new(owner, allowlist_bump);
+    let _ = allowlist_state.add(&system);
+    let mut allowlist_data = vec![0; ALLOWLIST_SIZE];
+    allowlist_state.pack(&mut allowlist_data);
+
+    let keys = [
+        ID,
+        REGISTRY_ID,
+        program_ids::SYSTEM,
+        program_ids::DELEGATION,
+        entity,
+        owner,
+        component,
+        allowlist,
+        system,
+        system_signer,
+    ];
+    let known = [
+        KnownAccount {{ key: component, owner: ID, data: &component_data }},
+        KnownAccount {{ key: entity, owner: REGISTRY_ID, data: &entity_data }},
+        KnownAccount {{ key: allowlist, owner: ID, data: &allowlist_data }},
+    ];
+    let Ok(mut accounts) = FuzzAccounts::arbitrary(&mut u, &keys, &known) else {{
+        return;
+    }};
+    let data = u.take_rest();
+
+    // Any bytes must decode or fail with an error, never panic
+    match {pascal_name}Instruction::unpack(data) {{
+        // The delegation CPI derives its PDAs with syscalls that only exist on-chain
+        Ok({pascal_name}Instruction::Delegate {{ .. }}) => return,
+        Ok(instruction) => {{
+            let _ = instruction.pack();
+        }}
+        Err(_) => {{}}
     }}
-}}
+    let _ = {snake_name}::processor::process_instruction(&ID, &accounts.infos(), data);
+}});
 "#,
-        pascal_name = pascal_name
+        pascal_name = pascal_name,
+        snake_name = snake_name,
+        upper_name = upper_name
     )
 }
 
+/// Generate fuzz/.gitignore for a component
+pub fn component_fuzz_gitignore() -> String {
+    r#"target
+corpus
+artifacts
+coverage
+"#
+    .to_string()
+}
+
 // ============================================================================
 // System templates
 // ============================================================================
@@ -468,6 +966,8 @@ license.workspace = true
 pinocchio.workspace = true
 pinocchio-pubkey.workspace = true
 pinocchio-system.workspace = true
+golt-runtime.workspace = true
+golt-macros.workspace = true
 ecs-core = {{ path = "../../core" }}
 # TODO: Add component dependencies as needed
 # health = {{ path = "../components/health", features = ["no-entrypoint"] }}
@@ -499,6 +999,14 @@ pub use instruction::*;
 
 // TODO: Replace with actual program ID after running `golt generate keypair {snake_name}`
 pinocchio_pubkey::declare_id!("11111111111111111111111111111111");
+
+/// This system's signer PDA (`["system_signer"]`)
+///
+/// Sign component CPIs with it so components that allowlist this system
+/// accept the write.
+pub fn system_signer() -> (pinocchio::pubkey::Pubkey, u8) {{
+    golt_runtime::authority::derive_system_signer(&ID)
+}}
 "#,
         pascal_name = pascal_name,
         snake_name = snake_name
@@ -546,6 +1054,9 @@ impl {pascal_name}Instruction {{
 pub fn system_processor_rs(pascal_name: &str) -> String {
     format!(
         r#"//! {pascal_name} system processor
+//!
+//! A system that is one function over an entity's components can use
+//! `#[golt_macros::system]` instead, which generates this processor.
 
 use pinocchio::{{
     account_info::AccountInfo,
@@ -573,25 +1084,24 @@ pub fn process_instruction(
 }
 
 /// Generate error.rs for a system
-pub fn system_error_rs(pascal_name: &str) -> String {
+pub fn system_error_rs(pascal_name: &str, error_offset: u32) -> String {
     format!(
         r#"//! {pascal_name} errors
+//!
+//! Codes start at {error_offset} (`error_offset` in golt.toml). Variants are
+//! numbered in order and the doc comment is the message clients see.
 
-use pinocchio::program_error::ProgramError;
+use golt_runtime::ErrorCode;
 
-#[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, ErrorCode)]
+#[golt_error(offset = {error_offset})]
 pub enum {pascal_name}Error {{
     // TODO: Add your custom errors here
-    InvalidOperation = 7000,
-}}
-
-impl From<{pascal_name}Error> for ProgramError {{
-    fn from(e: {pascal_name}Error) -> Self {{
-        ProgramError::Custom(e as u32)
-    }}
+    /// Invalid operation
+    InvalidOperation,
 }}
 "#,
-        pascal_name = pascal_name
+        pascal_name = pascal_name,
+        error_offset = error_offset,
     )
 }
@@ -0,0 +1,252 @@
+//! Golt account layout rules
+//!
+//! `golt-macros` lays out components, events and nested fields with these
+//! rules at compile time, and the `golt` CLI applies the same rules to parsed
+//! sources for the IDL. Keeping them in one crate keeps both in agreement:
+//!
+//! - Packed data starts with an 8-byte discriminator, followed by a version
+//!   byte for versioned components.
+//! - Fields follow in declaration order with no alignment. `#[padding(N)]`
+//!   skips `N` bytes before a field and `#[offset(N)]` places it.
+//! - Consecutive `#[bits(N)]` / `#[flags]` fields share one little-endian
+//!   word (see `bit_slots`).
+//! - `#[max_len(N)]` `Vec<T>` / `String` fields take a `u32` length plus room
+//!   for `N` elements.
+//! - `ComponentField` enums take a `u8` tag plus their largest variant.
+
+use sha2::{Digest, Sha256};
+
+/// Size of the discriminator at the start of packed data
+pub const DISCRIMINATOR_SIZE: usize = 8;
+
+/// Size of `golt_runtime::Fixed`, a Q32.32 number stored as an `i64`
+pub const FIXED_SIZE: usize = 8;
+
+/// Size of the `u32` length before a `#[max_len(N)]` field's elements
+pub const LEN_PREFIX_SIZE: usize = 4;
+
+/// Most bits sharing one word of bit fields
+pub const MAX_WORD_BITS: u32 = 64;
+
+/// Size in bytes of a primitive field type, by name
+///
+/// `Fixed` isn't included, see `is_fixed_path`.
+pub fn primitive_size(name: &str) -> Option<usize> {
+    match name {
+        "u8" | "i8" | "bool" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" | "f32" => Some(4),
+        "u64" | "i64" | "f64" => Some(8),
+        "u128" | "i128" => Some(16),
+        "Pubkey" => Some(32),
+        _ => None,
+    }
+}
+
+/// Whether a type path, given as its segments, names `golt_runtime::Fixed`
+///
+/// Only `Fixed`, `math::Fixed` and the `golt_runtime::` paths to it count.
+/// Other types named `Fixed` are nested fields like any other struct.
+pub fn is_fixed_path(segments: &[&str]) -> bool {
+    matches!(
+        segments,
+        ["Fixed"] | ["math", "Fixed"] | ["golt_runtime", "Fixed"] | ["golt_runtime", "math", "Fixed"]
+    )
+}
+
+/// Offset of the first field: the discriminator, then the version byte of
+/// versioned components
+pub fn header_size(versioned: bool) -> usize {
+    DISCRIMINATOR_SIZE + versioned as usize
+}
+
+/// Size of a `#[max_len(max_len)]` field of `elem_size`-byte elements (bytes
+/// for `String`), length prefix included
+pub fn var_len_size(max_len: usize, elem_size: usize) -> usize {
+    LEN_PREFIX_SIZE + max_len * elem_size
+}
+
+/// Size of a `ComponentField` enum: a `u8` tag, then the largest variant
+pub fn enum_size(variant_sizes: impl IntoIterator<Item = usize>) -> usize {
+    1 + variant_sizes.into_iter().max().unwrap_or(0)
+}
+
+/// Size of the word holding `bits` bits of bit fields: 1, 2, 4 or 8 bytes
+pub fn word_size(bits: u32) -> usize {
+    match bits {
+        0..=8 => 1,
+        9..=16 => 2,
+        17..=32 => 4,
+        _ => 8,
+    }
+}
+
+/// What `bit_slots` needs to know about a field
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BitInput {
+    /// Width of a `#[bits(N)]` / `#[flags]` field
+    pub width: Option<u32>,
+    /// Whether `#[offset]` or `#[padding]` moves the field
+    pub moved: bool,
+    /// Whether the field is `#[skip]`ped and not stored
+    pub skip: bool,
+}
+
+/// Position of a bit field in its group's storage word
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitSlot {
+    /// Bits below the field
+    pub shift: u32,
+    pub width: u32,
+    /// Size of the group's word in bytes: 1, 2, 4 or 8
+    pub word_size: usize,
+    /// First field of its group; the group's offset is taken here
+    pub first: bool,
+    /// Last field of its group; the offset advances past the word here
+    pub last: bool,
+}
+
+impl BitSlot {
+    /// Bits of the field, unshifted
+    pub fn mask(&self) -> u64 {
+        if self.width == MAX_WORD_BITS {
+            u64::MAX
+        } else {
+            (1 << self.width) - 1
+        }
+    }
+}
+
+/// `BitSlot` of each field, `None` for fields that aren't bit fields
+///
+/// Consecutive bit fields share a word of up to 64 bits, filled from the
+/// least significant bit. Skipped fields are ignored; any other field, a
+/// moved field or a full word starts a new group.
+pub fn bit_slots(fields: impl IntoIterator<Item = BitInput>) -> Vec<Option<BitSlot>> {
+    let mut slots: Vec<Option<BitSlot>> = Vec::new();
+    // Indices and widths of the open group's fields
+    let mut group: Vec<(usize, u32)> = Vec::new();
+
+    fn close(slots: &mut [Option<BitSlot>], group: &mut Vec<(usize, u32)>) {
+        let total: u32 = group.iter().map(|(_, width)| width).sum();
+        let mut shift = 0;
+        for (n, (index, width)) in group.iter().enumerate() {
+            slots[*index] = Some(BitSlot {
+                shift,
+                width: *width,
+                word_size: word_size(total),
+                first: n == 0,
+                last: n == group.len() - 1,
+            });
+            shift += width;
+        }
+        group.clear();
+    }
+
+    for field in fields {
+        let index = slots.len();
+        slots.push(None);
+        if field.skip {
+            continue;
+        }
+        let total: u32 = group.iter().map(|(_, width)| width).sum();
+        let full = field
+            .width
+            .is_some_and(|width| total + width > MAX_WORD_BITS);
+        if !group.is_empty() && (field.width.is_none() || field.moved || full) {
+            close(&mut slots, &mut group);
+        }
+        if let Some(width) = field.width {
+            group.push((index, width));
+        }
+    }
+    if !group.is_empty() {
+        close(&mut slots, &mut group);
+    }
+    slots
+}
+
+/// A field to place with `place_fields`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FieldSpec {
+    /// Size in bytes; ignored for bit fields
+    pub size: usize,
+    /// `#[offset(N)]`: absolute byte offset
+    pub offset: Option<usize>,
+    /// `#[padding(N)]`: bytes left before the field
+    pub padding: Option<usize>,
+    /// Width of a `#[bits(N)]` / `#[flags]` field
+    pub bits: Option<u32>,
+}
+
+/// Where `place_fields` put a field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub offset: usize,
+    /// The field's size, or the shared word's for bit fields
+    pub size: usize,
+    /// `(shift, width)` of a bit field within its word
+    pub bits: Option<(u32, u32)>,
+}
+
+/// Byte offsets of stored fields, packed from `start`
+///
+/// The derive macros follow the same steps, with nested `ComponentField`
+/// sizes only known once the program compiles.
+pub fn place_fields(fields: &[FieldSpec], start: usize) -> Vec<Placement> {
+    let slots = bit_slots(fields.iter().map(|f| BitInput {
+        width: f.bits,
+        moved: f.offset.is_some() || f.padding.is_some(),
+        skip: false,
+    }));
+
+    let mut placed = Vec::with_capacity(fields.len());
+    let mut offset = start;
+    // Offset of the open group of bit fields
+    let mut word_offset = start;
+    for (field, slot) in fields.iter().zip(slots) {
+        offset = field.offset.unwrap_or(offset + field.padding.unwrap_or(0));
+        match slot {
+            Some(slot) => {
+                if slot.first {
+                    word_offset = offset;
+                }
+                placed.push(Placement {
+                    offset: word_offset,
+                    size: slot.word_size,
+                    bits: Some((slot.shift, slot.width)),
+                });
+                if slot.last {
+                    offset += slot.word_size;
+                }
+            }
+            None => {
+                placed.push(Placement {
+                    offset,
+                    size: field.size,
+                    bits: None,
+                });
+                offset += field.size;
+            }
+        }
+    }
+    placed
+}
+
+/// The name padded with zeros or truncated to 8 bytes
+pub fn padded_discriminator(name: &str) -> [u8; 8] {
+    let mut disc = [0u8; 8];
+    let len = name.len().min(8);
+    disc[..len].copy_from_slice(&name.as_bytes()[..len]);
+    disc
+}
+
+/// First 8 bytes of `sha256("{namespace}:{name}")`, e.g. `component:health`
+///
+/// Unlike `padded_discriminator`, names sharing a prefix don't collide.
+pub fn hashed_discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("{}:{}", namespace, name));
+    let mut disc = [0u8; 8];
+    disc.copy_from_slice(&hash[..8]);
+    disc
+}
@@ -0,0 +1,170 @@
+//! Accounts derive macro implementation
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Expr, ExprArray, Fields, Ident};
+
+/// Parsed `#[account(...)]` options for one field
+#[derive(Default)]
+struct AccountOpts {
+    signer: bool,
+    writable: bool,
+    component: Option<syn::Path>,
+    owner: Option<Expr>,
+    seeds: Option<ExprArray>,
+    program: Option<Expr>,
+}
+
+fn parse_account_opts(field: &syn::Field) -> syn::Result<AccountOpts> {
+    let mut opts = AccountOpts::default();
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("account")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("signer") {
+                opts.signer = true;
+            } else if meta.path.is_ident("mut") {
+                opts.writable = true;
+            } else if meta.path.is_ident("component") {
+                opts.component = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("owner") {
+                opts.owner = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("seeds") {
+                opts.seeds = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("program") {
+                opts.program = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error(
+                    "expected one of: signer, mut, component, owner, seeds, program",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    if opts.program.is_some() && (opts.signer || opts.writable || opts.component.is_some()) {
+        return Err(syn::Error::new_spanned(
+            field,
+            "program accounts cannot be signer, mut or a component",
+        ));
+    }
+    if opts.owner.is_some() && opts.component.is_none() {
+        return Err(syn::Error::new_spanned(field, "owner requires component"));
+    }
+
+    Ok(opts)
+}
+
+pub fn derive_accounts_impl(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "Only named fields are supported")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "Only structs are supported")),
+    };
+
+    let lifetime = match input.generics.lifetimes().collect::<Vec<_>>().as_slice() {
+        [param] => param.lifetime.clone(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.generics,
+                "Accounts structs need exactly one lifetime, e.g. `struct Damage<'a>`",
+            ))
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut field_names: Vec<Ident> = Vec::new();
+    let mut checks = Vec::new();
+
+    for field in fields.iter() {
+        let field_name = field.ident.clone().unwrap();
+        let opts = parse_account_opts(field)?;
+
+        // Take the account, checking signer / writable / program key
+        let next = match (&opts.program, opts.signer, opts.writable) {
+            (Some(program), _, _) => quote! {
+                __ctx.next_program({
+                    #[allow(unused_imports)]
+                    use golt_runtime::program_ids::*;
+                    &#program
+                })?
+            },
+            (None, true, true) => quote! { __ctx.next_signer_writable()? },
+            (None, true, false) => quote! { __ctx.next_signer()? },
+            (None, false, true) => quote! { __ctx.next_writable()? },
+            (None, false, false) => quote! { __ctx.next()? },
+        };
+        checks.push(quote! { let #field_name = #next; });
+
+        // Component accounts: owner, discriminator and size
+        let owner = match &opts.owner {
+            Some(owner) => quote! { &#owner },
+            None => quote! { __program_id },
+        };
+        if let Some(component) = &opts.component {
+            checks.push(quote! {
+                golt_runtime::check_component_account::<#component>(#field_name, #owner)?;
+            });
+        }
+
+        // PDA address, using the stored bump for components
+        if let Some(seeds) = &opts.seeds {
+            let seed_exprs = seeds.elems.iter().map(|seed| {
+                match seed {
+                    Expr::Path(path)
+                        if path.path.get_ident().is_some_and(|id| field_names.contains(id)) =>
+                    {
+                        quote! { &#seed.key()[..] }
+                    }
+                    _ => quote! { ::core::convert::AsRef::<[u8]>::as_ref(&#seed) },
+                }
+            });
+            match &opts.component {
+                Some(component) => checks.push(quote! {
+                    golt_runtime::verify_component_pda::<#component>(
+                        #field_name,
+                        &[#(#seed_exprs),*],
+                        #owner,
+                    )?;
+                }),
+                None => checks.push(quote! {
+                    golt_runtime::pda::verify_pda(
+                        #field_name.key(),
+                        &[#(#seed_exprs),*],
+                        __program_id,
+                    )?;
+                }),
+            }
+        }
+
+        field_names.push(field_name);
+    }
+
+    let expanded = quote! {
+        impl #impl_generics golt_runtime::Accounts<#lifetime> for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn try_accounts(
+                __ctx: &mut golt_runtime::AccountContext<#lifetime>,
+                __program_id: &golt_runtime::prelude::Pubkey,
+            ) -> Result<Self, golt_runtime::prelude::ProgramError> {
+                #(#checks)*
+                Ok(Self { #(#field_names),* })
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Take and validate this instruction's accounts in one call
+            pub fn from_accounts(
+                accounts: &#lifetime [golt_runtime::prelude::AccountInfo],
+                program_id: &golt_runtime::prelude::Pubkey,
+            ) -> Result<Self, golt_runtime::prelude::ProgramError> {
+                golt_runtime::AccountContext::new(accounts).parse(program_id)
+            }
+        }
+    };
+
+    Ok(expanded)
+}
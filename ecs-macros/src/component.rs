@@ -1,11 +1,16 @@
 //! Component derive macro implementation
 
 use darling::FromDeriveInput;
+use heck::ToShoutySnakeCase;
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DeriveInput, Field, Fields};
 
-use crate::utils::{generate_pack_field, generate_unpack_field, string_to_discriminator, type_size};
+use crate::utils::{
+    accessors_from, bit_slots, generate_arbitrary, generate_field_accessors, generate_pack_field, generate_pack_var_len, generate_unpack_field,
+    generate_unpack_var_len, hashed_discriminator, padded_discriminator, type_size,
+    var_len_field, word_type, BitSlot, Offset, VarLenField,
+};
 
 #[derive(FromDeriveInput)]
 #[darling(attributes(component))]
@@ -13,6 +18,24 @@ struct ComponentArgs {
     seed: String,
     #[darling(default)]
     discriminator: Option<String>,
+    /// Use `sha256("component:{discriminator}")` instead of the padded string
+    #[darling(default)]
+    hashed: bool,
+    /// Layout version, stored in the byte after the discriminator
+    #[darling(default)]
+    version: Option<u8>,
+    /// Function upgrading older layouts: `fn(version: u8, data: &[u8]) -> Option<Self>`
+    #[darling(default)]
+    migrate: Option<syn::Path>,
+    /// Size of accounts written before the component was versioned
+    #[darling(default)]
+    legacy_size: Option<usize>,
+    /// Zeroed bytes kept free at the end of the account for later fields
+    #[darling(default)]
+    reserved: usize,
+    /// Implement `Delegatable` and generate the delegation handlers
+    #[darling(default)]
+    delegatable: bool,
 }
 
 pub fn derive_component_impl(input: DeriveInput) -> syn::Result<TokenStream> {
@@ -21,7 +44,11 @@ pub fn derive_component_impl(input: DeriveInput) -> syn::Result<TokenStream> {
     let name = &input.ident;
     let seed = &args.seed;
     let discriminator_str = args.discriminator.as_deref().unwrap_or(&args.seed);
-    let discriminator = string_to_discriminator(discriminator_str);
+    let discriminator = if args.hashed {
+        hashed_discriminator("component", discriminator_str)
+    } else {
+        padded_discriminator(discriminator_str)
+    };
 
     let fields = match &input.data {
         Data::Struct(data) => match &data.fields {
@@ -31,42 +58,326 @@ pub fn derive_component_impl(input: DeriveInput) -> syn::Result<TokenStream> {
         _ => return Err(syn::Error::new_spanned(name, "Only structs are supported")),
     };
 
+    if args.version == Some(0) {
+        return Err(syn::Error::new_spanned(
+            name,
+            "component version must be at least 1 (0 means unversioned)",
+        ));
+    }
+    if args.migrate.is_some() && args.version.is_none() {
+        return Err(syn::Error::new_spanned(
+            name,
+            "migrate requires a component version",
+        ));
+    }
+    if args.legacy_size.is_some() && args.migrate.is_none() {
+        return Err(syn::Error::new_spanned(
+            name,
+            "legacy_size requires a migrate function",
+        ));
+    }
+
     // Calculate size and generate pack/unpack code
-    let mut offset = 8usize; // Start after discriminator
+    // Start after discriminator and, for versioned components, the version byte
+    let mut offset = Offset::new(golt_layout::header_size(args.version.is_some()));
     let mut pack_fields = Vec::new();
     let mut unpack_fields = Vec::new();
     let mut field_names = Vec::new();
+    let mut raw_accessors = Vec::new();
+    let mut view_getters = Vec::new();
+    let mut view_setters = Vec::new();
+    let mut bump_field = None;
+    let mut entity_offset = None;
+    let mut entity_field = None;
+    let mut capacity_checks = Vec::new();
+    let mut capacity_helpers = Vec::new();
+    let mut layout = Vec::new();
+    let mut layout_checks = Vec::new();
+    let mut arbitrary_fields = Vec::new();
+    let slots = bit_slots(fields)?;
+    // Offset of the open group of bit fields
+    let mut word_offset = offset.clone();
 
-    for field in fields.iter() {
+    for (field, slot) in fields.iter().zip(&slots) {
         let field_name = field.ident.as_ref().unwrap();
         field_names.push(field_name.clone());
 
         // Check for skip attribute
         let has_skip = field.attrs.iter().any(|attr| attr.path().is_ident("skip"));
         if has_skip {
+            unpack_fields.push(quote! { let #field_name = Default::default(); });
+            arbitrary_fields.push(quote! { #field_name: Default::default() });
+            continue;
+        }
+
+        // Gaps before the field are zeroed so they never hold stale bytes
+        let gap_start = offset.clone();
+        let has_gap = match (field_attr_usize(field, "offset")?, field_attr_usize(field, "padding")?) {
+            (Some(_), Some(_)) => {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "use either #[offset] or #[padding], not both",
+                ));
+            }
+            (Some(start), None) => {
+                let message = format!(
+                    "#[offset({})] on `{}` overlaps the previous field",
+                    start, field_name
+                );
+                match offset.as_fixed() {
+                    Some(end) if start < end => return Err(syn::Error::new_spanned(field, message)),
+                    Some(_) => {}
+                    None => layout_checks.push(quote! { assert!(#start >= #offset, #message); }),
+                }
+                offset = Offset::new(start);
+                true
+            }
+            (None, Some(padding)) => {
+                offset.add(padding);
+                true
+            }
+            (None, None) => false,
+        };
+        if has_gap {
+            pack_fields.push(quote! { data[#gap_start..#offset].fill(0); });
+        }
+
+        if let Some(slot) = slot {
+            if slot.first {
+                word_offset = offset.clone();
+            }
+            let bits = generate_bit_field(name, field, slot, &word_offset);
+            pack_fields.push(bits.pack);
+            unpack_fields.push(bits.unpack);
+            capacity_checks.extend(bits.check);
+            let arbitrary = bits.arbitrary;
+            arbitrary_fields.push(quote! { #field_name: #arbitrary });
+            raw_accessors.push(bits.accessors.raw);
+            view_getters.push(bits.accessors.getter);
+            view_setters.push(bits.accessors.setter);
+            layout.push(field_layout(field_name, &word_offset, slot.word_size, Some(slot)));
+            if slot.last {
+                offset.add(slot.word_size);
+            }
             continue;
         }
 
         // Check for pda_bump attribute (always last, size 1)
         let is_bump = field.attrs.iter().any(|attr| attr.path().is_ident("pda_bump"));
 
-        let size = type_size(&field.ty).unwrap_or(0);
+        // Vec / String fields get a fixed region of #[max_len(N)] elements
+        if let Some(var) = var_len_field(field)? {
+            pack_fields.push(generate_pack_var_len(field, &var, &offset)?);
+            unpack_fields.push(generate_unpack_var_len(field, &var, &offset)?);
+            layout.push(field_layout(field_name, &offset, var.size(), None));
+            offset.add(var.size());
+
+            let max_len = var.max_len;
+            capacity_checks.push(quote! {
+                if self.#field_name.len() > #max_len {
+                    return Err(golt_runtime::GoltError::CapacityExceeded);
+                }
+            });
+            capacity_helpers.push(generate_capacity_helpers(field_name, &var));
+            arbitrary_fields.push(match var.elem {
+                Some(_) => quote! { #field_name: golt_runtime::fuzz::arbitrary_vec(u, #max_len)? },
+                None => quote! { #field_name: golt_runtime::fuzz::arbitrary_string(u, #max_len)? },
+            });
+            continue;
+        }
+
+        // None for nested ComponentField types
+        let size = type_size(&field.ty);
 
-        pack_fields.push(generate_pack_field(field, offset));
-        unpack_fields.push(generate_unpack_field(field, offset));
+        pack_fields.push(generate_pack_field(field, &offset)?);
+        unpack_fields.push(generate_unpack_field(field, &offset)?);
+        arbitrary_fields.push(quote! { #field_name: u.arbitrary()? });
+        if let Some(accessors) = generate_field_accessors(name, field, &offset) {
+            raw_accessors.push(accessors.raw);
+            view_getters.push(accessors.getter);
+            view_setters.push(accessors.setter);
+        }
 
-        offset += size;
+        let field_offset = offset.clone();
+        offset.advance(&field.ty);
+        let mut field_size = Offset::new(0);
+        field_size.advance(&field.ty);
+        layout.push(field_layout(field_name, &field_offset, &field_size, None));
 
-        if is_bump && size != 1 {
+        if is_bump && size != Some(1) {
             return Err(syn::Error::new_spanned(
                 field,
                 "pda_bump field must be u8",
             ));
         }
+        if is_bump {
+            bump_field = Some((field_name.clone(), field_offset.clone()));
+        }
+
+        // Check for entity attribute (the owning entity's key)
+        if field.attrs.iter().any(|attr| attr.path().is_ident("entity")) {
+            if size != Some(32) {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "entity field must be [u8; 32] or Pubkey",
+                ));
+            }
+            if entity_offset.is_some() {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "only one field can be marked #[entity]",
+                ));
+            }
+            entity_offset = Some(field_offset);
+            entity_field = Some(field_name.clone());
+        }
     }
 
+    let reserved = args.reserved;
+    if reserved > 0 {
+        pack_fields.push(quote! { data[#offset..#offset + #reserved].fill(0); });
+        offset.add(reserved);
+    }
     let total_size = offset;
     let disc_bytes = discriminator;
+    let version = args.version.unwrap_or(0);
+
+    let (version_check, version_pack) = if args.version.is_some() {
+        // Legacy accounts may hold anything in the version byte
+        let legacy_check = args.legacy_size.map(|legacy_size| {
+            quote! {
+                if data.len() == #legacy_size {
+                    return None;
+                }
+            }
+        });
+        (
+            quote! {
+                if data[8] != Self::VERSION {
+                    return None;
+                }
+                #legacy_check
+            },
+            quote! {
+                data[8] = Self::VERSION;
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    let (bump_offset, verify_pda_fn) = match &bump_field {
+        Some((bump_name, bump_offset)) => (
+            quote! { Some(#bump_offset) },
+            quote! {
+                /// Verify `account_key` is this component's PDA for `entity`
+                ///
+                /// Uses the stored bump with `create_program_address`, avoiding
+                /// the on-chain bump search of `derive_pda_with_entity`.
+                pub fn verify_pda_with_entity(
+                    &self,
+                    account_key: &golt_runtime::prelude::Pubkey,
+                    entity: &[u8; 32],
+                    program_id: &golt_runtime::prelude::Pubkey,
+                ) -> Result<(), golt_runtime::GoltError> {
+                    golt_runtime::pda::verify_pda_with_bump(
+                        account_key,
+                        &[Self::SEED, entity.as_ref()],
+                        self.#bump_name,
+                        program_id,
+                    )
+                }
+            },
+        ),
+        None => (quote! { None }, quote! {}),
+    };
+
+    let validate_fn = if capacity_checks.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn validate(&self) -> Result<(), golt_runtime::GoltError> {
+                #(#capacity_checks)*
+                Ok(())
+            }
+        }
+    };
+
+    let entity_key_offset = match &entity_offset {
+        Some(entity_offset) => quote! { Some(#entity_offset) },
+        None => quote! { None },
+    };
+    let entity_impl = entity_offset.map(|entity_offset| {
+        quote! {
+            impl golt_runtime::EntityComponent for #name {
+                const ENTITY_OFFSET: usize = #entity_offset;
+            }
+        }
+    });
+
+    let delegatable_impl = if args.delegatable {
+        let (Some(entity_name), Some((bump_name, _))) = (&entity_field, &bump_field) else {
+            return Err(syn::Error::new_spanned(
+                name,
+                "delegatable components need an #[entity] field and a #[pda_bump] field",
+            ));
+        };
+        Some(generate_delegatable(name, entity_name, bump_name))
+    } else {
+        None
+    };
+
+    let migrate_impl = args.migrate.as_ref().map(|migrate| {
+        quote! {
+            impl golt_runtime::Migrate for #name {
+                fn migrate(version: u8, data: &[u8]) -> Option<Self> {
+                    #migrate(version, data)
+                }
+            }
+        }
+    });
+
+    let legacy_size = match args.legacy_size {
+        Some(legacy_size) => {
+            let message = format!(
+                "legacy_size must differ from the size of `{}`, or legacy accounts can't be told apart",
+                name
+            );
+            match total_size.as_fixed() {
+                Some(size) if size == legacy_size => return Err(syn::Error::new_spanned(name, message)),
+                Some(_) => {}
+                None => layout_checks.push(quote! { assert!(#legacy_size != #total_size, #message); }),
+            }
+            quote! { Some(#legacy_size) }
+        }
+        None => quote! { None },
+    };
+
+    // Offsets after nested fields are only known once the program compiles
+    let layout_checks = (!layout_checks.is_empty()).then(|| {
+        quote! {
+            const _: () = {
+                #(#layout_checks)*
+            };
+        }
+    });
+
+    let arbitrary_impl = generate_arbitrary(
+        name,
+        &input.generics,
+        quote! {
+            Ok(Self {
+                #(#arbitrary_fields),*
+            })
+        },
+        Some(quote! { golt_runtime::fuzz::check_component_roundtrip::<#name>(); }),
+    );
+
+    let view_trait = syn::Ident::new(&format!("{}View", name), name.span());
+    let view_mut_trait = syn::Ident::new(&format!("{}ViewMut", name), name.span());
+    let view_doc = format!("Zero-copy field getters for borrowed `{}` account data", name);
+    let view_mut_doc = format!("Zero-copy field setters for mutably borrowed `{}` account data", name);
+    let vis = &input.vis;
 
     let expanded = quote! {
         impl golt_runtime::Component for #name {
@@ -75,6 +386,13 @@ pub fn derive_component_impl(input: DeriveInput) -> syn::Result<TokenStream> {
             ];
             const SEED: &'static [u8] = #seed.as_bytes();
             const SIZE: usize = #total_size;
+            const VERSION: u8 = #version;
+            const LEGACY_SIZE: Option<usize> = #legacy_size;
+            const BUMP_OFFSET: Option<usize> = #bump_offset;
+            const ENTITY_KEY_OFFSET: Option<usize> = #entity_key_offset;
+            const LAYOUT: &'static [golt_runtime::FieldLayout] = &[
+                #(#layout),*
+            ];
 
             fn unpack(data: &[u8]) -> Option<Self> {
                 if data.len() < Self::SIZE {
@@ -83,6 +401,7 @@ pub fn derive_component_impl(input: DeriveInput) -> syn::Result<TokenStream> {
                 if data[0..8] != Self::DISCRIMINATOR {
                     return None;
                 }
+                #version_check
 
                 #(#unpack_fields)*
 
@@ -93,8 +412,11 @@ pub fn derive_component_impl(input: DeriveInput) -> syn::Result<TokenStream> {
 
             fn pack(&self, data: &mut [u8]) {
                 data[0..8].copy_from_slice(&Self::DISCRIMINATOR);
+                #version_pack
                 #(#pack_fields)*
             }
+
+            #validate_fn
         }
 
         impl #name {
@@ -108,8 +430,298 @@ pub fn derive_component_impl(input: DeriveInput) -> syn::Result<TokenStream> {
                     program_id,
                 )
             }
+
+            #verify_pda_fn
+
+            /// Borrow an account as a zero-copy view, checking owner, size, discriminator and version once
+            pub fn view<'a>(
+                account: &'a golt_runtime::prelude::AccountInfo,
+                program_id: &golt_runtime::prelude::Pubkey,
+            ) -> Result<golt_runtime::ComponentRef<'a, Self>, golt_runtime::prelude::ProgramError> {
+                golt_runtime::ComponentRef::new(account, program_id)
+            }
+
+            /// Mutably borrow an account as a zero-copy view, checking owner, size, discriminator and version once
+            pub fn view_mut<'a>(
+                account: &'a golt_runtime::prelude::AccountInfo,
+                program_id: &golt_runtime::prelude::Pubkey,
+            ) -> Result<golt_runtime::ComponentRefMut<'a, Self>, golt_runtime::prelude::ProgramError> {
+                golt_runtime::ComponentRefMut::new(account, program_id)
+            }
+
+            #(#raw_accessors)*
+
+            #(#capacity_helpers)*
+        }
+
+        #[doc = #view_doc]
+        #vis trait #view_trait: golt_runtime::ComponentBytes {
+            #(#view_getters)*
         }
+
+        impl<'a> #view_trait for golt_runtime::ComponentRef<'a, #name> {}
+        impl<'a> #view_trait for golt_runtime::ComponentRefMut<'a, #name> {}
+
+        #[doc = #view_mut_doc]
+        #vis trait #view_mut_trait: golt_runtime::ComponentBytesMut {
+            #(#view_setters)*
+        }
+
+        impl<'a> #view_mut_trait for golt_runtime::ComponentRefMut<'a, #name> {}
+
+        #entity_impl
+
+        #migrate_impl
+
+        #delegatable_impl
+
+        #arbitrary_impl
+
+        #layout_checks
     };
 
     Ok(expanded)
 }
+
+/// Value of a field attribute like `#[offset(N)]`
+fn field_attr_usize(field: &Field, name: &str) -> syn::Result<Option<usize>> {
+    field
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident(name))
+        .map(|attr| attr.parse_args::<syn::LitInt>()?.base10_parse::<usize>())
+        .transpose()
+}
+
+/// `LAYOUT` entry for a field
+fn field_layout(
+    name: &syn::Ident,
+    offset: &Offset,
+    size: impl quote::ToTokens,
+    slot: Option<&BitSlot>,
+) -> TokenStream {
+    let name = name.to_string();
+    let bits = match slot {
+        Some(slot) => {
+            let shift = slot.shift as u8;
+            let width = slot.width as u8;
+            quote! { Some(golt_runtime::BitRange { shift: #shift, width: #width }) }
+        }
+        None => quote! { None },
+    };
+    quote! {
+        golt_runtime::FieldLayout {
+            name: #name,
+            offset: #offset,
+            size: #size,
+            bits: #bits,
+        }
+    }
+}
+
+/// Generated code for one `#[bits(N)]` / `#[flags]` field
+struct BitFieldCode {
+    pack: TokenStream,
+    unpack: TokenStream,
+    /// `validate` check that the value fits in its bits
+    check: Option<TokenStream>,
+    accessors: crate::utils::FieldAccessors,
+    /// Arbitrary value that fits in the field's bits
+    arbitrary: TokenStream,
+}
+
+/// Pack, unpack and accessors of a bit field in the word at `word_offset`
+///
+/// `pack` zeroes the word at the group's first field and ORs each field in.
+fn generate_bit_field(
+    component: &syn::Ident,
+    field: &Field,
+    slot: &BitSlot,
+    word_offset: &Offset,
+) -> BitFieldCode {
+    let name = field.ident.as_ref().unwrap();
+    let ty = &field.ty;
+    let word = word_type(slot);
+    let size = slot.word_size;
+    let shift = slot.shift;
+    let mask = proc_macro2::Literal::u64_unsuffixed(slot.mask());
+    let shifted_mask = proc_macro2::Literal::u64_unsuffixed(slot.mask() << slot.shift);
+    let is_bool = matches!(ty, syn::Type::Path(p) if p.path.is_ident("bool"));
+
+    let load = quote! { #word::from_le_bytes(data[#word_offset..#word_offset + #size].try_into().unwrap()) };
+    let bits = quote! { ((#load >> #shift) & #mask) };
+    let read = if is_bool {
+        quote! { #bits != 0 }
+    } else {
+        quote! { #bits as #ty }
+    };
+    let store = |value: TokenStream, current: TokenStream| {
+        quote! {
+            let word = #current | (((#value as #word) & #mask) << #shift);
+            data[#word_offset..#word_offset + #size].copy_from_slice(&word.to_le_bytes());
+        }
+    };
+
+    let clear = slot.first.then(|| quote! { data[#word_offset..#word_offset + #size].fill(0); });
+    let pack_store = store(quote! { self.#name }, load.clone());
+    let pack = quote! {
+        #clear
+        #pack_store
+    };
+    let unpack = quote! {
+        let #name: #ty = #read;
+    };
+
+    // Values too wide for their bits would be cut off by `pack`
+    let check = (!is_bool && slot.mask() != u64::MAX).then(|| {
+        let width = slot.width;
+        quote! {
+            if (self.#name as u64) >> #width != 0 {
+                return Err(golt_runtime::GoltError::CapacityExceeded);
+            }
+        }
+    });
+
+    let write = store(quote! { value }, quote! { (#load & !#shifted_mask) });
+    let accessors = accessors_from(component, name, &quote! { #ty }, &read, &write);
+    let arbitrary = if is_bool {
+        quote! { u.arbitrary()? }
+    } else {
+        quote! { u.int_in_range(0..=#mask)? }
+    };
+
+    BitFieldCode {
+        pack,
+        unpack,
+        check,
+        accessors,
+        arbitrary,
+    }
+}
+
+/// `Delegatable` from the `#[entity]` and `#[pda_bump]` fields, plus the
+/// delegation instruction handlers
+fn generate_delegatable(
+    name: &syn::Ident,
+    entity_name: &syn::Ident,
+    bump_name: &syn::Ident,
+) -> TokenStream {
+    quote! {
+        impl golt_runtime::Delegatable for #name {
+            fn get_entity(&self) -> &golt_runtime::prelude::Pubkey {
+                &self.#entity_name
+            }
+
+            fn get_bump(&self) -> u8 {
+                self.#bump_name
+            }
+        }
+
+        impl #name {
+            /// Handle `DELEGATE_DISCRIMINATOR`, `UNDELEGATE_DISCRIMINATOR` and the
+            /// undelegate callback, returning `None` for other instructions
+            ///
+            /// Call it before dispatching the program's own instructions.
+            /// `registry_program_id` is the entity registry holding the entities.
+            pub fn process_delegation_instruction(
+                program_id: &golt_runtime::prelude::Pubkey,
+                registry_program_id: &golt_runtime::prelude::Pubkey,
+                accounts: &[golt_runtime::prelude::AccountInfo],
+                instruction_data: &[u8],
+            ) -> Option<golt_runtime::prelude::ProgramResult> {
+                golt_runtime::process_delegation_instruction::<Self>(
+                    program_id,
+                    registry_program_id,
+                    accounts,
+                    instruction_data,
+                )
+            }
+
+            /// Delegate this component's account to an Ephemeral Rollup
+            ///
+            /// Accounts: `[payer (signer), authority (signer), entity or allowlist,
+            /// component (writable), buffer, delegation record, delegation metadata,
+            /// this program, system program, delegation program]`. The authority must
+            /// own the entity or be an allowlisted system signer.
+            pub fn process_delegate(
+                program_id: &golt_runtime::prelude::Pubkey,
+                registry_program_id: &golt_runtime::prelude::Pubkey,
+                accounts: &[golt_runtime::prelude::AccountInfo],
+                commit_frequency_ms: u32,
+                validator: golt_runtime::prelude::Pubkey,
+            ) -> golt_runtime::prelude::ProgramResult {
+                golt_runtime::process_delegate::<Self>(
+                    program_id,
+                    registry_program_id,
+                    accounts,
+                    commit_frequency_ms,
+                    validator,
+                )
+            }
+
+            /// Commit this component's account and undelegate it from the Ephemeral Rollup
+            ///
+            /// Accounts: `[payer (signer), authority (signer), entity or allowlist,
+            /// component (writable), magic context (writable), magic program]`.
+            pub fn process_undelegate(
+                program_id: &golt_runtime::prelude::Pubkey,
+                registry_program_id: &golt_runtime::prelude::Pubkey,
+                accounts: &[golt_runtime::prelude::AccountInfo],
+            ) -> golt_runtime::prelude::ProgramResult {
+                golt_runtime::process_undelegate::<Self>(program_id, registry_program_id, accounts)
+            }
+        }
+    }
+}
+
+/// `{FIELD}_MAX_LEN` plus `push_{field}` (Vec) or `set_{field}` (String)
+fn generate_capacity_helpers(field_name: &syn::Ident, var: &VarLenField) -> TokenStream {
+    let max_len = var.max_len;
+    let const_name = syn::Ident::new(
+        &format!("{}_MAX_LEN", field_name.to_string().to_shouty_snake_case()),
+        field_name.span(),
+    );
+    let const_doc = format!("Capacity of `{}`", field_name);
+
+    let helper = match var.elem {
+        Some(elem) => {
+            let push = syn::Ident::new(&format!("push_{}", field_name), field_name.span());
+            let doc = format!("Append to `{}`, failing with `CapacityExceeded` when it is full", field_name);
+            quote! {
+                #[doc = #doc]
+                pub fn #push(&mut self, item: #elem) -> Result<(), golt_runtime::GoltError> {
+                    if self.#field_name.len() >= Self::#const_name {
+                        return Err(golt_runtime::GoltError::CapacityExceeded);
+                    }
+                    self.#field_name.push(item);
+                    Ok(())
+                }
+            }
+        }
+        None => {
+            let set = syn::Ident::new(&format!("set_{}", field_name), field_name.span());
+            let doc = format!(
+                "Replace `{}`, failing with `CapacityExceeded` if it is longer than {} bytes",
+                field_name, max_len
+            );
+            quote! {
+                #[doc = #doc]
+                pub fn #set(&mut self, value: &str) -> Result<(), golt_runtime::GoltError> {
+                    if value.len() > Self::#const_name {
+                        return Err(golt_runtime::GoltError::CapacityExceeded);
+                    }
+                    self.#field_name.clear();
+                    self.#field_name.push_str(value);
+                    Ok(())
+                }
+            }
+        }
+    };
+
+    quote! {
+        #[doc = #const_doc]
+        pub const #const_name: usize = #max_len;
+
+        #helper
+    }
+}
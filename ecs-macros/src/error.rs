@@ -0,0 +1,148 @@
+//! ErrorCode derive macro implementation
+
+use darling::FromDeriveInput;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitStr};
+
+#[derive(FromDeriveInput)]
+#[darling(attributes(golt_error))]
+struct ErrorArgs {
+    /// First error code; variants are numbered from here in declaration order
+    offset: u32,
+}
+
+/// Message of a variant: `#[golt_error(msg = "...")]`, else its doc comment, else its name
+fn variant_message(attrs: &[Attribute], name: &str) -> syn::Result<String> {
+    let mut message = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("golt_error")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("msg") {
+                let lit: LitStr = meta.value()?.parse()?;
+                message = Some(lit.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `msg = \"...\"`"))
+            }
+        })?;
+    }
+    if let Some(message) = message {
+        return Ok(message);
+    }
+
+    let doc = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta.require_name_value().ok()?.value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) => Some(s.value().trim().to_string()),
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(if doc.is_empty() { name.to_string() } else { doc })
+}
+
+pub fn derive_error_code_impl(input: DeriveInput) -> syn::Result<TokenStream> {
+    let args = ErrorArgs::from_derive_input(&input)?;
+    let name = &input.ident;
+
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => return Err(syn::Error::new_spanned(name, "ErrorCode can only be derived for enums")),
+    };
+
+    let mut codes = Vec::new();
+    let mut idents = Vec::new();
+    let mut names = Vec::new();
+    let mut messages = Vec::new();
+
+    for (index, variant) in variants.iter().enumerate() {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(variant, "error variants cannot have fields"));
+        }
+
+        let code = args
+            .offset
+            .checked_add(index as u32)
+            .ok_or_else(|| syn::Error::new_spanned(variant, "error code overflows u32"))?;
+
+        // Explicit discriminants must agree, so `as u32` and `code()` never differ
+        if let Some((_, expr)) = &variant.discriminant {
+            let explicit = match expr {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(int), ..
+                }) => int.base10_parse::<u32>()?,
+                _ => return Err(syn::Error::new_spanned(expr, "expected an integer literal")),
+            };
+            if explicit != code {
+                return Err(syn::Error::new_spanned(
+                    expr,
+                    format!("expected {} (offset + variant index), or no discriminant", code),
+                ));
+            }
+        }
+
+        let ident = variant.ident.clone();
+        names.push(ident.to_string());
+        messages.push(variant_message(&variant.attrs, &ident.to_string())?);
+        idents.push(ident);
+        codes.push(code);
+    }
+
+    let offset = args.offset;
+    let count = codes.len() as u32;
+    let range_message = format!(
+        "`{}` has {} variants, but a program only gets PROGRAM_ERROR_RANGE error codes",
+        name, count
+    );
+
+    let expanded = quote! {
+        // The range is defined by golt-runtime, so it is checked when the program compiles
+        const _: () = {
+            assert!(#count <= golt_runtime::PROGRAM_ERROR_RANGE, #range_message);
+        };
+
+        impl golt_runtime::ErrorCode for #name {
+            const OFFSET: u32 = #offset;
+
+            const ERROR_TABLE: &'static [golt_runtime::ErrorInfo] = &[
+                #(golt_runtime::ErrorInfo {
+                    code: #codes,
+                    name: #names,
+                    message: #messages,
+                }),*
+            ];
+
+            fn code(&self) -> u32 {
+                match self {
+                    #(Self::#idents => #codes,)*
+                }
+            }
+
+            fn from_code(code: u32) -> Option<Self> {
+                match code {
+                    #(#codes => Some(Self::#idents),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl core::fmt::Display for #name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(golt_runtime::ErrorCode::message(self))
+            }
+        }
+
+        impl From<#name> for golt_runtime::prelude::ProgramError {
+            fn from(e: #name) -> Self {
+                golt_runtime::prelude::ProgramError::Custom(golt_runtime::ErrorCode::code(&e))
+            }
+        }
+    };
+
+    Ok(expanded)
+}
@@ -0,0 +1,82 @@
+//! Event derive macro implementation
+
+use darling::FromDeriveInput;
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields};
+
+use crate::utils::{generate_pack_field, generate_unpack_field, hashed_discriminator, Offset};
+
+#[derive(FromDeriveInput)]
+#[darling(attributes(event))]
+struct EventArgs {
+    /// Name hashed into the discriminator, defaults to the snake_case struct name
+    #[darling(default)]
+    discriminator: Option<String>,
+}
+
+pub fn derive_event_impl(input: DeriveInput) -> syn::Result<TokenStream> {
+    let args = EventArgs::from_derive_input(&input)?;
+
+    let name = &input.ident;
+    let discriminator_str = args
+        .discriminator
+        .unwrap_or_else(|| name.to_string().to_snake_case());
+    // Always hashed: padded names sharing their first 8 bytes would collide
+    let disc_bytes = hashed_discriminator("event", &discriminator_str);
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "Only named fields are supported")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "Only structs are supported")),
+    };
+
+    // Fields follow the discriminator
+    let mut offset = Offset::new(golt_layout::DISCRIMINATOR_SIZE);
+    let mut pack_fields = Vec::new();
+    let mut unpack_fields = Vec::new();
+    let mut field_names = Vec::new();
+
+    for field in fields.iter() {
+        field_names.push(field.ident.clone().unwrap());
+        pack_fields.push(generate_pack_field(field, &offset)?);
+        unpack_fields.push(generate_unpack_field(field, &offset)?);
+        offset.advance(&field.ty);
+    }
+
+    let total_size = offset;
+
+    let expanded = quote! {
+        impl golt_runtime::Event for #name {
+            const DISCRIMINATOR: [u8; 8] = [
+                #(#disc_bytes),*
+            ];
+            const SIZE: usize = #total_size;
+
+            fn pack(&self, data: &mut [u8]) {
+                data[0..8].copy_from_slice(&Self::DISCRIMINATOR);
+                #(#pack_fields)*
+            }
+
+            fn unpack(data: &[u8]) -> Option<Self> {
+                if data.len() < Self::SIZE {
+                    return None;
+                }
+                if data[0..8] != Self::DISCRIMINATOR {
+                    return None;
+                }
+
+                #(#unpack_fields)*
+
+                Some(Self {
+                    #(#field_names),*
+                })
+            }
+        }
+    };
+
+    Ok(expanded)
+}
@@ -0,0 +1,200 @@
+//! ComponentField derive macro implementation
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Ident, Index, Type};
+
+use crate::utils::{generate_arbitrary, pack_value, unpack_value, Offset};
+
+/// Bindings for a set of fields: the local names and their types
+fn field_bindings(fields: &Fields) -> Vec<(Ident, &Type)> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let binding = field
+                .ident
+                .clone()
+                .unwrap_or_else(|| format_ident!("field_{}", i));
+            (binding, &field.ty)
+        })
+        .collect()
+}
+
+/// Constructor (or pattern) for `path` from bindings, e.g. `Self { a, b }`
+fn construct(path: TokenStream, fields: &Fields, bindings: &[(Ident, &Type)]) -> TokenStream {
+    let names = bindings.iter().map(|(name, _)| name);
+    match fields {
+        Fields::Named(_) => quote! { #path { #(#names),* } },
+        Fields::Unnamed(_) => quote! { #path ( #(#names),* ) },
+        Fields::Unit => path,
+    }
+}
+
+/// `path` built from arbitrary field values, e.g. `Self { a: u.arbitrary()? }`
+fn arbitrary_value(path: TokenStream, fields: &Fields) -> TokenStream {
+    let values = fields.iter().map(|field| match &field.ident {
+        Some(ident) => quote! { #ident: u.arbitrary()? },
+        None => quote! { u.arbitrary()? },
+    });
+    match fields {
+        Fields::Named(_) => quote! { #path { #(#values),* } },
+        Fields::Unnamed(_) => quote! { #path ( #(#values),* ) },
+        Fields::Unit => path,
+    }
+}
+
+/// Unpack statements binding each field, starting at `offset`
+fn unpack_bindings(bindings: &[(Ident, &Type)], offset: &mut Offset) -> syn::Result<Vec<TokenStream>> {
+    let mut unpacks = Vec::new();
+    for (name, ty) in bindings {
+        let value = unpack_value(ty, offset)?;
+        unpacks.push(quote! { let #name: #ty = #value; });
+        offset.advance(ty);
+    }
+    Ok(unpacks)
+}
+
+pub fn derive_component_field_impl(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let (size, pack_body, unpack_body, arbitrary_body) = match &input.data {
+        Data::Struct(data) => {
+            let mut offset = Offset::new(0);
+            let mut packs = Vec::new();
+            for (i, field) in data.fields.iter().enumerate() {
+                let value = match &field.ident {
+                    Some(ident) => quote! { self.#ident },
+                    None => {
+                        let index = Index::from(i);
+                        quote! { self.#index }
+                    }
+                };
+                packs.push(pack_value(&field.ty, value, &offset)?);
+                offset.advance(&field.ty);
+            }
+
+            let bindings = field_bindings(&data.fields);
+            let unpacks = unpack_bindings(&bindings, &mut Offset::new(0))?;
+            let value = construct(quote! { Self }, &data.fields, &bindings);
+            let arbitrary = arbitrary_value(quote! { Self }, &data.fields);
+
+            (
+                quote! { #offset },
+                quote! { #(#packs)* },
+                quote! {
+                    #(#unpacks)*
+                    Some(#value)
+                },
+                quote! { Ok(#arbitrary) },
+            )
+        }
+        Data::Enum(data) => {
+            if data.variants.len() > 256 {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "ComponentField enums can have at most 256 variants",
+                ));
+            }
+
+            // A u8 tag, then the variant's fields, zero-padded to the largest variant
+            let mut size = quote! { 1usize };
+            let mut pack_arms = Vec::new();
+            let mut unpack_arms = Vec::new();
+            let mut arbitrary_arms = Vec::new();
+
+            for (index, variant) in data.variants.iter().enumerate() {
+                let tag = index as u8;
+                let ident = &variant.ident;
+                let bindings = field_bindings(&variant.fields);
+                let pattern = construct(quote! { Self::#ident }, &variant.fields, &bindings);
+
+                let mut end = Offset::new(1);
+                let mut packs = Vec::new();
+                for (binding, ty) in &bindings {
+                    packs.push(pack_value(ty, quote! { (*#binding) }, &end)?);
+                    end.advance(ty);
+                }
+                pack_arms.push(quote! {
+                    #pattern => {
+                        data[0] = #tag;
+                        #(#packs)*
+                        data[#end..Self::SIZE].fill(0);
+                    }
+                });
+
+                let unpacks = unpack_bindings(&bindings, &mut Offset::new(1))?;
+                unpack_arms.push(quote! {
+                    #tag => {
+                        #(#unpacks)*
+                        Some(#pattern)
+                    }
+                });
+
+                let arbitrary = arbitrary_value(quote! { Self::#ident }, &variant.fields);
+                arbitrary_arms.push(quote! { #index => #arbitrary, });
+
+                size = quote! { golt_runtime::field::max_size(#size, #end) };
+            }
+
+            let variants = data.variants.len();
+            let arbitrary_body = if variants == 0 {
+                quote! { Err(golt_runtime::arbitrary::Error::EmptyChoose) }
+            } else {
+                quote! {
+                    Ok(match u.choose_index(#variants)? {
+                        #(#arbitrary_arms)*
+                        _ => unreachable!(),
+                    })
+                }
+            };
+
+            (
+                size,
+                quote! {
+                    match self {
+                        #(#pack_arms)*
+                    }
+                },
+                quote! {
+                    match data[0] {
+                        #(#unpack_arms)*
+                        _ => None,
+                    }
+                },
+                arbitrary_body,
+            )
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "ComponentField can only be derived for structs and enums",
+            ))
+        }
+    };
+
+    let arbitrary_impl = generate_arbitrary(name, &input.generics, arbitrary_body, None);
+
+    let expanded = quote! {
+        impl #impl_generics golt_runtime::ComponentField for #name #ty_generics #where_clause {
+            const SIZE: usize = #size;
+
+            #[allow(unused_variables)]
+            fn pack_into(&self, data: &mut [u8]) {
+                #pack_body
+            }
+
+            fn unpack_from(data: &[u8]) -> Option<Self> {
+                if data.len() < Self::SIZE {
+                    return None;
+                }
+                #unpack_body
+            }
+        }
+
+        #arbitrary_impl
+    };
+
+    Ok(expanded)
+}
@@ -1,15 +1,217 @@
 //! Instruction generation for components and systems
 
 use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{ImplItem, ItemImpl};
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, ImplItem, ItemImpl};
 
-pub fn generate_instructions_impl(input: ItemImpl) -> syn::Result<TokenStream> {
-    let struct_name = &input.self_ty;
+use crate::utils::{generate_arbitrary, pack_value, unpack_value, Offset};
+
+/// Tags of the system write and allowlist instructions in
+/// `golt_runtime::authority`, which the generated processor handles itself,
+/// and of the delegate / undelegate instructions of `#[component(delegatable)]`
+const RESERVED_TAGS: [u8; 6] = [248, 249, 250, 251, 253, 254];
+
+/// How the generated processor calls an instruction method
+#[derive(Clone, Copy, PartialEq)]
+enum MethodKind {
+    /// No receiver, returns `Self`: creates the component account
+    Constructor,
+    /// `&mut self`: loads, calls, writes back
+    Mutate,
+    /// `&self`: loads and calls
+    Read,
+}
+
+impl MethodKind {
+    fn of(method: &syn::ImplItemFn) -> syn::Result<Self> {
+        match method.sig.receiver() {
+            Some(receiver) if receiver.reference.is_none() => Err(syn::Error::new_spanned(
+                receiver,
+                "instruction methods take `&self`, `&mut self` or no receiver",
+            )),
+            Some(receiver) if receiver.mutability.is_some() => Ok(Self::Mutate),
+            Some(_) => Ok(Self::Read),
+            None if returns_self(&method.sig.output) => Ok(Self::Constructor),
+            None => Err(syn::Error::new_spanned(
+                &method.sig,
+                "instructions without a receiver must return `Self` or `Result<Self, E>`",
+            )),
+        }
+    }
+
+    /// Accounts the generated processor expects, used when the method
+    /// documents none
+    fn default_accounts(self) -> Vec<AccountDoc> {
+        let account = |is_signer, is_writable| AccountDoc {
+            is_signer,
+            is_writable,
+        };
+        match self {
+            // payer, entity owner, entity, component PDA, system program
+            Self::Constructor => vec![
+                account(true, true),
+                account(true, false),
+                account(false, false),
+                account(false, true),
+                account(false, false),
+            ],
+            // component, allowlist, system signer PDA
+            Self::Mutate => vec![account(false, true), account(false, false), account(true, false)],
+            // component
+            Self::Read => vec![account(false, false)],
+        }
+    }
+}
+
+/// `Result<..>`, whose error the processor propagates with `?`
+pub fn returns_result(output: &syn::ReturnType) -> bool {
+    match output {
+        syn::ReturnType::Type(_, ty) => matches!(
+            &**ty,
+            syn::Type::Path(p) if p.path.segments.last().is_some_and(|s| s.ident == "Result")
+        ),
+        syn::ReturnType::Default => false,
+    }
+}
+
+/// `Self` or `Result<Self, E>`
+fn returns_self(output: &syn::ReturnType) -> bool {
+    let syn::ReturnType::Type(_, ty) = output else {
+        return false;
+    };
+    let syn::Type::Path(type_path) = &**ty else {
+        return false;
+    };
+    let Some(last) = type_path.path.segments.last() else {
+        return false;
+    };
+    if last.ident == "Self" {
+        return true;
+    }
+    if last.ident != "Result" {
+        return false;
+    }
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) => matches!(
+            args.args.first(),
+            Some(syn::GenericArgument::Type(syn::Type::Path(p))) if p.path.is_ident("Self")
+        ),
+        _ => false,
+    }
+}
+
+pub fn arg_ident(name: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(&format!("arg_{}", name), name.span())
+}
+
+/// Processor arm body calling `method` for one instruction
+fn generate_process_body(
+    struct_name: &syn::Type,
+    registry: &syn::Path,
+    method_name: &syn::Ident,
+    kind: MethodKind,
+    params: &[(syn::Ident, Box<syn::Type>)],
+    is_result: bool,
+) -> TokenStream {
+    let args: Vec<_> = params.iter().map(|(name, _)| arg_ident(name)).collect();
+    let try_op = if is_result { quote! { ? } } else { quote! {} };
+
+    match kind {
+        MethodKind::Constructor => quote! {
+            let payer = ctx.next_signer_writable()?;
+            let owner = ctx.next_signer()?;
+            let entity = ctx.next()?;
+            let account = ctx.next_writable()?;
+            ctx.next_program(&golt_runtime::program_ids::SYSTEM)?;
+            golt_runtime::require_entity_owner(entity, owner, &#registry)?;
+
+            let (expected, bump) = <#struct_name as golt_runtime::Component>::derive_pda(
+                &[entity.key().as_ref()],
+                program_id,
+            );
+            if account.key() != &expected {
+                return Err(golt_runtime::GoltError::InvalidPda.into());
+            }
+            let bump_seed = [bump];
+            golt_runtime::init_component_account::<#struct_name>(
+                payer,
+                account,
+                program_id,
+                &[<#struct_name as golt_runtime::Component>::SEED, entity.key().as_ref(), &bump_seed],
+            )?;
+
+            let component = <#struct_name>::#method_name(#(#args),*) #try_op;
+            golt_runtime::Component::validate(&component)?;
+            let mut data = account.try_borrow_mut_data()?;
+            golt_runtime::Component::pack(&component, &mut data);
+            // The PDA is the entity's, so the stored `#[entity]` must be too
+            let stored = <#struct_name as golt_runtime::EntityComponent>::entity_key(&data);
+            if stored != Some(entity.key()) {
+                return Err(golt_runtime::GoltError::EntityMismatch.into());
+            }
+            if let Some(offset) = <#struct_name as golt_runtime::Component>::BUMP_OFFSET {
+                data[offset] = bump;
+            }
+            Ok(())
+        },
+        MethodKind::Mutate => quote! {
+            let account = ctx.next_writable()?;
+            let allowlist = ctx.next()?;
+            let system_signer = ctx.next()?;
+            golt_runtime::check_component_account::<#struct_name>(account, program_id)?;
+            golt_runtime::require_allowed_system(allowlist, system_signer, program_id)?;
+
+            let mut component = golt_runtime::load_component_mut::<#struct_name>(account)?;
+            component.get_mut().#method_name(#(#args),*) #try_op;
+            component.save()
+        },
+        MethodKind::Read => quote! {
+            let account = ctx.next()?;
+            golt_runtime::check_component_account::<#struct_name>(account, program_id)?;
+
+            let component = golt_runtime::load_component::<#struct_name>(account)?;
+            let _ = component.#method_name(#(#args),*) #try_op;
+            Ok(())
+        },
+    }
+}
+
+/// Arguments of `#[component_instructions(program_id = ..., registry = ...)]`
+pub struct ComponentArgs {
+    /// Program ID owning the component accounts, its `OwnedComponent::OWNER`
+    pub program_id: syn::Path,
+    /// Entity registry program; given, a `process_instruction` is generated
+    pub registry: Option<syn::Path>,
+}
+
+/// `component` implements `OwnedComponent` for the impl's type, and its
+/// `registry` adds a `process_instruction` calling the methods on component
+/// accounts, whose entities live in that registry program; systems have no
+/// component to load and handle their own.
+pub fn generate_instructions_impl(
+    input: ItemImpl,
+    component: Option<&ComponentArgs>,
+) -> syn::Result<TokenStream> {
+    let registry = component.and_then(|c| c.registry.as_ref());
+    let dispatch = registry.is_some();
+    let struct_name = &*input.self_ty.clone();
     let mut instruction_variants = Vec::new();
     let mut unpack_arms = Vec::new();
     let mut pack_arms = Vec::new();
     let mut process_arms = Vec::new();
+    let mut cpi_fns = Vec::new();
+    let mut arbitrary_arms = Vec::new();
+
+    let type_name = quote!(#struct_name).to_string().replace(" ", "");
+    let instruction_enum_name = syn::Ident::new(
+        &format!("{}Instruction", type_name),
+        proc_macro2::Span::call_site(),
+    );
+    let cpi_mod = cpi_module_name(&type_name);
+    // Components know their program; other impls take it as a parameter
+    let cpi_program_id = component.is_some().then(|| {
+        quote! { &<#struct_name as golt_runtime::OwnedComponent>::OWNER }
+    });
 
     for item in &input.items {
         if let ImplItem::Fn(method) = item {
@@ -18,6 +220,14 @@ pub fn generate_instructions_impl(input: ItemImpl) -> syn::Result<TokenStream> {
 
             if let Some(attr) = instruction_attr {
                 let tag: u8 = parse_instruction_tag(attr)?;
+                if component.is_some() && RESERVED_TAGS.contains(&tag) {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "tags 248-251, 253 and 254 are reserved for the system write, allowlist \
+                         and delegation instructions",
+                    ));
+                }
+                let kind = if dispatch { Some(MethodKind::of(method)?) } else { None };
                 let method_name = &method.sig.ident;
                 let variant_name = heck::AsUpperCamelCase(method_name.to_string()).to_string();
                 let variant_ident = syn::Ident::new(&variant_name, method_name.span());
@@ -40,6 +250,12 @@ pub fn generate_instructions_impl(input: ItemImpl) -> syn::Result<TokenStream> {
                 let param_names: Vec<_> = params.iter().map(|(name, _)| name.clone()).collect();
                 let param_types: Vec<_> = params.iter().map(|(_, ty)| ty.clone()).collect();
 
+                // Generate arbitrary arm, choosing variants in declaration order
+                let index = instruction_variants.len();
+                arbitrary_arms.push(quote! {
+                    #index => Self::#variant_ident { #(#param_names: u.arbitrary()?),* },
+                });
+
                 // Generate variant
                 if params.is_empty() {
                     instruction_variants.push(quote! { #variant_ident });
@@ -50,14 +266,15 @@ pub fn generate_instructions_impl(input: ItemImpl) -> syn::Result<TokenStream> {
                 }
 
                 // Generate unpack arm
-                let unpack_code = generate_unpack_code(&params);
                 if params.is_empty() {
                     unpack_arms.push(quote! {
                         #tag => Ok(Self::#variant_ident),
                     });
                 } else {
+                    let unpack_code = generate_unpack_code(&params)?;
                     unpack_arms.push(quote! {
                         #tag => {
+                            let data = rest;
                             #unpack_code
                             Ok(Self::#variant_ident { #(#param_names),* })
                         }
@@ -65,37 +282,147 @@ pub fn generate_instructions_impl(input: ItemImpl) -> syn::Result<TokenStream> {
                 }
 
                 // Generate pack arm
-                let pack_code = generate_pack_code(&params);
-                let size = calculate_params_size(&params);
                 if params.is_empty() {
                     pack_arms.push(quote! {
                         Self::#variant_ident => vec![#tag],
                     });
                 } else {
+                    let pack_code = generate_pack_code(&params)?;
                     pack_arms.push(quote! {
                         Self::#variant_ident { #(#param_names),* } => {
-                            let mut data = vec![0u8; 1 + #size];
-                            data[0] = #tag;
                             #pack_code
+                            data[0] = #tag;
                             data
                         }
                     });
                 }
 
-                // Generate process arm (placeholder - actual implementation in processor)
-                process_arms.push(quote! {
-                    Self::#variant_ident { #(#param_names),* } => {
-                        // Process #method_name
-                    }
-                });
+                // Generate CPI helpers, with the processor's accounts unless documented
+                let mut accounts = parse_account_docs(&method.attrs);
+                if let (true, Some(kind)) = (accounts.is_empty(), kind) {
+                    accounts = kind.default_accounts();
+                }
+                cpi_fns.push(generate_cpi_fns(
+                    &instruction_enum_name,
+                    method_name,
+                    &variant_ident,
+                    &params,
+                    &accounts,
+                    cpi_program_id.as_ref(),
+                ));
+
+                // Generate process arm
+                if let (Some(kind), Some(registry)) = (kind, registry) {
+                    let body = generate_process_body(
+                        struct_name,
+                        registry,
+                        method_name,
+                        kind,
+                        &params,
+                        returns_result(&method.sig.output),
+                    );
+                    // Bound as `arg_*` so they can't shadow the processor's locals
+                    let args = param_names.iter().map(arg_ident);
+                    let pattern = if params.is_empty() {
+                        quote! { #instruction_enum_name::#variant_ident }
+                    } else {
+                        quote! { #instruction_enum_name::#variant_ident { #(#param_names: #args),* } }
+                    };
+                    process_arms.push(quote! {
+                        #pattern => {
+                            #body
+                        }
+                    });
+                }
             }
         }
     }
 
-    let instruction_enum_name = syn::Ident::new(
-        &format!("{}Instruction", quote!(#struct_name).to_string().replace(" ", "")),
-        proc_macro2::Span::call_site(),
-    );
+    // `#[instruction]` is only a marker for this macro
+    let mut input = input;
+    for item in &mut input.items {
+        if let ImplItem::Fn(method) = item {
+            method.attrs.retain(|attr| !attr.path().is_ident("instruction"));
+        }
+    }
+
+    let processor = if dispatch {
+        let doc = format!(
+            "Process an instruction of this component program\n\n\
+             Dispatches `{enum_name}` to the `{name}` methods; system writes \
+             and the allowlist instructions are handled by \
+             `golt_runtime::process_component_write` and \
+             `golt_runtime::process_allowlist_instruction`.",
+            enum_name = instruction_enum_name,
+            name = quote!(#struct_name).to_string().replace(' ', ""),
+        );
+        // Named after the impl so the error points at it
+        let requires_entity = quote_spanned! {struct_name.span()=>
+            const _: fn() = {
+                fn requires_entity_component<T: golt_runtime::EntityComponent>() {}
+                requires_entity_component::<#struct_name>
+            };
+        };
+        quote! {
+            #requires_entity
+
+            #[doc = #doc]
+            pub fn process_instruction(
+                program_id: &golt_runtime::prelude::Pubkey,
+                accounts: &[golt_runtime::prelude::AccountInfo],
+                instruction_data: &[u8],
+            ) -> golt_runtime::prelude::ProgramResult {
+                if let Some(result) = golt_runtime::process_component_write::<#struct_name>(
+                    program_id,
+                    accounts,
+                    instruction_data,
+                ) {
+                    return result;
+                }
+                if let Some(result) =
+                    golt_runtime::process_allowlist_instruction(program_id, accounts, instruction_data)
+                {
+                    return result;
+                }
+
+                #[allow(unused_mut, unused_variables)]
+                let mut ctx = golt_runtime::AccountContext::new(accounts);
+                match #instruction_enum_name::unpack(instruction_data)? {
+                    #(#process_arms)*
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let owned_impl = component.map(|ComponentArgs { program_id, .. }| {
+        quote! {
+            impl golt_runtime::OwnedComponent for #struct_name {
+                const OWNER: golt_runtime::prelude::Pubkey = #program_id;
+            }
+        }
+    });
+
+    let arbitrary_impl = (!arbitrary_arms.is_empty()).then(|| {
+        let variants = arbitrary_arms.len();
+        generate_arbitrary(
+            &instruction_enum_name,
+            &syn::Generics::default(),
+            quote! {
+                Ok(match u.choose_index(#variants)? {
+                    #(#arbitrary_arms)*
+                    _ => unreachable!(),
+                })
+            },
+            Some(quote! {
+                golt_runtime::fuzz::check_instruction_roundtrip(
+                    #instruction_enum_name::pack,
+                    #instruction_enum_name::unpack,
+                );
+            }),
+        )
+    });
 
     let expanded = quote! {
         #input
@@ -123,14 +450,238 @@ pub fn generate_instructions_impl(input: ItemImpl) -> syn::Result<TokenStream> {
                 }
             }
         }
+
+        /// CPI helpers for calling this program's instructions
+        ///
+        /// Each instruction gets `name(accounts, ..)` using `invoke`,
+        /// `name_signed(accounts, .., signers)` using `invoke_signed`, and
+        /// `name_account_metas(accounts)` with the metas both send.
+        pub mod #cpi_mod {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #(#cpi_fns)*
+        }
+
+        #arbitrary_impl
+
+        #owned_impl
+
+        #processor
     };
 
     Ok(expanded)
 }
 
+pub fn generate_component_instructions_impl(
+    attr: TokenStream,
+    input: ItemImpl,
+) -> syn::Result<TokenStream> {
+    let mut program_id = None;
+    let mut registry = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("program_id") {
+            program_id = Some(meta.value()?.parse::<syn::Path>()?);
+            Ok(())
+        } else if meta.path.is_ident("registry") {
+            registry = Some(meta.value()?.parse::<syn::Path>()?);
+            Ok(())
+        } else {
+            Err(meta.error(
+                "expected `program_id = <component program ID>` or \
+                 `registry = <entity registry program ID>`",
+            ))
+        }
+    });
+    syn::parse::Parser::parse2(parser, attr)?;
+    let program_id = program_id.ok_or_else(|| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`#[component_instructions]` needs the program owning the component: \
+             add `program_id = <component program ID>`",
+        )
+    })?;
+    generate_instructions_impl(input, Some(&ComponentArgs { program_id, registry }))
+}
+
+/// The required `registry = <path>` argument of `#[macro_name(...)]`
+pub fn parse_registry_arg(attr: TokenStream, macro_name: &str) -> syn::Result<syn::Path> {
+    let mut registry = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("registry") {
+            registry = Some(meta.value()?.parse::<syn::Path>()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `registry = <entity registry program ID>`"))
+        }
+    });
+    syn::parse::Parser::parse2(parser, attr)?;
+    registry.ok_or_else(|| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!(
+                "`#[{}]` checks entities against the registry: \
+                 add `registry = <entity registry program ID>`",
+                macro_name
+            ),
+        )
+    })
+}
+
 pub fn generate_system_instructions_impl(input: ItemImpl) -> syn::Result<TokenStream> {
-    // Similar to component instructions but for systems
-    generate_instructions_impl(input)
+    // Same enum and CPI helpers as components, without the processor
+    generate_instructions_impl(input, None)
+}
+
+/// Account flags from doc lines like "0. `[signer, writable]` Payer"
+pub struct AccountDoc {
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+fn parse_account_docs(attrs: &[syn::Attribute]) -> Vec<AccountDoc> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }),
+                ..
+            }) => Some(doc.value()),
+            _ => None,
+        })
+        .filter_map(|doc| {
+            let doc = doc.trim();
+            let (index, rest) = doc.split_once('.')?;
+            if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let flags = rest.trim().strip_prefix("`[")?.split_once("]`")?.0;
+            Some(AccountDoc {
+                is_signer: flags.contains("signer"),
+                is_writable: flags.contains("writable"),
+            })
+        })
+        .collect()
+}
+
+/// Name of the CPI module for `name`: `health_cpi` for `Health`
+///
+/// Named per type so several impls can share a module.
+pub fn cpi_module_name(name: &str) -> syn::Ident {
+    syn::Ident::new(
+        &format!("{}_cpi", heck::AsSnakeCase(name)),
+        proc_macro2::Span::call_site(),
+    )
+}
+
+/// Generate `name` / `name_signed` CPI functions for one instruction
+///
+/// With documented accounts the account count and flags are fixed; otherwise
+/// the function takes any number of accounts and copies their flags. The
+/// instruction goes to `program_id`, or to a leading `program_id` parameter
+/// if `None`.
+pub fn generate_cpi_fns(
+    enum_name: &syn::Ident,
+    method_name: &syn::Ident,
+    variant_ident: &syn::Ident,
+    params: &[(syn::Ident, Box<syn::Type>)],
+    accounts: &[AccountDoc],
+    program_id: Option<&TokenStream>,
+) -> TokenStream {
+    let signed_name = syn::Ident::new(&format!("{}_signed", method_name), method_name.span());
+    let metas_name = syn::Ident::new(&format!("{}_account_metas", method_name), method_name.span());
+    let param_names: Vec<_> = params.iter().map(|(name, _)| name).collect();
+    let param_types: Vec<_> = params.iter().map(|(_, ty)| ty).collect();
+
+    let instruction = if params.is_empty() {
+        quote! { #enum_name::#variant_ident }
+    } else {
+        quote! { #enum_name::#variant_ident { #(#param_names),* } }
+    };
+
+    let (generics, len, metas) = if accounts.is_empty() {
+        (
+            quote! { const N: usize },
+            quote! { N },
+            quote! {
+                core::array::from_fn(|i| {
+                    golt_runtime::prelude::AccountMeta::new(
+                        accounts[i].key(),
+                        accounts[i].is_writable(),
+                        accounts[i].is_signer(),
+                    )
+                })
+            },
+        )
+    } else {
+        let count = accounts.len();
+        let meta_exprs = accounts.iter().enumerate().map(|(i, account)| {
+            let is_writable = account.is_writable;
+            let is_signer = account.is_signer;
+            quote! {
+                golt_runtime::prelude::AccountMeta::new(accounts[#i].key(), #is_writable, #is_signer)
+            }
+        });
+        (quote! {}, quote! { #count }, quote! { [#(#meta_exprs),*] })
+    };
+
+    let (program, program_param, program_arg) = match program_id {
+        Some(program_id) => (program_id.clone(), quote! {}, quote! {}),
+        None => (
+            quote! { program_id },
+            quote! { program_id: &golt_runtime::prelude::Pubkey, },
+            quote! { program_id, },
+        ),
+    };
+    let target = if program_id.is_some() { "this program" } else { "`program_id`" };
+    let doc = format!("Call `{}::{}` on {}", enum_name, variant_ident, target);
+    let signed_doc = format!(
+        "Call `{}::{}` on {}, signing with PDA seeds",
+        enum_name, variant_ident, target
+    );
+    let metas_doc = format!("Account metas `{}` passes for `accounts`", method_name);
+
+    quote! {
+        #[doc = #metas_doc]
+        pub fn #metas_name<'a, #generics>(
+            accounts: &[&'a golt_runtime::prelude::AccountInfo; #len],
+        ) -> [golt_runtime::prelude::AccountMeta<'a>; #len] {
+            #metas
+        }
+
+        #[doc = #doc]
+        #[allow(clippy::too_many_arguments)]
+        pub fn #method_name<#generics>(
+            #program_param
+            accounts: &[&golt_runtime::prelude::AccountInfo; #len],
+            #(#param_names: #param_types,)*
+        ) -> golt_runtime::prelude::ProgramResult {
+            #signed_name(#program_arg accounts, #(#param_names,)* &[])
+        }
+
+        #[doc = #signed_doc]
+        #[allow(clippy::too_many_arguments)]
+        pub fn #signed_name<#generics>(
+            #program_param
+            accounts: &[&golt_runtime::prelude::AccountInfo; #len],
+            #(#param_names: #param_types,)*
+            signers: &[golt_runtime::prelude::Signer],
+        ) -> golt_runtime::prelude::ProgramResult {
+            let data = #instruction.pack();
+            let metas = #metas_name(accounts);
+            let instruction = golt_runtime::prelude::Instruction {
+                program_id: #program,
+                data: &data,
+                accounts: &metas,
+            };
+            golt_runtime::prelude::invoke_signed(&instruction, accounts, signers)
+        }
+    }
 }
 
 fn parse_instruction_tag(attr: &syn::Attribute) -> syn::Result<u8> {
@@ -149,134 +700,166 @@ fn parse_instruction_tag(attr: &syn::Attribute) -> syn::Result<u8> {
     Err(syn::Error::new_spanned(attr, "Expected #[instruction(tag = N)]"))
 }
 
-fn generate_unpack_code(params: &[(syn::Ident, Box<syn::Type>)]) -> TokenStream {
-    let mut offset = 0usize;
-    let mut code = Vec::new();
+/// Variable-length parameter: a `u32` length, then the bytes
+enum VarLenParam {
+    Bytes,
+    String,
+}
 
-    for (name, ty) in params {
-        let size = estimate_type_size(ty);
-        let unpack = generate_param_unpack(name, ty, offset);
-        code.push(unpack);
-        offset += size;
+/// `Vec<u8>` / `String`, or `None` for fixed-size types
+fn var_len_param(ty: &syn::Type) -> syn::Result<Option<VarLenParam>> {
+    let syn::Type::Path(type_path) = ty else {
+        return Ok(None);
+    };
+    let Some(last) = type_path.path.segments.last() else {
+        return Ok(None);
+    };
+    if last.ident == "String" {
+        return Ok(Some(VarLenParam::String));
+    }
+    if last.ident != "Vec" {
+        return Ok(None);
+    }
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(args)
+            if args.args.len() == 1
+                && matches!(
+                    args.args.first(),
+                    Some(syn::GenericArgument::Type(syn::Type::Path(p))) if p.path.is_ident("u8")
+                ) =>
+        {
+            Ok(Some(VarLenParam::Bytes))
+        }
+        _ => Err(syn::Error::new_spanned(
+            ty,
+            "only `Vec<u8>` and `String` are supported as variable-length parameters",
+        )),
     }
-
-    quote! { #(#code)* }
 }
 
-fn generate_pack_code(params: &[(syn::Ident, Box<syn::Type>)]) -> TokenStream {
-    let mut offset = 1usize; // Skip tag byte
-    let mut code = Vec::new();
-
-    for (name, ty) in params {
-        let size = estimate_type_size(ty);
-        let pack = generate_param_pack(name, ty, offset);
-        code.push(pack);
-        offset += size;
+/// Smallest encoding of `params`: fixed sizes plus the length prefixes
+fn min_params_size(params: &[(syn::Ident, Box<syn::Type>)]) -> syn::Result<Offset> {
+    let mut size = Offset::new(0);
+    for (_, ty) in params {
+        match var_len_param(ty)? {
+            Some(_) => size.add(4),
+            None => size.advance(ty),
+        }
     }
-
-    quote! { #(#code)* }
+    Ok(size)
 }
 
-fn calculate_params_size(params: &[(syn::Ident, Box<syn::Type>)]) -> usize {
-    params.iter().map(|(_, ty)| estimate_type_size(ty)).sum()
+/// Size of a fixed-size parameter
+fn fixed_param_size(ty: &syn::Type) -> TokenStream {
+    let mut size = Offset::new(0);
+    size.advance(ty);
+    quote! { #size }
 }
 
-fn estimate_type_size(ty: &syn::Type) -> usize {
-    if let syn::Type::Path(type_path) = ty {
-        let ident = type_path.path.segments.last().map(|s| s.ident.to_string());
-        match ident.as_deref() {
-            Some("u8") | Some("i8") | Some("bool") => 1,
-            Some("u16") | Some("i16") => 2,
-            Some("u32") | Some("i32") | Some("f32") => 4,
-            Some("u64") | Some("i64") | Some("f64") => 8,
-            Some("u128") | Some("i128") => 16,
-            Some("Pubkey") => 32,
-            _ => 0,
-        }
-    } else if let syn::Type::Array(arr) = ty {
-        if let syn::Expr::Lit(syn::ExprLit {
-            lit: syn::Lit::Int(lit_int),
-            ..
-        }) = &arr.len
-        {
-            let len: usize = lit_int.base10_parse().unwrap_or(0);
-            let elem_size = estimate_type_size(&arr.elem);
-            len * elem_size
-        } else {
-            0
+/// Unpack `params` from `data`, returning `InvalidInstructionData` if it is
+/// too short or malformed
+///
+/// Parameters are read at a running `offset`, since a variable-length
+/// parameter moves everything after it.
+pub fn generate_unpack_code(params: &[(syn::Ident, Box<syn::Type>)]) -> syn::Result<TokenStream> {
+    let min_size = min_params_size(params)?;
+    let mut code = Vec::new();
+
+    for (i, (name, ty)) in params.iter().enumerate() {
+        let is_last = i + 1 == params.len();
+        match var_len_param(ty)? {
+            Some(kind) => {
+                // The bytes and every fixed-size parameter after them must fit
+                let rest_min = min_params_size(&params[i + 1..])?;
+                let value = match kind {
+                    VarLenParam::Bytes => quote! { data[offset..offset + len].to_vec() },
+                    VarLenParam::String => quote! {
+                        String::from_utf8(data[offset..offset + len].to_vec()).ok()?
+                    },
+                };
+                code.push(quote! {
+                    let len = u32::from_le_bytes(data[offset..offset + 4].try_into().ok()?) as usize;
+                    offset += 4;
+                    if data.len() < offset + len + #rest_min {
+                        return None;
+                    }
+                    let #name: #ty = #value;
+                });
+                if !is_last {
+                    code.push(quote! { offset += len; });
+                }
+            }
+            None => {
+                let value = unpack_value(ty, &quote!(offset))?;
+                code.push(quote! { let #name: #ty = #value; });
+                if !is_last {
+                    let size = fixed_param_size(ty);
+                    code.push(quote! { offset += #size; });
+                }
+            }
         }
-    } else {
-        0
     }
-}
 
-fn generate_param_unpack(name: &syn::Ident, ty: &syn::Type, offset: usize) -> TokenStream {
-    if let syn::Type::Path(type_path) = ty {
-        let ident = type_path.path.segments.last().map(|s| s.ident.to_string());
-        match ident.as_deref() {
-            Some("u8") => quote! { let #name = rest[#offset]; },
-            Some("i8") => quote! { let #name = rest[#offset] as i8; },
-            Some("bool") => quote! { let #name = rest[#offset] != 0; },
-            Some("u16") => quote! {
-                let #name = u16::from_le_bytes(rest[#offset..#offset + 2].try_into()
-                    .map_err(|_| golt_runtime::prelude::ProgramError::InvalidInstructionData)?);
-            },
-            Some("i16") => quote! {
-                let #name = i16::from_le_bytes(rest[#offset..#offset + 2].try_into()
-                    .map_err(|_| golt_runtime::prelude::ProgramError::InvalidInstructionData)?);
-            },
-            Some("u32") => quote! {
-                let #name = u32::from_le_bytes(rest[#offset..#offset + 4].try_into()
-                    .map_err(|_| golt_runtime::prelude::ProgramError::InvalidInstructionData)?);
-            },
-            Some("i32") => quote! {
-                let #name = i32::from_le_bytes(rest[#offset..#offset + 4].try_into()
-                    .map_err(|_| golt_runtime::prelude::ProgramError::InvalidInstructionData)?);
-            },
-            Some("u64") => quote! {
-                let #name = u64::from_le_bytes(rest[#offset..#offset + 8].try_into()
-                    .map_err(|_| golt_runtime::prelude::ProgramError::InvalidInstructionData)?);
-            },
-            Some("i64") => quote! {
-                let #name = i64::from_le_bytes(rest[#offset..#offset + 8].try_into()
-                    .map_err(|_| golt_runtime::prelude::ProgramError::InvalidInstructionData)?);
-            },
-            _ => quote! { let #name = Default::default(); },
-        }
-    } else if let syn::Type::Array(arr) = ty {
-        let size = estimate_type_size(ty);
-        quote! {
-            let #name: #ty = rest[#offset..#offset + #size].try_into()
-                .map_err(|_| golt_runtime::prelude::ProgramError::InvalidInstructionData)?;
-        }
+    let param_names: Vec<_> = params.iter().map(|(name, _)| name).collect();
+    let offset_decl = if params.len() > 1 || var_len_param(&params[0].1)?.is_some() {
+        quote! { let mut offset = 0usize; }
     } else {
-        quote! { let #name = Default::default(); }
-    }
-}
+        quote! { let offset = 0usize; }
+    };
 
-fn generate_param_pack(name: &syn::Ident, ty: &syn::Type, offset: usize) -> TokenStream {
-    if let syn::Type::Path(type_path) = ty {
-        let ident = type_path.path.segments.last().map(|s| s.ident.to_string());
-        match ident.as_deref() {
-            Some("u8") | Some("i8") => quote! { data[#offset] = #name as u8; },
-            Some("bool") => quote! { data[#offset] = if #name { 1 } else { 0 }; },
-            Some("u16") | Some("i16") => quote! {
-                data[#offset..#offset + 2].copy_from_slice(&#name.to_le_bytes());
-            },
-            Some("u32") | Some("i32") | Some("f32") => quote! {
-                data[#offset..#offset + 4].copy_from_slice(&#name.to_le_bytes());
-            },
-            Some("u64") | Some("i64") | Some("f64") => quote! {
-                data[#offset..#offset + 8].copy_from_slice(&#name.to_le_bytes());
-            },
-            _ => quote! {},
+    Ok(quote! {
+        if data.len() < #min_size {
+            return Err(golt_runtime::prelude::ProgramError::InvalidInstructionData);
         }
-    } else if let syn::Type::Array(_) = ty {
-        let size = estimate_type_size(ty);
-        quote! {
-            data[#offset..#offset + #size].copy_from_slice(&#name);
+        let (#(#param_names,)*) = (|| -> Option<_> {
+            #offset_decl
+            #(#code)*
+            Some((#(#param_names,)*))
+        })()
+        .ok_or(golt_runtime::prelude::ProgramError::InvalidInstructionData)?;
+    })
+}
+
+/// Pack `params` (bound by reference) into a new `data` buffer after the tag
+pub fn generate_pack_code(params: &[(syn::Ident, Box<syn::Type>)]) -> syn::Result<TokenStream> {
+    let min_size = min_params_size(params)?;
+    let mut code = Vec::new();
+    let mut var_lens = Vec::new();
+
+    for (i, (name, ty)) in params.iter().enumerate() {
+        let is_last = i + 1 == params.len();
+        match var_len_param(ty)? {
+            Some(_) => {
+                var_lens.push(quote! { AsRef::<[u8]>::as_ref(#name).len() });
+                code.push(quote! {
+                    let bytes = AsRef::<[u8]>::as_ref(#name);
+                    data[offset..offset + 4].copy_from_slice(&(bytes.len() as u32).to_le_bytes());
+                    offset += 4;
+                    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+                });
+                if !is_last {
+                    code.push(quote! { offset += bytes.len(); });
+                }
+            }
+            None => {
+                code.push(pack_value(ty, quote! { (*#name) }, &quote!(offset))?);
+                if !is_last {
+                    let size = fixed_param_size(ty);
+                    code.push(quote! { offset += #size; });
+                }
+            }
         }
-    } else {
-        quote! {}
     }
+
+    let offset_decl = if params.len() > 1 || !var_lens.is_empty() {
+        quote! { let mut offset = 1usize; }
+    } else {
+        quote! { let offset = 1usize; }
+    };
+
+    Ok(quote! {
+        let mut data = vec![0u8; 1 + #min_size #(+ #var_lens)*];
+        #offset_decl
+        #(#code)*
+    })
 }
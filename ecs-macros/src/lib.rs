@@ -4,15 +4,26 @@
 //! boilerplate code for Solana ECS programs.
 
 use proc_macro::TokenStream;
-use syn::{parse_macro_input, DeriveInput, ItemImpl};
+use syn::{parse_macro_input, DeriveInput, ItemFn, ItemImpl};
 
+mod accounts;
 mod component;
+mod error;
+mod event;
+mod field;
 mod instruction;
 mod system;
 mod utils;
 
 /// Derive macro for ECS components
 ///
+/// Besides `golt_runtime::Component`, this generates the zero-copy accessor
+/// traits `{Name}View` and `{Name}ViewMut` for `ComponentRef`/`ComponentRefMut`,
+/// returned by `Name::view` / `Name::view_mut`. The same accessors exist on raw
+/// account data as `Name::read_{field}(&data)` / `Name::write_{field}(&mut data, v)`.
+/// Marking the entity key field `#[entity]` also implements
+/// `golt_runtime::EntityComponent`, for `load_component_for_entity`.
+///
 /// # Example
 ///
 /// ```rust
@@ -21,14 +32,150 @@ mod utils;
 /// #[derive(Component)]
 /// #[component(seed = "health", discriminator = "health\0\0")]
 /// pub struct Health {
+///     #[entity]
+///     pub entity: [u8; 32],
+///     pub current: u32,
+///     pub max: u32,
+///     #[pda_bump]
+///     pub bump: u8,
+/// }
+/// ```
+///
+/// # Discriminators
+///
+/// The discriminator is the seed (or `discriminator = "..."`) padded or
+/// truncated to 8 bytes, so seeds sharing their first 8 bytes collide.
+/// `#[component(hashed)]` uses the first 8 bytes of
+/// `sha256("component:{seed}")` instead, matching `discriminators = "hashed"`
+/// in golt.toml.
+///
+/// # Versioned layouts
+///
+/// `#[component(version = N)]` stores a version byte after the discriminator
+/// and `unpack` only accepts that version. `migrate = "path"` names a function
+/// `fn(version: u8, data: &[u8]) -> Option<Self>` that decodes older layouts;
+/// it is used by `golt_runtime::migrate_component_account`.
+///
+/// Accounts created before the component had a version have no version byte.
+/// `legacy_size = N` gives their size: accounts of exactly `N` bytes are
+/// passed to the migrate function as version 0, and `unpack` and the views
+/// reject them until then. `N` must differ from the current `SIZE`.
+///
+/// ```ignore
+/// #[derive(Component)]
+/// #[component(seed = "health", version = 2, migrate = "upgrade_health")]
+/// pub struct Health {
 ///     pub entity: [u8; 32],
 ///     pub current: u32,
 ///     pub max: u32,
+///     pub shield: u32,
 ///     #[pda_bump]
 ///     pub bump: u8,
 /// }
+///
+/// fn upgrade_health(version: u8, data: &[u8]) -> Option<Health> {
+///     match version {
+///         1 => HealthV1::unpack(data).map(|old| Health { shield: 0, ..old.into() }),
+///         _ => None,
+///     }
+/// }
+/// ```
+///
+/// # Variable-length fields
+///
+/// `Vec<T>` and `String` fields need `#[max_len(N)]`: they are stored as a
+/// `u32` length followed by room for `N` elements (bytes for `String`), so the
+/// component keeps a fixed `SIZE`. The derive adds `{FIELD}_MAX_LEN`,
+/// `push_{field}` / `set_{field}` helpers that reject overflow, and a
+/// `validate` check that runs before the component is written back.
+///
+/// ```ignore
+/// #[derive(Component)]
+/// #[component(seed = "inventory")]
+/// pub struct Inventory {
+///     pub entity: [u8; 32],
+///     #[max_len(16)]
+///     pub items: Vec<u32>,
+///     #[max_len(24)]
+///     pub name: String,
+/// }
+///
+/// inventory.push_items(sword_id)?;
+/// inventory.set_name("Backpack")?;
+/// ```
+///
+/// # Nested fields
+///
+/// Fields of any other type are packed through `golt_runtime::ComponentField`
+/// (see `#[derive(ComponentField)]`) and `SIZE` includes their size. A type
+/// without an implementation fails to compile.
+///
+/// # Bit fields
+///
+/// `#[bits(N)]` stores a `bool` or unsigned integer field in `N` bits, and
+/// `#[flags]` stores a `bool` in one. Consecutive bit fields share the
+/// smallest little-endian word (`u8` to `u64`) that holds them, filled from
+/// the least significant bit; a non-bit field, `#[offset]`/`#[padding]` or a
+/// full word starts a new one. `validate` fails with `CapacityExceeded` for
+/// values that don't fit, while `write_{field}` keeps only the low `N` bits.
+///
+/// ```ignore
+/// #[derive(Component)]
+/// #[component(seed = "status")]
+/// pub struct Status {
+///     #[flags]
+///     pub alive: bool,   // bit 0
+///     #[flags]
+///     pub stunned: bool, // bit 1
+///     #[bits(6)]
+///     pub level: u8,     // bits 2..8, one byte in total
+/// }
 /// ```
-#[proc_macro_derive(Component, attributes(component, pda_bump, skip))]
+///
+/// # Delegation
+///
+/// `#[component(delegatable)]` implements `golt_runtime::Delegatable` from the
+/// `#[entity]` and `#[pda_bump]` fields, which it then requires, and generates
+/// `process_delegate`, `process_undelegate` and `process_delegation_instruction`
+/// (tags 253 / 254 and the delegation program's callback). Delegating and
+/// undelegating take the signature of the entity's owner, checked against the
+/// registry program passed in, or of an allowlisted system.
+///
+/// # Explicit layout
+///
+/// Fields are packed back to back in declaration order. `#[offset(N)]` places
+/// a field at byte `N` of the account data and `#[padding(N)]` leaves `N`
+/// bytes before it; `#[component(reserved = N)]` keeps `N` bytes free at the
+/// end for later fields. Gaps and reserved bytes are zeroed on `pack`. An
+/// offset that overlaps the previous field is a compile error.
+///
+/// `Component::LAYOUT` lists each field's name, offset and size.
+///
+/// ```ignore
+/// #[derive(Component)]
+/// #[component(seed = "stats", reserved = 32)]
+/// pub struct Stats {
+///     #[entity]
+///     pub entity: [u8; 32],   // 8..40
+///     #[offset(48)]
+///     pub level: u16,         // 48..50
+///     #[padding(2)]
+///     pub xp: u32,            // 52..56, then 32 reserved bytes (SIZE = 88)
+/// }
+/// ```
+///
+/// # Arbitrary
+///
+/// With golt-runtime's `arbitrary` feature the component implements
+/// `arbitrary::Arbitrary`, generating only values that pass `validate`:
+/// `#[max_len]` fields stay within capacity and bit fields within their bits.
+/// A `golt_{name}_roundtrip` test runs
+/// `golt_runtime::fuzz::check_component_roundtrip` on it. Without the feature
+/// nothing is generated.
+#[proc_macro_derive(
+    Component,
+    attributes(bits, component, entity, flags, max_len, offset, padding, pda_bump, skip)
+)]
 pub fn derive_component(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     component::derive_component_impl(input)
@@ -36,12 +183,177 @@ pub fn derive_component(input: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Derive macro for validated instruction accounts
+///
+/// Fields are `&'a AccountInfo`, taken from an `AccountContext` in
+/// declaration order. `#[account(...)]` options:
+///
+/// - `signer`, `mut` - signer / writable checks
+/// - `component = T` - owned by the program (or `owner = expr`) and holds a `T`
+/// - `seeds = [..]` - PDA check; earlier fields stand for their keys. With
+///   `component`, the `T::SEED` prefix is implied and the stored bump is used
+/// - `program = ID` - the account's key; `SYSTEM` and `DELEGATION` come from
+///   `golt_runtime::program_ids`
+///
+/// Failures return the matching `GoltError`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Accounts)]
+/// pub struct Damage<'a> {
+///     #[account(signer)]
+///     pub authority: &'a AccountInfo,
+///     pub entity: &'a AccountInfo,
+///     #[account(mut, component = Health, seeds = [entity])]
+///     pub health: &'a AccountInfo,
+///     #[account(program = SYSTEM)]
+///     pub system_program: &'a AccountInfo,
+/// }
+///
+/// let accounts = Damage::from_accounts(accounts, program_id)?;
+/// ```
+#[proc_macro_derive(Accounts, attributes(account))]
+pub fn derive_accounts(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    accounts::derive_accounts_impl(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derive macro for program events
+///
+/// Implements `golt_runtime::Event`: an 8-byte discriminator followed by the
+/// packed fields. The discriminator is the first 8 bytes of
+/// `sha256("event:{name}")`, where `name` is the snake_case struct name or
+/// `#[event(discriminator = "...")]`. Emit with `golt_runtime::emit!`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Event)]
+/// pub struct DamageDealt {
+///     pub target: [u8; 32],
+///     pub amount: u32,
+/// }
+///
+/// emit!(DamageDealt { target, amount });
+/// ```
+#[proc_macro_derive(Event, attributes(event))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    event::derive_event_impl(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derive macro for structs and enums nested inside components
+///
+/// Implements `golt_runtime::ComponentField`. Structs pack their fields in
+/// order; enums pack a `u8` tag (the variant index) and the variant's fields,
+/// zero-padded to the largest variant. Fields follow the same rules as
+/// component fields, so types can nest. With golt-runtime's `arbitrary`
+/// feature the type also implements `arbitrary::Arbitrary`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Clone, Copy, ComponentField)]
+/// pub struct Stats {
+///     pub strength: u16,
+///     pub agility: u16,
+/// }
+///
+/// #[derive(Clone, Copy, ComponentField)]
+/// pub enum Status {
+///     Idle,
+///     Stunned { turns: u8 },
+///     Poisoned(u32),
+/// }
+/// ```
+#[proc_macro_derive(ComponentField)]
+pub fn derive_component_field(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    field::derive_component_field_impl(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derive macro for program error enums
+///
+/// Implements `golt_runtime::ErrorCode`, `Display` and `From<_> for ProgramError`.
+/// Variants are numbered from `offset` in declaration order; messages come from
+/// `#[golt_error(msg = "...")]` or the variant's doc comment and are kept in
+/// the program binary as `ERROR_TABLE`. An enum with more variants than
+/// `golt_runtime::PROGRAM_ERROR_RANGE` is a compile error, since its codes
+/// would run into the next program's range.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Clone, Copy, Debug, PartialEq, ErrorCode)]
+/// #[golt_error(offset = 6000)]
+/// pub enum HealthError {
+///     /// Health is already zero
+///     AlreadyDead, // 6000
+///     #[golt_error(msg = "Heal exceeds max health")]
+///     Overheal, // 6001
+/// }
+/// ```
+#[proc_macro_derive(ErrorCode, attributes(golt_error))]
+pub fn derive_error_code(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    error::derive_error_code_impl(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
 /// Attribute macro for component instruction implementations
 ///
+/// Generates the `{Name}Instruction` enum, a `{name}_cpi` module of CPI
+/// helpers sending to `OwnedComponent::OWNER`, and, given a `registry`, a
+/// `process_instruction` that calls the `#[instruction]` methods:
+///
+/// - no receiver, returning `Self` (or `Result<Self, E>`): creates the
+///   component PDA `[SEED, entity]` and writes the new component, storing the
+///   bump in its `#[pda_bump]` field. The entity must be an active entity of
+///   the `registry` program, whose owner signs, and the new component's
+///   `#[entity]` field must be the entity's key. Accounts: `[payer (signer,
+///   writable), entity owner (signer), entity, component (writable), system
+///   program]`
+/// - `&mut self`: loads the component, calls the method and writes it back.
+///   Only allowlisted systems may call these. Accounts: `[component
+///   (writable), allowlist, system signer PDA (signer)]`
+/// - `&self`: loads the component and calls the method. Accounts: `[component]`
+///
+/// Methods may return `Result<_, E>` with `E: Into<ProgramError>`. Tags
+/// 248-251 are the `#[system]` write-back and allowlist admin instructions,
+/// which `process_instruction` also handles; a write cannot change the
+/// component's `#[entity]` or bump. Tags 253 and 254 are reserved for the
+/// delegate and undelegate instructions of `#[component(delegatable)]`. With
+/// the processor, the component must implement `golt_runtime::EntityComponent`
+/// (an `#[entity]` field).
+///
+/// `program_id = PATH` names the component's program ID and is required; the
+/// component implements `golt_runtime::OwnedComponent` with it as its owner.
+/// `registry = PATH` names the entity registry's program ID; without it no
+/// `process_instruction` is generated, and the program's own processor should
+/// hand tags 248-251 to `golt_runtime::process_component_write` and
+/// `golt_runtime::process_allowlist_instruction`.
+///
+/// Parameters after the tag byte are packed like component fields: any type
+/// `#[derive(Component)]` supports, including `ComponentField` types. `Vec<u8>`
+/// and `String` are a `u32` length followed by the bytes. Data that is too
+/// short, or a `String` that isn't UTF-8, is `InvalidInstructionData`.
+///
+/// With golt-runtime's `arbitrary` feature the enum implements
+/// `arbitrary::Arbitrary`, and a `golt_{name}_instruction_roundtrip` test runs
+/// `golt_runtime::fuzz::check_instruction_roundtrip` on it.
+///
 /// # Example
 ///
 /// ```rust
-/// #[component_instructions]
+/// #[component_instructions(program_id = ID, registry = REGISTRY_ID)]
 /// impl Health {
 ///     #[instruction(tag = 0)]
 ///     pub fn init(entity: [u8; 32], max: u32) -> Self {
@@ -55,9 +367,9 @@ pub fn derive_component(input: TokenStream) -> TokenStream {
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn component_instructions(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn component_instructions(attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as ItemImpl);
-    instruction::generate_instructions_impl(input)
+    instruction::generate_component_instructions_impl(attr.into(), input)
         .unwrap_or_else(|e| e.to_compile_error())
         .into()
 }
@@ -81,6 +393,60 @@ pub fn derive_system(input: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Attribute macro turning a function over components into a system program
+///
+/// `&C` and `&mut C` arguments are component accounts of one entity, in
+/// order, each component type at most once; every other argument is
+/// instruction data, packed like
+/// `#[component_instructions]` parameters. The macro keeps the function and
+/// generates:
+///
+/// - `{Name}Instruction` with a single `{Name}` variant (tag 0) holding the
+///   data arguments, with `pack` / `unpack`. If any component is `&mut`, a
+///   `system_signer_bump: u8` field comes first: the bump of the system's
+///   `["system_signer"]` PDA, which clients derive once off-chain
+/// - `process_instruction`, which checks each component account's owner
+///   program, discriminator, `#[entity]` field and PDA, and that the entity
+///   is an active entity of the `registry` program, calls the function and
+///   writes the `&mut` components back through their programs
+/// - a `{name}_cpi` module with `name` / `name_signed` helpers, which take
+///   the system's program ID first
+///
+/// Accounts: `[entity, one per component (writable if `&mut`)]`, then, if
+/// any component is `&mut`, the system signer PDA followed by the allowlist
+/// PDA and program of each `&mut` component.
+///
+/// `registry = PATH` names the entity registry's program ID and is required.
+///
+/// Components must be `EntityComponent`s whose program uses
+/// `#[component_instructions]`, which implements `OwnedComponent` and accepts
+/// the write-back from allowlisted systems. The function may return
+/// `Result<(), E>` with `E: Into<ProgramError>`. The crate must declare its
+/// program ID; use `#[system]` instead of `#[derive(System)]`, whose helper
+/// attribute has the same name. The instruction enum implements `Arbitrary`
+/// with the `arbitrary` feature, like `#[component_instructions]`.
+///
+/// # Example
+///
+/// ```ignore
+/// use golt_macros::system;
+///
+/// #[system(registry = REGISTRY_ID)]
+/// pub fn movement(pos: &mut Position, vel: &Velocity, dt: u32) {
+///     pos.x += vel.dx * dt as i64;
+///     pos.y += vel.dy * dt as i64;
+/// }
+///
+/// entrypoint!(process_instruction);
+/// ```
+#[proc_macro_attribute]
+pub fn system(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+    system::system_fn_impl(attr.into(), input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
 /// Attribute macro for system instruction implementations
 #[proc_macro_attribute]
 pub fn system_instructions(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -1,9 +1,15 @@
-//! System derive macro implementation
+//! System derive and `#[system]` function macro implementations
 
 use darling::FromDeriveInput;
 use proc_macro2::TokenStream;
-use quote::quote;
-use syn::DeriveInput;
+use quote::{format_ident, quote, ToTokens};
+use syn::{DeriveInput, Ident, ItemFn, Pat, Type};
+
+use crate::instruction::{
+    arg_ident, cpi_module_name, generate_cpi_fns, generate_pack_code, generate_unpack_code,
+    parse_registry_arg, returns_result, AccountDoc,
+};
+use crate::utils::generate_arbitrary;
 
 #[derive(FromDeriveInput)]
 #[darling(attributes(system))]
@@ -29,3 +35,318 @@ pub fn derive_system_impl(input: DeriveInput) -> syn::Result<TokenStream> {
 
     Ok(expanded)
 }
+
+/// A `&C` or `&mut C` argument of a `#[system]` function
+struct ComponentArg<'a> {
+    name: &'a Ident,
+    ty: &'a Type,
+    is_mut: bool,
+}
+
+impl ComponentArg<'_> {
+    fn account(&self) -> Ident {
+        format_ident!("account_{}", self.name)
+    }
+
+    fn component(&self) -> Ident {
+        format_ident!("component_{}", self.name)
+    }
+
+    fn allowlist(&self) -> Ident {
+        format_ident!("allowlist_{}", self.name)
+    }
+}
+
+/// Account list of the generated processor, as documented on it
+fn account_docs(components: &[ComponentArg]) -> (Vec<String>, Vec<AccountDoc>) {
+    let mut lines = vec!["`[]` Entity".to_string()];
+    let mut docs = vec![AccountDoc {
+        is_signer: false,
+        is_writable: false,
+    }];
+    for c in components {
+        let flags = if c.is_mut { "`[writable]`" } else { "`[]`" };
+        let ty = c.ty;
+        lines.push(format!("{} `{}` ({})", flags, c.name, quote!(#ty).to_string().replace(' ', "")));
+        docs.push(AccountDoc {
+            is_signer: false,
+            is_writable: c.is_mut,
+        });
+    }
+    if components.iter().any(|c| c.is_mut) {
+        lines.push("`[]` System signer PDA".to_string());
+        docs.push(AccountDoc {
+            is_signer: false,
+            is_writable: false,
+        });
+    }
+    for c in components.iter().filter(|c| c.is_mut) {
+        lines.push(format!("`[]` `{}` allowlist PDA", c.name));
+        lines.push(format!("`[]` `{}` component program", c.name));
+        for _ in 0..2 {
+            docs.push(AccountDoc {
+                is_signer: false,
+                is_writable: false,
+            });
+        }
+    }
+    (lines, docs)
+}
+
+pub fn system_fn_impl(attr: TokenStream, input: ItemFn) -> syn::Result<TokenStream> {
+    let registry = parse_registry_arg(attr, "system")?;
+    let sig = &input.sig;
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "`#[system]` functions can't be generic",
+        ));
+    }
+
+    // Components become accounts, everything else instruction data
+    let mut components = Vec::new();
+    let mut params = Vec::new();
+    let mut call_args = Vec::new();
+    for arg in &sig.inputs {
+        let syn::FnArg::Typed(pat_type) = arg else {
+            return Err(syn::Error::new_spanned(arg, "`#[system]` functions take no receiver"));
+        };
+        let Pat::Ident(pat) = &*pat_type.pat else {
+            return Err(syn::Error::new_spanned(
+                &pat_type.pat,
+                "`#[system]` arguments must be plain names",
+            ));
+        };
+        match &*pat_type.ty {
+            Type::Reference(reference) => {
+                let component = ComponentArg {
+                    name: &pat.ident,
+                    ty: &reference.elem,
+                    is_mut: reference.mutability.is_some(),
+                };
+                let local = component.component();
+                call_args.push(if component.is_mut {
+                    quote! { &mut #local }
+                } else {
+                    quote! { &#local }
+                });
+                components.push(component);
+            }
+            _ => {
+                call_args.push(arg_ident(&pat.ident).into_token_stream());
+                params.push((pat.ident.clone(), pat_type.ty.clone()));
+            }
+        }
+    }
+    if components.is_empty() {
+        return Err(syn::Error::new_spanned(
+            sig,
+            "`#[system]` functions take at least one `&Component` or `&mut Component` argument",
+        ));
+    }
+    // Each component is the entity's PDA, so a repeated type would be the
+    // same account twice and the last write-back would undo the others
+    for (i, component) in components.iter().enumerate() {
+        let ty = component.ty.to_token_stream().to_string();
+        if components[..i].iter().any(|c| c.ty.to_token_stream().to_string() == ty) {
+            return Err(syn::Error::new_spanned(
+                component.ty,
+                format!(
+                    "`{}` is already an argument; `#[system]` functions take each component type once",
+                    ty.replace(' ', "")
+                ),
+            ));
+        }
+    }
+
+    let has_mut = components.iter().any(|c| c.is_mut);
+    // Signing the write-backs takes the signer PDA's bump, which the
+    // client passes so the processor needn't search for it
+    let signer_bump_name = format_ident!("system_signer_bump");
+    if has_mut {
+        params.insert(
+            0,
+            (signer_bump_name.clone(), Box::new(syn::parse_quote!(u8))),
+        );
+    }
+
+    let fn_name = &sig.ident;
+    let variant_name = heck::AsUpperCamelCase(fn_name.to_string()).to_string();
+    let variant_ident = Ident::new(&variant_name, fn_name.span());
+    let enum_name = format_ident!("{}Instruction", variant_name);
+
+    // Instruction enum, tag 0
+    let param_names: Vec<_> = params.iter().map(|(name, _)| name).collect();
+    let param_types: Vec<_> = params.iter().map(|(_, ty)| ty).collect();
+    let (variant, unpack_arm, pack_arm) = if params.is_empty() {
+        (
+            quote! { #variant_ident },
+            quote! { 0 => Ok(Self::#variant_ident), },
+            quote! { Self::#variant_ident => vec![0], },
+        )
+    } else {
+        let unpack_code = generate_unpack_code(&params)?;
+        let pack_code = generate_pack_code(&params)?;
+        (
+            quote! { #variant_ident { #(#param_names: #param_types),* } },
+            quote! {
+                0 => {
+                    let data = rest;
+                    #unpack_code
+                    Ok(Self::#variant_ident { #(#param_names),* })
+                }
+            },
+            quote! {
+                Self::#variant_ident { #(#param_names),* } => {
+                    #pack_code
+                    data[0] = 0;
+                    data
+                }
+            },
+        )
+    };
+
+    // Processor
+    let args = param_names.iter().map(|name| arg_ident(name));
+    let pattern = if params.is_empty() {
+        quote! { #enum_name::#variant_ident }
+    } else {
+        quote! { #enum_name::#variant_ident { #(#param_names: #args),* } }
+    };
+    let mut_components: Vec<_> = components.iter().filter(|c| c.is_mut).collect();
+
+    let take_accounts = components.iter().map(|c| {
+        let account = c.account();
+        if c.is_mut {
+            quote! { let #account = ctx.next_writable()?; }
+        } else {
+            quote! { let #account = ctx.next()?; }
+        }
+    });
+    let take_signer = has_mut.then(|| quote! { let system_signer = ctx.next()?; });
+    let take_write_accounts = mut_components.iter().map(|c| {
+        let allowlist = c.allowlist();
+        let ty = c.ty;
+        quote! {
+            let #allowlist = ctx.next()?;
+            ctx.next_program(&<#ty as golt_runtime::OwnedComponent>::OWNER)?;
+        }
+    });
+    let loads = components.iter().map(|c| {
+        let (account, local, ty) = (c.account(), c.component(), c.ty);
+        let binding = if c.is_mut { quote! { mut #local } } else { quote! { #local } };
+        quote! {
+            golt_runtime::check_system_component::<#ty>(#account, entity, &#registry)?;
+            let #binding = golt_runtime::load_component::<#ty>(#account)?;
+        }
+    });
+    let signer_bump = arg_ident(&signer_bump_name);
+    let check_signer = has_mut.then(|| {
+        quote! { golt_runtime::check_system_signer(system_signer, #signer_bump, program_id)?; }
+    });
+    let try_op = returns_result(&sig.output).then(|| quote! { ? });
+    let writes = mut_components.iter().map(|c| {
+        let (account, local, allowlist) = (c.account(), c.component(), c.allowlist());
+        quote! {
+            golt_runtime::write_system_component(
+                &#local,
+                #account,
+                entity,
+                #allowlist,
+                system_signer,
+                #signer_bump,
+            )?;
+        }
+    });
+
+    let (account_lines, accounts) = account_docs(&components);
+    let account_list: String = account_lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| format!("{}. {}\n", i, line))
+        .collect();
+    let processor_doc = format!(
+        "Process a `{enum_name}`: load the components, call `{fn_name}` and write \
+         the `&mut` ones back\n\n\
+         Every component must belong to the entity, an active entity of the \
+         registry. Accounts:\n{account_list}",
+    );
+    let cpi_fns = generate_cpi_fns(&enum_name, fn_name, &variant_ident, &params, &accounts, None);
+    let cpi_mod = cpi_module_name(&fn_name.to_string());
+    let arbitrary_impl = generate_arbitrary(
+        &enum_name,
+        &syn::Generics::default(),
+        quote! {
+            Ok(Self::#variant_ident { #(#param_names: u.arbitrary()?),* })
+        },
+        Some(quote! {
+            golt_runtime::fuzz::check_instruction_roundtrip(#enum_name::pack, #enum_name::unpack);
+        }),
+    );
+
+    let expanded = quote! {
+        #input
+
+        #[derive(Clone, Debug)]
+        pub enum #enum_name {
+            #variant
+        }
+
+        impl #enum_name {
+            pub fn unpack(data: &[u8]) -> Result<Self, golt_runtime::prelude::ProgramError> {
+                let (&tag, rest) = data
+                    .split_first()
+                    .ok_or(golt_runtime::prelude::ProgramError::InvalidInstructionData)?;
+
+                match tag {
+                    #unpack_arm
+                    _ => Err(golt_runtime::prelude::ProgramError::InvalidInstructionData),
+                }
+            }
+
+            pub fn pack(&self) -> Vec<u8> {
+                match self {
+                    #pack_arm
+                }
+            }
+        }
+
+        #[doc = #processor_doc]
+        pub fn process_instruction(
+            program_id: &golt_runtime::prelude::Pubkey,
+            accounts: &[golt_runtime::prelude::AccountInfo],
+            instruction_data: &[u8],
+        ) -> golt_runtime::prelude::ProgramResult {
+            let #pattern = #enum_name::unpack(instruction_data)?;
+
+            let mut ctx = golt_runtime::AccountContext::new(accounts);
+            let entity = ctx.next()?;
+            #(#take_accounts)*
+            #take_signer
+            #(#take_write_accounts)*
+            #(#loads)*
+            #check_signer
+
+            #fn_name(#(#call_args),*) #try_op;
+
+            #(#writes)*
+            Ok(())
+        }
+
+        /// CPI helpers for calling this system
+        ///
+        /// `name(program_id, accounts, ..)` uses `invoke` and
+        /// `name_signed(program_id, accounts, .., signers)` uses `invoke_signed`,
+        /// with the accounts `process_instruction` expects.
+        pub mod #cpi_mod {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #cpi_fns
+        }
+
+        #arbitrary_impl
+    };
+
+    Ok(expanded)
+}
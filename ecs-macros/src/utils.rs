@@ -1,23 +1,20 @@
 //! Utility functions for code generation
 
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
 use syn::{Field, Type};
 
+pub use golt_layout::{hashed_discriminator, padded_discriminator, BitSlot};
+use golt_layout::BitInput;
+
 /// Get the size of a type in bytes
 pub fn type_size(ty: &Type) -> Option<usize> {
     match ty {
+        Type::Path(_) if is_fixed(ty) => Some(golt_layout::FIXED_SIZE),
         Type::Path(type_path) => {
             let ident = type_path.path.segments.last()?.ident.to_string();
-            match ident.as_str() {
-                "u8" | "i8" | "bool" => Some(1),
-                "u16" | "i16" => Some(2),
-                "u32" | "i32" | "f32" => Some(4),
-                "u64" | "i64" | "f64" => Some(8),
-                "u128" | "i128" => Some(16),
-                "Pubkey" => Some(32),
-                _ => None,
-            }
+            golt_layout::primitive_size(&ident)
         }
         Type::Array(arr) => {
             if let syn::Expr::Lit(syn::ExprLit {
@@ -36,143 +33,600 @@ pub fn type_size(ty: &Type) -> Option<usize> {
     }
 }
 
+/// Whether `ty` is written as a path to `golt_runtime::Fixed`
+///
+/// A bare `Fixed` may still be some other type in scope, so the generated
+/// code converts through `golt_runtime::math::Fixed` and fails to compile
+/// for anything else.
+fn is_fixed(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    let segments: Vec<String> = type_path
+        .path
+        .segments
+        .iter()
+        .map(|s| s.ident.to_string())
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    type_path.qself.is_none() && golt_layout::is_fixed_path(&segments)
+}
+
+/// Byte offset of a field in packed data
+///
+/// A known constant plus the `SIZE` of every nested `ComponentField` before
+/// the field, which is only known once the program compiles.
+#[derive(Clone)]
+pub struct Offset {
+    fixed: usize,
+    nested: Vec<TokenStream>,
+}
+
+impl Offset {
+    pub fn new(start: usize) -> Self {
+        Self {
+            fixed: start,
+            nested: Vec::new(),
+        }
+    }
+
+    /// Advance past `n` bytes
+    pub fn add(&mut self, n: usize) {
+        self.fixed += n;
+    }
+
+    /// The offset, if it doesn't depend on nested field sizes
+    pub fn as_fixed(&self) -> Option<usize> {
+        self.nested.is_empty().then_some(self.fixed)
+    }
+
+    /// Advance past a field of type `ty`
+    pub fn advance(&mut self, ty: &Type) {
+        match type_size(ty) {
+            Some(size) => self.fixed += size,
+            None => self.nested.push(quote! { <#ty as golt_runtime::ComponentField>::SIZE }),
+        }
+    }
+}
+
+impl ToTokens for Offset {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let fixed = self.fixed;
+        if self.nested.is_empty() {
+            tokens.extend(quote! { #fixed });
+        } else {
+            let nested = &self.nested;
+            tokens.extend(quote! { (#fixed #(+ #nested)*) });
+        }
+    }
+}
+
+/// Whether `ty` is packed by the derives themselves rather than through
+/// `golt_runtime::ComponentField`
+fn is_builtin(ty: &Type) -> bool {
+    match ty {
+        Type::Array(arr) => is_u8(&arr.elem) && type_size(ty).is_some(),
+        _ => type_size(ty).is_some(),
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.path.is_ident("u8"))
+}
+
+fn check_supported(ty: &Type) -> syn::Result<()> {
+    match ty {
+        Type::Path(_) | Type::Array(_) => Ok(()),
+        _ => Err(syn::Error::new_spanned(
+            ty,
+            "unsupported field type; use a named type implementing `ComponentField`",
+        )),
+    }
+}
+
+/// Generate code writing `value` (a place expression of type `ty`) at `offset`
+pub fn pack_value(ty: &Type, value: TokenStream, offset: &impl ToTokens) -> syn::Result<TokenStream> {
+    check_supported(ty)?;
+    if !is_builtin(ty) {
+        return Ok(quote! {
+            golt_runtime::ComponentField::pack_into(&#value, &mut data[#offset..]);
+        });
+    }
+
+    if is_fixed(ty) {
+        return Ok(quote_spanned! {ty.span()=>
+            data[#offset..#offset + 8].copy_from_slice(&golt_runtime::math::Fixed::to_le_bytes(#value));
+        });
+    }
+
+    let size = type_size(ty).unwrap_or(0);
+    let ident = match ty {
+        Type::Path(type_path) => type_path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    };
+    Ok(match ident.as_deref() {
+        Some("u8") | Some("i8") => quote! {
+            data[#offset] = #value as u8;
+        },
+        Some("bool") => quote! {
+            data[#offset] = if #value { 1 } else { 0 };
+        },
+        // Pubkey and byte arrays
+        Some("Pubkey") | None => quote! {
+            data[#offset..#offset + #size].copy_from_slice(&#value);
+        },
+        Some(_) => quote! {
+            data[#offset..#offset + #size].copy_from_slice(&#value.to_le_bytes());
+        },
+    })
+}
+
+/// Generate an expression reading a `ty` at `offset`, returning `None` from
+/// the enclosing function if it can't be decoded
+pub fn unpack_value(ty: &Type, offset: &impl ToTokens) -> syn::Result<TokenStream> {
+    check_supported(ty)?;
+    if !is_builtin(ty) {
+        return Ok(quote! {
+            <#ty as golt_runtime::ComponentField>::unpack_from(&data[#offset..])?
+        });
+    }
+
+    if is_fixed(ty) {
+        return Ok(quote_spanned! {ty.span()=>
+            golt_runtime::math::Fixed::from_le_bytes(data[#offset..#offset + 8].try_into().ok()?)
+        });
+    }
+
+    let size = type_size(ty).unwrap_or(0);
+    let ident = match ty {
+        Type::Path(type_path) => type_path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    };
+    Ok(match ident.as_deref() {
+        Some("u8") => quote! { data[#offset] },
+        Some("i8") => quote! { data[#offset] as i8 },
+        Some("bool") => quote! { data[#offset] != 0 },
+        Some("Pubkey") | None => quote! { data[#offset..#offset + #size].try_into().ok()? },
+        Some(_) => quote! { <#ty>::from_le_bytes(data[#offset..#offset + #size].try_into().ok()?) },
+    })
+}
+
 /// Generate pack code for a field
-pub fn generate_pack_field(field: &Field, offset: usize) -> TokenStream {
+pub fn generate_pack_field(field: &Field, offset: &Offset) -> syn::Result<TokenStream> {
+    let name = field.ident.as_ref().unwrap();
+    pack_value(&field.ty, quote! { self.#name }, offset)
+}
+
+/// Generate unpack code for a field, binding it to a local of the same name
+pub fn generate_unpack_field(field: &Field, offset: &Offset) -> syn::Result<TokenStream> {
     let name = field.ident.as_ref().unwrap();
     let ty = &field.ty;
+    let value = unpack_value(ty, offset)?;
+    Ok(quote! {
+        let #name: #ty = #value;
+    })
+}
 
-    match ty {
-        Type::Path(type_path) => {
-            let ident = type_path.path.segments.last().unwrap().ident.to_string();
-            match ident.as_str() {
-                "u8" | "i8" => quote! {
-                    data[#offset] = self.#name as u8;
-                },
-                "bool" => quote! {
-                    data[#offset] = if self.#name { 1 } else { 0 };
-                },
-                "u16" | "i16" => quote! {
-                    data[#offset..#offset + 2].copy_from_slice(&self.#name.to_le_bytes());
-                },
-                "u32" | "i32" | "f32" => quote! {
-                    data[#offset..#offset + 4].copy_from_slice(&self.#name.to_le_bytes());
-                },
-                "u64" | "i64" | "f64" => quote! {
-                    data[#offset..#offset + 8].copy_from_slice(&self.#name.to_le_bytes());
-                },
-                "u128" | "i128" => quote! {
-                    data[#offset..#offset + 16].copy_from_slice(&self.#name.to_le_bytes());
-                },
-                "Pubkey" => quote! {
-                    data[#offset..#offset + 32].copy_from_slice(&self.#name);
-                },
-                _ => quote! {
-                    // Unknown type, try to copy as bytes
-                    data[#offset..#offset + core::mem::size_of_val(&self.#name)]
-                        .copy_from_slice(&self.#name);
-                },
+/// A `#[max_len(N)]` field: a `u32` length prefix followed by room for `N`
+/// elements (bytes for `String`)
+pub struct VarLenField<'a> {
+    /// Element type, `None` for `String`
+    pub elem: Option<&'a Type>,
+    /// Size of one element
+    pub elem_size: usize,
+    /// Capacity in elements
+    pub max_len: usize,
+}
+
+impl VarLenField<'_> {
+    /// Size of the whole region, prefix included
+    pub fn size(&self) -> usize {
+        golt_layout::var_len_size(self.max_len, self.elem_size)
+    }
+}
+
+/// Parse a `Vec<T>` / `String` field and its `#[max_len(N)]` attribute
+///
+/// Returns `None` for other fields, and an error for a `Vec`/`String` without
+/// a capacity or with an unsupported element type.
+pub fn var_len_field(field: &Field) -> syn::Result<Option<VarLenField<'_>>> {
+    let max_len = field
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("max_len"))
+        .map(|attr| attr.parse_args::<syn::LitInt>()?.base10_parse::<usize>())
+        .transpose()?;
+
+    let segment = match &field.ty {
+        Type::Path(type_path) => type_path.path.segments.last(),
+        _ => None,
+    };
+    let elem = match segment {
+        Some(seg) if seg.ident == "String" => None,
+        Some(seg) if seg.ident == "Vec" => match &seg.arguments {
+            syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+                Some(syn::GenericArgument::Type(ty)) => Some(ty),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => {
+            if max_len.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "#[max_len] is only supported on Vec<T> and String fields",
+                ));
             }
+            return Ok(None);
         }
-        Type::Array(arr) => {
-            if let Some(size) = type_size(ty) {
-                quote! {
-                    data[#offset..#offset + #size].copy_from_slice(&self.#name);
+    };
+
+    let max_len = max_len.ok_or_else(|| {
+        syn::Error::new_spanned(field, "Vec and String fields need #[max_len(N)]")
+    })?;
+    let elem_size = match elem {
+        Some(Type::Array(arr)) if !matches!(&*arr.elem, Type::Path(p) if p.path.is_ident("u8")) => {
+            return Err(syn::Error::new_spanned(arr, "array elements must be [u8; N]"));
+        }
+        Some(ty) => type_size(ty).ok_or_else(|| {
+            syn::Error::new_spanned(ty, "unsupported Vec element type")
+        })?,
+        None => 1,
+    };
+
+    Ok(Some(VarLenField {
+        elem,
+        elem_size,
+        max_len,
+    }))
+}
+
+/// Generate pack code for a `#[max_len(N)]` field
+///
+/// Writes at most `N` elements and zeroes the rest of the region, so stale
+/// elements never linger after the field shrinks. A `String` over `N` bytes
+/// is cut on a char boundary, so it still unpacks as UTF-8.
+pub fn generate_pack_var_len(
+    field: &Field,
+    var: &VarLenField,
+    offset: &Offset,
+) -> syn::Result<TokenStream> {
+    let name = field.ident.as_ref().unwrap();
+    let max_len = var.max_len;
+    let elem_size = var.elem_size;
+    let mut end = offset.clone();
+    end.add(var.size());
+
+    let (len, write_items) = match var.elem {
+        Some(elem) => {
+            let pack = pack_value(elem, quote!((*item)), &quote!(start))?;
+            let write_items = quote! {
+                for (i, item) in self.#name.iter().take(len).enumerate() {
+                    let start = #offset + 4 + i * #elem_size;
+                    #pack
                 }
-            } else {
-                quote! {
-                    // Array copy
-                    let arr_size = core::mem::size_of_val(&self.#name);
-                    data[#offset..#offset + arr_size].copy_from_slice(
-                        unsafe { core::slice::from_raw_parts(
-                            &self.#name as *const _ as *const u8,
-                            arr_size
-                        )}
-                    );
+            };
+            (quote! { self.#name.len().min(#max_len) }, write_items)
+        }
+        None => {
+            let len = quote! {{
+                let mut len = self.#name.len().min(#max_len);
+                while !self.#name.is_char_boundary(len) {
+                    len -= 1;
+                }
+                len
+            }};
+            let write_items = quote! {
+                data[#offset + 4..#offset + 4 + len].copy_from_slice(&self.#name.as_bytes()[..len]);
+            };
+            (len, write_items)
+        }
+    };
+
+    Ok(quote! {
+        {
+            let len = #len;
+            data[#offset..#offset + 4].copy_from_slice(&(len as u32).to_le_bytes());
+            #write_items
+            data[#offset + 4 + len * #elem_size..#end].fill(0);
+        }
+    })
+}
+
+/// Generate unpack code for a `#[max_len(N)]` field, rejecting lengths past `N`
+pub fn generate_unpack_var_len(
+    field: &Field,
+    var: &VarLenField,
+    offset: &Offset,
+) -> syn::Result<TokenStream> {
+    let name = field.ident.as_ref().unwrap();
+    let max_len = var.max_len;
+    let elem_size = var.elem_size;
+
+    let read_items = match var.elem {
+        Some(elem) => {
+            let unpack = unpack_value(elem, &quote!(start))?;
+            quote! {
+                let mut items: Vec<#elem> = Vec::with_capacity(len);
+                for i in 0..len {
+                    let start = #offset + 4 + i * #elem_size;
+                    items.push(#unpack);
                 }
+                items
             }
         }
-        _ => quote! {},
-    }
+        None => quote! {
+            String::from_utf8(data[#offset + 4..#offset + 4 + len].to_vec()).ok()?
+        },
+    };
+
+    Ok(quote! {
+        let #name = {
+            let len = u32::from_le_bytes(data[#offset..#offset + 4].try_into().ok()?) as usize;
+            if len > #max_len {
+                return None;
+            }
+            #read_items
+        };
+    })
 }
 
-/// Generate unpack code for a field
-pub fn generate_unpack_field(field: &Field, offset: usize) -> TokenStream {
+/// Raw and zero-copy accessors for one field
+pub struct FieldAccessors {
+    /// `read_{field}(data)` / `write_{field}(data, value)` on the component
+    pub raw: TokenStream,
+    /// Getter for the `{Name}View` trait, reading `self.bytes()`
+    pub getter: TokenStream,
+    /// Setter for the `{Name}ViewMut` trait, writing `self.bytes_mut()`
+    pub setter: TokenStream,
+}
+
+/// Generate accessors reading and writing a field in place at `offset`
+///
+/// Byte arrays and pubkeys are returned by reference, everything else is
+/// decoded from little-endian bytes. The view getters and setters call the
+/// raw functions on `component`. Returns `None` for nested `ComponentField`
+/// types, which have no in-place accessor.
+pub fn generate_field_accessors(
+    component: &syn::Ident,
+    field: &Field,
+    offset: &Offset,
+) -> Option<FieldAccessors> {
     let name = field.ident.as_ref().unwrap();
     let ty = &field.ty;
+    if !is_builtin(ty) {
+        return None;
+    }
+    let size = type_size(ty)?;
 
-    match ty {
+    let (value_ty, read, write) = match ty {
+        Type::Path(_) if is_fixed(ty) => (
+            quote! { #ty },
+            quote_spanned! {ty.span()=>
+                golt_runtime::math::Fixed::from_le_bytes(data[#offset..#offset + 8].try_into().unwrap())
+            },
+            quote_spanned! {ty.span()=>
+                data[#offset..#offset + 8].copy_from_slice(&golt_runtime::math::Fixed::to_le_bytes(value));
+            },
+        ),
         Type::Path(type_path) => {
-            let ident = type_path.path.segments.last().unwrap().ident.to_string();
+            let ident = type_path.path.segments.last()?.ident.to_string();
             match ident.as_str() {
-                "u8" => quote! {
-                    let #name = data[#offset];
-                },
-                "i8" => quote! {
-                    let #name = data[#offset] as i8;
-                },
-                "bool" => quote! {
-                    let #name = data[#offset] != 0;
-                },
-                "u16" => quote! {
-                    let #name = u16::from_le_bytes(data[#offset..#offset + 2].try_into().ok()?);
-                },
-                "i16" => quote! {
-                    let #name = i16::from_le_bytes(data[#offset..#offset + 2].try_into().ok()?);
-                },
-                "u32" => quote! {
-                    let #name = u32::from_le_bytes(data[#offset..#offset + 4].try_into().ok()?);
-                },
-                "i32" => quote! {
-                    let #name = i32::from_le_bytes(data[#offset..#offset + 4].try_into().ok()?);
-                },
-                "f32" => quote! {
-                    let #name = f32::from_le_bytes(data[#offset..#offset + 4].try_into().ok()?);
-                },
-                "u64" => quote! {
-                    let #name = u64::from_le_bytes(data[#offset..#offset + 8].try_into().ok()?);
-                },
-                "i64" => quote! {
-                    let #name = i64::from_le_bytes(data[#offset..#offset + 8].try_into().ok()?);
-                },
-                "f64" => quote! {
-                    let #name = f64::from_le_bytes(data[#offset..#offset + 8].try_into().ok()?);
-                },
-                "u128" => quote! {
-                    let #name = u128::from_le_bytes(data[#offset..#offset + 16].try_into().ok()?);
-                },
-                "i128" => quote! {
-                    let #name = i128::from_le_bytes(data[#offset..#offset + 16].try_into().ok()?);
-                },
-                "Pubkey" => quote! {
-                    let #name: [u8; 32] = data[#offset..#offset + 32].try_into().ok()?;
-                },
-                _ => quote! {
-                    let #name = Default::default(); // Unknown type
-                },
+                "u8" => (
+                    quote! { u8 },
+                    quote! { data[#offset] },
+                    quote! { data[#offset] = value; },
+                ),
+                "i8" => (
+                    quote! { i8 },
+                    quote! { data[#offset] as i8 },
+                    quote! { data[#offset] = value as u8; },
+                ),
+                "bool" => (
+                    quote! { bool },
+                    quote! { data[#offset] != 0 },
+                    quote! { data[#offset] = if value { 1 } else { 0 }; },
+                ),
+                "Pubkey" => (
+                    quote! { &[u8; 32] },
+                    quote! { data[#offset..#offset + 32].try_into().unwrap() },
+                    quote! { data[#offset..#offset + 32].copy_from_slice(value); },
+                ),
+                _ => (
+                    quote! { #ty },
+                    quote! { <#ty>::from_le_bytes(data[#offset..#offset + #size].try_into().unwrap()) },
+                    quote! { data[#offset..#offset + #size].copy_from_slice(&value.to_le_bytes()); },
+                ),
             }
         }
-        Type::Array(arr) => {
-            if let Some(size) = type_size(ty) {
-                quote! {
-                    let #name: #ty = data[#offset..#offset + #size].try_into().ok()?;
-                }
-            } else {
-                quote! {
-                    let #name: #ty = Default::default(); // Unknown array
-                }
+        Type::Array(_) => (
+            quote! { &#ty },
+            quote! { data[#offset..#offset + #size].try_into().unwrap() },
+            quote! { data[#offset..#offset + #size].copy_from_slice(value); },
+        ),
+        _ => return None,
+    };
+
+    Some(accessors_from(component, name, &value_ty, &read, &write))
+}
+
+/// `FieldAccessors` from a value type, a read expression over `data` and a
+/// write statement storing `value` into `data`
+pub fn accessors_from(
+    component: &syn::Ident,
+    name: &syn::Ident,
+    value_ty: &TokenStream,
+    read: &TokenStream,
+    write: &TokenStream,
+) -> FieldAccessors {
+    let read_name = syn::Ident::new(&format!("read_{}", name), name.span());
+    let write_name = syn::Ident::new(&format!("write_{}", name), name.span());
+    let setter_name = syn::Ident::new(&format!("set_{}", name), name.span());
+    let read_doc = format!(
+        "Read `{}` from raw account data\n\nPanics if `data` is shorter than `SIZE`; `view` checks it once.",
+        name
+    );
+    let write_doc = format!(
+        "Write `{}` into raw account data\n\nPanics if `data` is shorter than `SIZE`; `view_mut` checks it once.",
+        name
+    );
+
+    FieldAccessors {
+        raw: quote! {
+            #[doc = #read_doc]
+            #[inline]
+            pub fn #read_name(data: &[u8]) -> #value_ty {
+                #read
+            }
+
+            #[doc = #write_doc]
+            #[inline]
+            pub fn #write_name(data: &mut [u8], value: #value_ty) {
+                #write
+            }
+        },
+        getter: quote! {
+            #[inline]
+            fn #name(&self) -> #value_ty {
+                #component::#read_name(self.bytes())
+            }
+        },
+        setter: quote! {
+            #[inline]
+            fn #setter_name(&mut self, value: #value_ty) {
+                #component::#write_name(self.bytes_mut(), value)
             }
-        }
-        _ => quote! {
-            let #name = Default::default();
         },
     }
 }
 
-/// Convert a string to a discriminator (8 bytes, padded with zeros)
-pub fn string_to_discriminator(s: &str) -> [u8; 8] {
-    let mut disc = [0u8; 8];
-    let bytes = s.as_bytes();
-    let len = bytes.len().min(8);
-    disc[..len].copy_from_slice(&bytes[..len]);
-    disc
+/// Unsigned integer type of a bit field group's word
+pub fn word_type(slot: &BitSlot) -> syn::Ident {
+    syn::Ident::new(&format!("u{}", slot.word_size * 8), proc_macro2::Span::call_site())
+}
+
+/// Width of a bit field: `#[bits(N)]` on a `bool` or unsigned integer, or
+/// `#[flags]` (one bit) on a `bool`
+pub fn bit_width(field: &Field) -> syn::Result<Option<u32>> {
+    let bits = field.attrs.iter().find(|attr| attr.path().is_ident("bits"));
+    let flags = field.attrs.iter().find(|attr| attr.path().is_ident("flags"));
+    let width = match (bits, flags) {
+        (None, None) => return Ok(None),
+        (Some(_), Some(flags)) => {
+            return Err(syn::Error::new_spanned(flags, "use either #[bits(N)] or #[flags], not both"));
+        }
+        (Some(bits), None) => bits.parse_args::<syn::LitInt>()?.base10_parse::<u32>()?,
+        (None, Some(_)) => 1,
+    };
+
+    for conflict in ["entity", "pda_bump", "max_len"] {
+        if let Some(attr) = field.attrs.iter().find(|attr| attr.path().is_ident(conflict)) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("#[{}] fields can't be bit fields", conflict),
+            ));
+        }
+    }
+
+    let ident = match &field.ty {
+        Type::Path(type_path) => type_path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    };
+    let type_bits = match ident.as_deref() {
+        Some("bool") => 1,
+        Some("u8") => 8,
+        Some("u16") => 16,
+        Some("u32") => 32,
+        Some("u64") => 64,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "bit fields must be bool or an unsigned integer",
+            ));
+        }
+    };
+    if flags.is_some() && type_bits != 1 {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            "#[flags] fields must be bool; use #[bits(N)] for integers",
+        ));
+    }
+    if width == 0 || width > type_bits {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            format!("#[bits({})] doesn't fit this type ({} bits)", width, type_bits),
+        ));
+    }
+    Ok(Some(width))
+}
+
+/// `BitSlot` of each field, `None` for fields that aren't bit fields
+///
+/// Groups follow `golt_layout::bit_slots`: `#[skip]` fields are ignored and an
+/// `#[offset]` / `#[padding]` attribute starts a new group.
+pub fn bit_slots<'a>(fields: impl IntoIterator<Item = &'a Field>) -> syn::Result<Vec<Option<BitSlot>>> {
+    let inputs = fields
+        .into_iter()
+        .map(|field| {
+            let has = |name: &str| field.attrs.iter().any(|attr| attr.path().is_ident(name));
+            let skip = has("skip");
+            Ok(BitInput {
+                width: if skip { None } else { bit_width(field)? },
+                moved: has("offset") || has("padding"),
+                skip,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(golt_layout::bit_slots(inputs))
+}
+
+/// `Arbitrary` impl for `name` returning `body`, plus a round-trip `#[test]`
+/// running `check`
+///
+/// Both only expand with golt-runtime's `arbitrary` feature. Type parameters
+/// get an `Arbitrary` bound.
+pub fn generate_arbitrary(
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    body: TokenStream,
+    check: Option<TokenStream>,
+) -> TokenStream {
+    let mut arbitrary_generics = generics.clone();
+    for param in arbitrary_generics.type_params_mut() {
+        param
+            .bounds
+            .push(syn::parse_quote!(golt_runtime::arbitrary::Arbitrary<'arbitrary>));
+    }
+    arbitrary_generics.params.insert(0, syn::parse_quote!('arbitrary));
+    let (impl_generics, _, where_clause) = arbitrary_generics.split_for_impl();
+    let (_, ty_generics, _) = generics.split_for_impl();
+
+    let test = check.map(|check| {
+        let test_name = syn::Ident::new(
+            &format!("golt_{}_roundtrip", heck::AsSnakeCase(name.to_string())),
+            name.span(),
+        );
+        quote! {
+            #[cfg(test)]
+            #[test]
+            fn #test_name() {
+                #check
+            }
+        }
+    });
+
+    quote! {
+        golt_runtime::__arbitrary! {
+            impl #impl_generics golt_runtime::arbitrary::Arbitrary<'arbitrary> for #name #ty_generics #where_clause {
+                #[allow(unused_variables)]
+                fn arbitrary(
+                    u: &mut golt_runtime::arbitrary::Unstructured<'arbitrary>,
+                ) -> golt_runtime::arbitrary::Result<Self> {
+                    #body
+                }
+            }
+
+            #test
+        }
+    }
 }
@@ -1,33 +1,27 @@
 //! Entity Registry error types
 
-use pinocchio::program_error::ProgramError;
+use golt_runtime::ErrorCode;
 
-/// Entity Registry errors
-#[derive(Clone, Copy, Debug, PartialEq)]
-#[repr(u32)]
+/// Entity Registry errors, codes 2000-2999
+#[derive(Clone, Copy, Debug, PartialEq, ErrorCode)]
+#[golt_error(offset = 2000)]
 pub enum RegistryError {
     /// Invalid instruction discriminator
-    InvalidInstruction = 0,
+    InvalidInstruction,
     /// Entity already exists
-    EntityAlreadyExists = 1,
+    EntityAlreadyExists,
     /// Entity not found
-    EntityNotFound = 2,
+    EntityNotFound,
     /// Invalid entity discriminator
-    InvalidEntityDiscriminator = 3,
+    InvalidEntityDiscriminator,
     /// Unauthorized - not the owner
-    Unauthorized = 4,
+    Unauthorized,
     /// Entity is not active
-    EntityNotActive = 5,
+    EntityNotActive,
     /// Invalid PDA
-    InvalidPda = 6,
+    InvalidPda,
     /// Account not writable
-    AccountNotWritable = 7,
+    AccountNotWritable,
     /// Missing required signature
-    MissingSignature = 8,
-}
-
-impl From<RegistryError> for ProgramError {
-    fn from(e: RegistryError) -> Self {
-        ProgramError::Custom(e as u32)
-    }
+    MissingSignature,
 }
@@ -8,6 +8,7 @@ use pinocchio::{
     sysvars::{rent::Rent, Sysvar},
     ProgramResult,
 };
+use golt_runtime::pda::verify_pda_with_bump;
 use pinocchio_system::instructions::CreateAccount;
 
 use crate::{
@@ -31,8 +32,12 @@ pub fn process_instruction(
 
     match instruction_data[0] {
         discriminator::CREATE => process_create_entity(program_id, accounts, instruction_data),
-        discriminator::TRANSFER => process_transfer_ownership(accounts, instruction_data),
-        discriminator::DEACTIVATE => process_deactivate_entity(accounts, instruction_data),
+        discriminator::TRANSFER => {
+            process_transfer_ownership(program_id, accounts, instruction_data)
+        }
+        discriminator::DEACTIVATE => {
+            process_deactivate_entity(program_id, accounts, instruction_data)
+        }
         _ => Err(RegistryError::InvalidInstruction.into()),
     }
 }
@@ -104,8 +109,28 @@ fn process_create_entity(
     Ok(())
 }
 
+/// Verify an entity account's address using its stored bump
+fn verify_entity_pda(
+    entity_account: &AccountInfo,
+    entity: &Entity,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let entity_id_bytes = entity.id.to_le_bytes();
+    verify_pda_with_bump(
+        entity_account.key(),
+        &[ENTITY_SEED, &entity_id_bytes],
+        entity.bump,
+        program_id,
+    )
+    .map_err(|_| RegistryError::InvalidPda.into())
+}
+
 /// Process transfer ownership instruction
-fn process_transfer_ownership(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+fn process_transfer_ownership(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
     let _instruction = TransferOwnershipInstruction::unpack(instruction_data)
         .ok_or(RegistryError::InvalidInstruction)?;
 
@@ -132,6 +157,9 @@ fn process_transfer_ownership(accounts: &[AccountInfo], instruction_data: &[u8])
     let mut entity = Entity::unpack(&data).ok_or(RegistryError::InvalidEntityDiscriminator)?;
     drop(data);
 
+    // Verify PDA with the stored bump
+    verify_entity_pda(entity_account, &entity, program_id)?;
+
     // Verify ownership
     if entity.owner != *owner.key() {
         return Err(RegistryError::Unauthorized.into());
@@ -152,7 +180,11 @@ fn process_transfer_ownership(accounts: &[AccountInfo], instruction_data: &[u8])
 }
 
 /// Process deactivate entity instruction
-fn process_deactivate_entity(accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+fn process_deactivate_entity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
     let _instruction = DeactivateEntityInstruction::unpack(instruction_data)
         .ok_or(RegistryError::InvalidInstruction)?;
 
@@ -178,6 +210,9 @@ fn process_deactivate_entity(accounts: &[AccountInfo], instruction_data: &[u8])
     let mut entity = Entity::unpack(&data).ok_or(RegistryError::InvalidEntityDiscriminator)?;
     drop(data);
 
+    // Verify PDA with the stored bump
+    verify_entity_pda(entity_account, &entity, program_id)?;
+
     // Verify ownership
     if entity.owner != *owner.key() {
         return Err(RegistryError::Unauthorized.into());
@@ -5,11 +5,10 @@ use pinocchio::{
     instruction::{Seed, Signer},
     program_error::ProgramError,
     pubkey::Pubkey,
-    sysvars::{rent::Rent, Sysvar},
 };
-use pinocchio_system::instructions::CreateAccount;
+use pinocchio_system::instructions::{CreateAccount, Transfer};
 
-use crate::{Component, GoltError};
+use crate::{Component, GoltError, Migrate};
 
 /// Wrapper for accounts that provides validation and typed access
 pub struct AccountContext<'a> {
@@ -62,12 +61,85 @@ impl<'a> AccountContext<'a> {
         Ok(account)
     }
 
+    /// Get the next account, checking it is the program `id`
+    pub fn next_program(&mut self, id: &Pubkey) -> Result<&'a AccountInfo, ProgramError> {
+        let account = self.next()?;
+        if account.key() != id {
+            return Err(GoltError::InvalidProgramId.into());
+        }
+        Ok(account)
+    }
+
     /// Get remaining accounts
     pub fn remaining(&self) -> &'a [AccountInfo] {
         &self.accounts[self.index..]
     }
+
+    /// Parse and validate the next accounts as `T`
+    pub fn parse<T: Accounts<'a>>(&mut self, program_id: &Pubkey) -> Result<T, ProgramError> {
+        T::try_accounts(self, program_id)
+    }
+}
+
+/// A validated set of instruction accounts
+///
+/// Implemented by `#[derive(Accounts)]`.
+pub trait Accounts<'a>: Sized {
+    /// Take accounts from `ctx` in declaration order and validate them
+    fn try_accounts(ctx: &mut AccountContext<'a>, program_id: &Pubkey) -> Result<Self, ProgramError>;
 }
 
+/// Well-known program IDs for `#[account(program = ...)]`
+pub mod program_ids {
+    use pinocchio::pubkey::Pubkey;
+
+    /// System program
+    pub const SYSTEM: Pubkey = pinocchio_system::ID;
+
+    /// MagicBlock delegation program
+    pub const DELEGATION: Pubkey = crate::delegation::DELEGATION_PROGRAM;
+
+    /// Upgradeable BPF loader, which owns each program's ProgramData account
+    pub const BPF_LOADER_UPGRADEABLE: Pubkey =
+        pinocchio_pubkey::pubkey!("BPFLoaderUpgradeab1e11111111111111111111111");
+}
+
+/// Check that an account is owned by `owner` and holds a `C`
+pub fn check_component_account<C: Component>(
+    account: &AccountInfo,
+    owner: &Pubkey,
+) -> Result<(), ProgramError> {
+    if unsafe { account.owner() } != owner {
+        return Err(GoltError::InvalidProgramId.into());
+    }
+    let data = account.try_borrow_data()?;
+    if !C::verify_discriminator(&data) {
+        return Err(GoltError::InvalidDiscriminator.into());
+    }
+    if data.len() < C::SIZE {
+        return Err(GoltError::InvalidAccountData.into());
+    }
+    Ok(())
+}
+
+/// The Rent sysvar
+///
+/// Host tests and fuzzing (`arbitrary` feature off-chain) get the cluster's
+/// default rent instead, as there is no sysvar to read.
+#[cfg(not(any(test, all(feature = "arbitrary", not(target_os = "solana")))))]
+pub fn rent() -> Result<pinocchio::sysvars::rent::Rent, ProgramError> {
+    pinocchio::sysvars::Sysvar::get()
+}
+
+#[cfg(any(test, all(feature = "arbitrary", not(target_os = "solana"))))]
+pub use crate::test_utils::rent;
+
+/// Discriminator written into component accounts closed by `close_component_account`
+///
+/// A zero-lamport account is only reclaimed at the end of the transaction,
+/// so until then this marker keeps it from being loaded or re-initialised.
+pub const CLOSED_ACCOUNT_DISCRIMINATOR: [u8; 8] = [0xff; 8];
+
 /// Initialize a new PDA account for a component
 pub fn init_component_account<'a, C: Component>(
     payer: &AccountInfo,
@@ -75,7 +147,12 @@ pub fn init_component_account<'a, C: Component>(
     program_id: &Pubkey,
     signer_seeds: &[&[u8]],
 ) -> Result<(), ProgramError> {
-    let rent = Rent::get()?;
+    // Refuse to recreate an account closed earlier in this transaction
+    if account.try_borrow_data()?.starts_with(&CLOSED_ACCOUNT_DISCRIMINATOR) {
+        return Err(GoltError::AccountClosed.into());
+    }
+
+    let rent = rent()?;
     let lamports = rent.minimum_balance(C::SIZE);
 
     // Build signer
@@ -101,6 +178,110 @@ pub fn init_component_account<'a, C: Component>(
     Ok(())
 }
 
+/// Close a component account and send its lamports to `recipient`
+///
+/// The account must be owned by `program_id` and hold a `C`. Its data is
+/// zeroed and marked with `CLOSED_ACCOUNT_DISCRIMINATOR`.
+pub fn close_component_account<C: Component>(
+    account: &AccountInfo,
+    recipient: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if unsafe { account.owner() } != program_id {
+        return Err(GoltError::InvalidProgramId.into());
+    }
+    if !account.is_writable() || !recipient.is_writable() {
+        return Err(GoltError::AccountNotWritable.into());
+    }
+    if account.key() == recipient.key() {
+        return Err(GoltError::InvalidAccountData.into());
+    }
+
+    {
+        let mut data = account.try_borrow_mut_data()?;
+        if !C::verify_discriminator(&data) {
+            return Err(GoltError::InvalidDiscriminator.into());
+        }
+        data.fill(0);
+        data[0..8].copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);
+    }
+
+    let lamports = account.lamports();
+    let mut recipient_lamports = recipient.try_borrow_mut_lamports()?;
+    *recipient_lamports = recipient_lamports
+        .checked_add(lamports)
+        .ok_or(GoltError::Overflow)?;
+    *account.try_borrow_mut_lamports()? = 0;
+
+    Ok(())
+}
+
+/// Upgrade a versioned component account to the current layout of `C`
+///
+/// Reads the stored version byte and, if it is older than `C::VERSION`,
+/// decodes the data with `C::migrate`, resizes the account to `C::SIZE`, tops
+/// up rent from `payer` and writes the current layout back. Accounts of
+/// `C::LEGACY_SIZE`, written before the component was versioned, are
+/// migrated as version 0. Returns `false` if the account was already up to date.
+pub fn migrate_component_account<C: Migrate>(
+    account: &AccountInfo,
+    payer: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if unsafe { account.owner() } != program_id {
+        return Err(GoltError::InvalidProgramId.into());
+    }
+    if !account.is_writable() {
+        return Err(GoltError::AccountNotWritable.into());
+    }
+
+    let data = account.try_borrow_data()?;
+    if !C::verify_discriminator(&data) {
+        return Err(GoltError::InvalidDiscriminator.into());
+    }
+    // Unversioned accounts have entity or field bytes where the version goes
+    let version = if C::LEGACY_SIZE == Some(data.len()) {
+        0
+    } else {
+        *data.get(8).ok_or(GoltError::InvalidAccountData)?
+    };
+    if version == C::VERSION {
+        return Ok(false);
+    }
+    if version > C::VERSION {
+        return Err(GoltError::UnsupportedVersion.into());
+    }
+    let component = C::migrate(version, &data).ok_or(GoltError::UnsupportedVersion)?;
+    drop(data);
+
+    if account.data_len() < C::SIZE {
+        let rent = rent()?;
+        let required = rent.minimum_balance(C::SIZE);
+        let lamports = account.lamports();
+        if lamports < required {
+            if !payer.is_signer() {
+                return Err(GoltError::AccountNotSigner.into());
+            }
+            Transfer {
+                from: payer,
+                to: account,
+                lamports: required - lamports,
+            }
+            .invoke()?;
+        }
+    }
+    // Legacy layouts may also be larger, and must not keep their old size
+    if account.data_len() != C::SIZE {
+        account.realloc(C::SIZE, true)?;
+    }
+
+    component.validate()?;
+    let mut data = account.try_borrow_mut_data()?;
+    component.pack(&mut data);
+
+    Ok(true)
+}
+
 /// Load a component from an account
 pub fn load_component<C: Component>(account: &AccountInfo) -> Result<C, ProgramError> {
     let data = account.try_borrow_data()?;
@@ -108,6 +289,9 @@ pub fn load_component<C: Component>(account: &AccountInfo) -> Result<C, ProgramE
 }
 
 /// Load a component mutably from an account
+///
+/// Changes are only written back when `save` or `flush` is called.
+/// Use `load_component_autosave` to write back on drop instead.
 pub fn load_component_mut<'a, C: Component>(
     account: &'a AccountInfo,
 ) -> Result<ComponentMut<'a, C>, ProgramError> {
@@ -117,21 +301,69 @@ pub fn load_component_mut<'a, C: Component>(
     Ok(ComponentMut {
         account,
         component,
+        dirty: false,
+        autosave: false,
     })
 }
 
-/// Mutable component wrapper that saves on drop
+/// Load a component mutably from an account, writing it back on drop if modified
+pub fn load_component_autosave<'a, C: Component>(
+    account: &'a AccountInfo,
+) -> Result<ComponentMut<'a, C>, ProgramError> {
+    let mut component = load_component_mut(account)?;
+    component.autosave = true;
+    Ok(component)
+}
+
+/// Mutable component wrapper with dirty tracking
+///
+/// Any mutable access (`get_mut` or `DerefMut`) marks the component dirty.
+/// The wrapped component is not a public field, since writes through it
+/// would bypass that tracking: code using `m.component.x` should use `m.x`
+/// (`Deref` / `DerefMut`) or `m.get()` / `m.get_mut()` instead.
+/// In autosave mode (`load_component_autosave`) a dirty component is written
+/// back when the wrapper is dropped; call `discard` to drop the changes instead.
+///
+/// `Drop` cannot return an error, so if the account data can't be borrowed or
+/// the component fails `validate` at that point the write-back panics, which
+/// aborts the transaction rather than silently losing the change. Call `flush`
+/// first to handle the error yourself.
 pub struct ComponentMut<'a, C: Component> {
     account: &'a AccountInfo,
-    pub component: C,
+    component: C,
+    dirty: bool,
+    autosave: bool,
 }
 
 impl<'a, C: Component> ComponentMut<'a, C> {
-    /// Save the component back to the account
-    pub fn save(self) -> Result<(), ProgramError> {
-        let mut data = self.account.try_borrow_mut_data()?;
-        self.component.pack(&mut data);
-        Ok(())
+    /// Save the component back to the account, whether or not it was modified
+    pub fn save(mut self) -> Result<(), ProgramError> {
+        self.dirty = false;
+        self.write()
+    }
+
+    /// Write the component back if it was modified, keeping the wrapper alive
+    pub fn flush(&mut self) -> Result<(), ProgramError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+        self.write()
+    }
+
+    /// Drop the wrapper without writing any pending changes
+    pub fn discard(mut self) {
+        self.dirty = false;
+    }
+
+    /// Whether the component was modified since it was loaded or last written
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Whether the component is written back on drop
+    pub fn is_autosave(&self) -> bool {
+        self.autosave
     }
 
     /// Get a reference to the inner component
@@ -139,10 +371,26 @@ impl<'a, C: Component> ComponentMut<'a, C> {
         &self.component
     }
 
-    /// Get a mutable reference to the inner component
+    /// Get a mutable reference to the inner component, marking it dirty
     pub fn get_mut(&mut self) -> &mut C {
+        self.dirty = true;
         &mut self.component
     }
+
+    fn write(&self) -> Result<(), ProgramError> {
+        self.component.validate()?;
+        let mut data = self.account.try_borrow_mut_data()?;
+        self.component.pack(&mut data);
+        Ok(())
+    }
+}
+
+impl<'a, C: Component> Drop for ComponentMut<'a, C> {
+    fn drop(&mut self) {
+        if self.autosave && self.dirty && self.write().is_err() {
+            panic!("ComponentMut: failed to write back dirty component");
+        }
+    }
 }
 
 impl<'a, C: Component> std::ops::Deref for ComponentMut<'a, C> {
@@ -154,6 +402,429 @@ impl<'a, C: Component> std::ops::Deref for ComponentMut<'a, C> {
 
 impl<'a, C: Component> std::ops::DerefMut for ComponentMut<'a, C> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.component
+        self.get_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::{Accounts, Component};
+
+    use super::*;
+    use crate::test_utils::TestAccount;
+
+    const PROGRAM_ID: Pubkey = [5; 32];
+    const AUTHORITY: Pubkey = [2; 32];
+    const ENTITY: Pubkey = [3; 32];
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "health", reserved = 4)]
+    struct Health {
+        #[entity]
+        entity: [u8; 32],
+        current: u32,
+        #[max_len(2)]
+        buffs: Vec<u8>,
+    }
+
+    /// Bytes of `Health`'s reserved region, which `pack` zeroes
+    const RESERVED: core::ops::Range<usize> = Health::SIZE - 4..Health::SIZE;
+
+    /// Health account with its reserved bytes set, showing whether it was
+    /// written back
+    fn health_account(current: u32) -> TestAccount {
+        let mut data = vec![0; Health::SIZE];
+        Health {
+            entity: [3; 32],
+            current,
+            buffs: vec![1],
+        }
+        .pack(&mut data);
+        data[RESERVED].fill(0xaa);
+        TestAccount::new([6; 32], PROGRAM_ID, &data)
+    }
+
+    fn is_rewritten(account: &AccountInfo) -> bool {
+        account.try_borrow_data().unwrap()[RESERVED] == [0; 4]
+    }
+
+    #[test]
+    fn autosave_writes_back_on_drop() {
+        let mut account = health_account(7);
+        let account = account.info();
+        {
+            let mut health = load_component_autosave::<Health>(&account).unwrap();
+            health.current = 3;
+            health.buffs.push(2);
+            assert!(health.is_dirty());
+        }
+        assert!(is_rewritten(&account));
+        let health = load_component::<Health>(&account).unwrap();
+        assert_eq!((health.current, health.buffs), (3, vec![1, 2]));
+    }
+
+    #[test]
+    fn untouched_component_is_not_rewritten() {
+        let mut account = health_account(7);
+        let account = account.info();
+        {
+            let health = load_component_autosave::<Health>(&account).unwrap();
+            assert_eq!(health.current, 7);
+            assert_eq!(health.get().buffs, [1]);
+            assert!(!health.is_dirty());
+        }
+        assert!(!is_rewritten(&account));
+
+        // Without autosave only `save` / `flush` write
+        {
+            let mut health = load_component_mut::<Health>(&account).unwrap();
+            health.current = 3;
+        }
+        assert!(!is_rewritten(&account));
+        assert_eq!(load_component::<Health>(&account).unwrap().current, 7);
+
+        let mut health = load_component_mut::<Health>(&account).unwrap();
+        health.flush().unwrap();
+        assert!(!is_rewritten(&account));
+        health.get_mut().current = 3;
+        health.flush().unwrap();
+        assert!(!health.is_dirty());
+        assert_eq!(load_component::<Health>(&account).unwrap().current, 3);
+    }
+
+    #[test]
+    fn discard_drops_changes() {
+        let mut account = health_account(7);
+        let account = account.info();
+        let mut health = load_component_autosave::<Health>(&account).unwrap();
+        health.current = 3;
+        health.discard();
+        assert!(!is_rewritten(&account));
+        assert_eq!(load_component::<Health>(&account).unwrap().current, 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to write back dirty component")]
+    fn failed_write_back_on_drop_panics() {
+        let mut account = health_account(7);
+        let account = account.info();
+        let mut health = load_component_autosave::<Health>(&account).unwrap();
+        // Over `#[max_len(2)]`, so `validate` fails when written back
+        health.buffs.extend([2, 3]);
+    }
+
+    #[test]
+    fn close_checks_owner_and_discriminator() {
+        let mut recipient = TestAccount::new([7; 32], [0; 32], &[]);
+        let recipient = recipient.info();
+
+        let mut foreign = health_account(7);
+        assert_eq!(
+            close_component_account::<Health>(&foreign.info(), &recipient, &[9; 32]),
+            Err(GoltError::InvalidProgramId.into())
+        );
+
+        let mut other = TestAccount::new([6; 32], PROGRAM_ID, &[1; Health::SIZE]);
+        assert_eq!(
+            close_component_account::<Health>(&other.info(), &recipient, &PROGRAM_ID),
+            Err(GoltError::InvalidDiscriminator.into())
+        );
+
+        let mut account = health_account(7);
+        let account = account.info();
+        let mut readonly = TestAccount::with_flags([7; 32], [0; 32], 0, false, false, &[]);
+        assert_eq!(
+            close_component_account::<Health>(&account, &readonly.info(), &PROGRAM_ID),
+            Err(GoltError::AccountNotWritable.into())
+        );
+        assert_eq!(
+            close_component_account::<Health>(&account, &account, &PROGRAM_ID),
+            Err(GoltError::InvalidAccountData.into())
+        );
+        // Nothing was touched by the rejected closes
+        assert_eq!(account.lamports(), 1_000_000);
+        assert!(load_component::<Health>(&account).is_ok());
+    }
+
+    #[test]
+    fn close_moves_lamports_and_marks_account_closed() {
+        let mut account = health_account(7);
+        let account = account.info();
+        let mut recipient = TestAccount::with_flags([7; 32], [0; 32], 500, false, true, &[]);
+        let recipient = recipient.info();
+
+        assert_eq!(
+            close_component_account::<Health>(&account, &recipient, &PROGRAM_ID),
+            Ok(())
+        );
+        assert_eq!(recipient.lamports(), 1_000_500);
+        assert_eq!(account.lamports(), 0);
+        {
+            let data = account.try_borrow_data().unwrap();
+            assert_eq!(data[..8], CLOSED_ACCOUNT_DISCRIMINATOR);
+            assert!(data[8..].iter().all(|&byte| byte == 0));
+        }
+
+        // The closed account can't be loaded, closed again or re-initialised
+        assert!(load_component::<Health>(&account).is_err());
+        assert_eq!(
+            close_component_account::<Health>(&account, &recipient, &PROGRAM_ID),
+            Err(GoltError::InvalidDiscriminator.into())
+        );
+        let mut payer = TestAccount::new([8; 32], [0; 32], &[]).signer();
+        assert_eq!(
+            init_component_account::<Health>(&payer.info(), &account, &PROGRAM_ID, &[b"health"]),
+            Err(GoltError::AccountClosed.into())
+        );
+    }
+
+    /// `Armor` before it was versioned
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "armor")]
+    struct LegacyArmor {
+        #[entity]
+        entity: [u8; 32],
+        value: u32,
+    }
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "armor", version = 2)]
+    struct ArmorV2 {
+        #[entity]
+        entity: [u8; 32],
+        value: u32,
+    }
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(
+        seed = "armor",
+        version = 3,
+        migrate = "upgrade_armor",
+        legacy_size = 44
+    )]
+    struct Armor {
+        #[entity]
+        entity: [u8; 32],
+        value: u32,
+        durability: u16,
+    }
+
+    /// Upgrades legacy and version 2 accounts; version 1 is unknown
+    fn upgrade_armor(version: u8, data: &[u8]) -> Option<Armor> {
+        let (entity, value, durability) = match version {
+            0 => LegacyArmor::unpack(data).map(|old| (old.entity, old.value, 100))?,
+            2 => ArmorV2::unpack(data).map(|old| (old.entity, old.value, 50))?,
+            _ => return None,
+        };
+        Some(Armor {
+            entity,
+            value,
+            durability,
+        })
+    }
+
+    fn packed<C: Component>(component: C) -> Vec<u8> {
+        let mut data = vec![0; C::SIZE];
+        component.pack(&mut data);
+        data
+    }
+
+    #[test]
+    fn migrates_legacy_account() {
+        let legacy = packed(LegacyArmor {
+            entity: [3; 32],
+            value: 9,
+        });
+        assert_eq!(legacy.len(), 44);
+        // Below rent for the new size, so the payer must sign for the top-up
+        let mut account = TestAccount::new([6; 32], PROGRAM_ID, &legacy);
+        let account = account.info();
+        let mut unsigned = TestAccount::new([8; 32], [0; 32], &[]);
+        assert_eq!(
+            migrate_component_account::<Armor>(&account, &unsigned.info(), &PROGRAM_ID),
+            Err(GoltError::AccountNotSigner.into())
+        );
+        assert_eq!(account.data_len(), 44);
+
+        let mut payer = TestAccount::new([8; 32], [0; 32], &[]).signer();
+        let payer = payer.info();
+        assert_eq!(
+            migrate_component_account::<Armor>(&account, &payer, &PROGRAM_ID),
+            Ok(true)
+        );
+        assert_eq!(account.data_len(), Armor::SIZE);
+        assert_eq!(account.try_borrow_data().unwrap()[8], Armor::VERSION);
+        assert_eq!(
+            load_component::<Armor>(&account),
+            Ok(Armor {
+                entity: [3; 32],
+                value: 9,
+                durability: 100
+            })
+        );
+
+        // Already current
+        assert_eq!(
+            migrate_component_account::<Armor>(&account, &payer, &PROGRAM_ID),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn migrates_by_version_byte() {
+        let old = packed(ArmorV2 {
+            entity: [3; 32],
+            value: 9,
+        });
+        assert_eq!(old[8], 2);
+        // Enough lamports for the new size, so no top-up and no signature
+        let mut account =
+            TestAccount::with_flags([6; 32], PROGRAM_ID, 2_000_000, false, true, &old);
+        let account = account.info();
+        let mut payer = TestAccount::new([8; 32], [0; 32], &[]);
+        let payer = payer.info();
+
+        // Versioned data isn't loaded as the current layout until migrated
+        assert!(load_component::<Armor>(&account).is_err());
+        assert_eq!(
+            migrate_component_account::<Armor>(&account, &payer, &PROGRAM_ID),
+            Ok(true)
+        );
+        assert_eq!(account.data_len(), Armor::SIZE);
+        assert_eq!(account.try_borrow_data().unwrap()[8], 3);
+        assert_eq!(load_component::<Armor>(&account).unwrap().durability, 50);
+    }
+
+    #[test]
+    fn migrate_rejects_unknown_versions() {
+        let mut payer = TestAccount::new([8; 32], [0; 32], &[]).signer();
+        let payer = payer.info();
+        let current = packed(Armor {
+            entity: [3; 32],
+            value: 9,
+            durability: 1,
+        });
+
+        // Newer than the program, and older without a migration
+        for version in [4, 1] {
+            let mut data = current.clone();
+            data[8] = version;
+            let mut account = TestAccount::new([6; 32], PROGRAM_ID, &data);
+            let account = account.info();
+            assert_eq!(
+                migrate_component_account::<Armor>(&account, &payer, &PROGRAM_ID),
+                Err(GoltError::UnsupportedVersion.into())
+            );
+            assert_eq!(account.try_borrow_data().unwrap()[..], data[..]);
+        }
+
+        let mut foreign = TestAccount::new([6; 32], [9; 32], &current);
+        assert_eq!(
+            migrate_component_account::<Armor>(&foreign.info(), &payer, &PROGRAM_ID),
+            Err(GoltError::InvalidProgramId.into())
+        );
+        let mut other = health_account(7);
+        assert_eq!(
+            migrate_component_account::<Armor>(&other.info(), &payer, &PROGRAM_ID),
+            Err(GoltError::InvalidDiscriminator.into())
+        );
+    }
+
+    #[derive(Accounts)]
+    struct Damage<'a> {
+        #[account(signer)]
+        authority: &'a AccountInfo,
+        entity: &'a AccountInfo,
+        #[account(mut, component = Health, seeds = [entity])]
+        health: &'a AccountInfo,
+        #[account(program = SYSTEM)]
+        system_program: &'a AccountInfo,
+    }
+
+    fn health_pda() -> Pubkey {
+        Health::derive_pda(&[&ENTITY], &PROGRAM_ID).0
+    }
+
+    fn health_data() -> Vec<u8> {
+        packed(Health {
+            entity: ENTITY,
+            current: 7,
+            buffs: Vec::new(),
+        })
+    }
+
+    /// `Damage` accounts that pass every check
+    fn damage_accounts() -> Vec<TestAccount> {
+        vec![
+            TestAccount::new(AUTHORITY, [0; 32], &[]).signer(),
+            TestAccount::new(ENTITY, [1; 32], &[]),
+            TestAccount::new(health_pda(), PROGRAM_ID, &health_data()),
+            TestAccount::with_flags(program_ids::SYSTEM, [0; 32], 1, false, false, &[]),
+        ]
+    }
+
+    /// Parse `Damage` from `accounts` with account `index` replaced
+    fn parse_with(index: usize, account: TestAccount) -> Result<(), ProgramError> {
+        let mut accounts = damage_accounts();
+        accounts[index] = account;
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        Damage::from_accounts(&infos, &PROGRAM_ID).map(|_| ())
+    }
+
+    #[test]
+    fn accounts_derive_takes_accounts_in_order() {
+        let mut accounts = damage_accounts();
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        let damage = Damage::from_accounts(&infos, &PROGRAM_ID).unwrap();
+        assert_eq!(damage.authority.key(), &AUTHORITY);
+        assert_eq!(damage.entity.key(), &ENTITY);
+        assert_eq!(damage.health.key(), &health_pda());
+        assert_eq!(damage.system_program.key(), &program_ids::SYSTEM);
+
+        assert_eq!(
+            Damage::from_accounts(&infos[..3], &PROGRAM_ID).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn accounts_derive_error_codes() {
+        assert_eq!(
+            parse_with(0, TestAccount::new(AUTHORITY, [0; 32], &[])),
+            Err(GoltError::AccountNotSigner.into())
+        );
+        assert_eq!(
+            parse_with(
+                2,
+                TestAccount::with_flags(health_pda(), PROGRAM_ID, 1, false, false, &health_data())
+            ),
+            Err(GoltError::AccountNotWritable.into())
+        );
+        assert_eq!(
+            parse_with(2, TestAccount::new(health_pda(), [9; 32], &health_data())),
+            Err(GoltError::InvalidProgramId.into())
+        );
+        assert_eq!(
+            parse_with(
+                2,
+                TestAccount::new(health_pda(), PROGRAM_ID, &[1; Health::SIZE])
+            ),
+            Err(GoltError::InvalidDiscriminator.into())
+        );
+        assert_eq!(
+            parse_with(
+                2,
+                TestAccount::new(health_pda(), PROGRAM_ID, &health_data()[..40])
+            ),
+            Err(GoltError::InvalidAccountData.into())
+        );
+        assert_eq!(
+            parse_with(2, TestAccount::new([6; 32], PROGRAM_ID, &health_data())),
+            Err(GoltError::InvalidPda.into())
+        );
+        assert_eq!(
+            parse_with(3, TestAccount::new([9; 32], [0; 32], &[])),
+            Err(GoltError::InvalidProgramId.into())
+        );
     }
 }
@@ -0,0 +1,903 @@
+//! System allowlists
+//!
+//! Any program can call a component program, so components that should only
+//! be written by approved systems keep an allowlist PDA (`["allowlist"]`
+//! under the component program) managed by an admin.
+//!
+//! The allowlist sits at a fixed address, so whoever creates it first would
+//! become its admin. `init_allowlist` therefore requires the admin to be the
+//! program's upgrade authority, proven by the program's ProgramData account.
+//!
+//! A system proves its identity by signing the CPI with its own
+//! `["system_signer"]` PDA. Only the owning program can sign for a PDA, so a
+//! signer that matches an allowlisted entry must come from that system.
+//! The signer address is derived once when the system is added, which keeps
+//! the per-instruction check to a key comparison.
+//!
+//! `process_component_write` accepts a whole component from an allowlisted
+//! system; `#[system]` functions write their `&mut` components back with it.
+//!
+//! # Example
+//! ```ignore
+//! // Component processor, before writing
+//! require_allowed_system(allowlist, system_signer, program_id)?;
+//!
+//! // System processor, when calling the component
+//! let (_, bump) = derive_system_signer(&crate::ID);
+//! let bump = [bump];
+//! let seeds = [Seed::from(SYSTEM_SIGNER_SEED), Seed::from(&bump)];
+//! invoke_signed(&instruction, &accounts, &[Signer::from(&seeds)])?;
+//! ```
+
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use crate::{
+    check_component_account, derive_pda, program_ids, rent, AccountContext, EntityComponent,
+    GoltError,
+};
+
+/// PDA seed for a component program's allowlist
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+
+/// PDA seed a system signs with when calling components
+pub const SYSTEM_SIGNER_SEED: &[u8] = b"system_signer";
+
+/// Allowlist account discriminator
+pub const ALLOWLIST_DISCRIMINATOR: [u8; 8] = *b"allowlst";
+
+/// Instruction tag creating a component program's allowlist
+pub const INIT_ALLOWLIST_DISCRIMINATOR: u8 = 249;
+
+/// Instruction tag adding a system to the allowlist
+pub const ADD_SYSTEM_DISCRIMINATOR: u8 = 250;
+
+/// Instruction tag removing a system from the allowlist
+pub const REMOVE_SYSTEM_DISCRIMINATOR: u8 = 251;
+
+/// Instruction tag of an allowlisted system writing a whole component back
+pub const WRITE_COMPONENT_DISCRIMINATOR: u8 = 248;
+
+/// Maximum number of systems in one allowlist
+pub const MAX_ALLOWED_SYSTEMS: usize = 16;
+
+/// Size of the allowlist account in bytes:
+/// - 8 bytes discriminator
+/// - 32 bytes admin pubkey
+/// - 1 byte bump
+/// - 1 byte system count
+/// - 64 bytes per system (program ID + signer PDA)
+pub const ALLOWLIST_SIZE: usize = 8 + 32 + 1 + 1 + MAX_ALLOWED_SYSTEMS * 64;
+
+/// A system allowed to write a component
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AllowedSystem {
+    /// System program ID
+    pub program_id: Pubkey,
+    /// The system's `["system_signer"]` PDA
+    pub signer: Pubkey,
+}
+
+/// Allowlist of systems for a component program
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Allowlist {
+    /// Authority allowed to add and remove systems
+    pub admin: Pubkey,
+    /// PDA bump seed
+    pub bump: u8,
+    len: u8,
+    systems: [AllowedSystem; MAX_ALLOWED_SYSTEMS],
+}
+
+impl Allowlist {
+    /// Create an empty allowlist
+    pub fn new(admin: Pubkey, bump: u8) -> Self {
+        Self {
+            admin,
+            bump,
+            len: 0,
+            systems: [AllowedSystem::default(); MAX_ALLOWED_SYSTEMS],
+        }
+    }
+
+    /// Unpack an allowlist from raw account data
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() < ALLOWLIST_SIZE || data[0..8] != ALLOWLIST_DISCRIMINATOR {
+            return None;
+        }
+
+        let admin = Pubkey::try_from(&data[8..40]).ok()?;
+        let bump = data[40];
+        let len = data[41];
+        if len as usize > MAX_ALLOWED_SYSTEMS {
+            return None;
+        }
+
+        let mut systems = [AllowedSystem::default(); MAX_ALLOWED_SYSTEMS];
+        for (i, system) in systems.iter_mut().take(len as usize).enumerate() {
+            let start = 42 + i * 64;
+            system.program_id = Pubkey::try_from(&data[start..start + 32]).ok()?;
+            system.signer = Pubkey::try_from(&data[start + 32..start + 64]).ok()?;
+        }
+
+        Some(Self {
+            admin,
+            bump,
+            len,
+            systems,
+        })
+    }
+
+    /// Pack an allowlist into raw account data
+    pub fn pack(&self, data: &mut [u8]) {
+        data[0..8].copy_from_slice(&ALLOWLIST_DISCRIMINATOR);
+        data[8..40].copy_from_slice(self.admin.as_ref());
+        data[40] = self.bump;
+        data[41] = self.len;
+        for (i, system) in self.systems.iter().enumerate() {
+            let start = 42 + i * 64;
+            data[start..start + 32].copy_from_slice(system.program_id.as_ref());
+            data[start + 32..start + 64].copy_from_slice(system.signer.as_ref());
+        }
+    }
+
+    /// The allowed systems
+    pub fn systems(&self) -> &[AllowedSystem] {
+        &self.systems[..self.len as usize]
+    }
+
+    /// Whether `signer` is the signer PDA of an allowed system
+    pub fn is_allowed_signer(&self, signer: &Pubkey) -> bool {
+        self.systems().iter().any(|s| &s.signer == signer)
+    }
+
+    /// Add a system; adding one that is already listed does nothing
+    pub fn add(&mut self, program_id: &Pubkey) -> Result<(), GoltError> {
+        if self.systems().iter().any(|s| &s.program_id == program_id) {
+            return Ok(());
+        }
+        if self.len as usize >= MAX_ALLOWED_SYSTEMS {
+            return Err(GoltError::AllowlistFull);
+        }
+        let (signer, _) = derive_system_signer(program_id);
+        self.systems[self.len as usize] = AllowedSystem {
+            program_id: *program_id,
+            signer,
+        };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove a system
+    pub fn remove(&mut self, program_id: &Pubkey) -> Result<(), GoltError> {
+        let index = self
+            .systems()
+            .iter()
+            .position(|s| &s.program_id == program_id)
+            .ok_or(GoltError::SystemNotAllowed)?;
+        let len = self.len as usize;
+        self.systems.copy_within(index + 1..len, index);
+        self.systems[len - 1] = AllowedSystem::default();
+        self.len -= 1;
+        Ok(())
+    }
+}
+
+/// Derive the allowlist PDA of a component program
+pub fn derive_allowlist_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    derive_pda(&[ALLOWLIST_SEED], program_id)
+}
+
+/// Derive the PDA a system signs with
+pub fn derive_system_signer(system_program_id: &Pubkey) -> (Pubkey, u8) {
+    derive_pda(&[SYSTEM_SIGNER_SEED], system_program_id)
+}
+
+/// Check that `authority` signed and is the upgrade authority of `program_id`
+///
+/// `program_data` must be the program's ProgramData account, the
+/// `[program_id]` PDA of the upgradeable loader. Programs that are immutable
+/// or not deployed with the upgradeable loader have no upgrade authority and
+/// always fail with `InvalidAuthority`.
+pub fn require_upgrade_authority(
+    program_data: &AccountInfo,
+    authority: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if !authority.is_signer() {
+        return Err(GoltError::AccountNotSigner.into());
+    }
+    if unsafe { program_data.owner() } != &program_ids::BPF_LOADER_UPGRADEABLE {
+        return Err(GoltError::InvalidProgramId.into());
+    }
+    let (expected, _) = derive_pda(&[program_id.as_ref()], &program_ids::BPF_LOADER_UPGRADEABLE);
+    if program_data.key() != &expected {
+        return Err(GoltError::InvalidPda.into());
+    }
+
+    // UpgradeableLoaderState::ProgramData: u32 tag (3), u64 slot,
+    // Option<Pubkey> upgrade authority
+    let data = program_data.try_borrow_data()?;
+    if data.len() < 45 || data[0..4] != 3u32.to_le_bytes() {
+        return Err(GoltError::InvalidAccountData.into());
+    }
+    if data[12] != 1 || &data[13..45] != authority.key().as_ref() {
+        return Err(GoltError::InvalidAuthority.into());
+    }
+    Ok(())
+}
+
+/// Create an empty allowlist for `program_id`, administered by `admin`
+///
+/// `admin` must sign and be the program's upgrade authority (see
+/// `require_upgrade_authority`), so nobody else can claim the allowlist
+/// before it is created.
+pub fn init_allowlist(
+    payer: &AccountInfo,
+    allowlist_account: &AccountInfo,
+    admin: &AccountInfo,
+    program_data: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    require_upgrade_authority(program_data, admin, program_id)?;
+
+    let (expected_pda, bump) = derive_allowlist_pda(program_id);
+    if allowlist_account.key() != &expected_pda {
+        return Err(GoltError::InvalidPda.into());
+    }
+    // The system program refuses to create over existing data; this gives
+    // the clearer error for an allowlist that is already there
+    if allowlist_account.try_borrow_data()?.starts_with(&ALLOWLIST_DISCRIMINATOR) {
+        return Err(GoltError::AlreadyInitialized.into());
+    }
+
+    let rent = rent()?;
+    let lamports = rent.minimum_balance(ALLOWLIST_SIZE);
+
+    let bump_bytes = [bump];
+    let seeds: [Seed; 2] = [Seed::from(ALLOWLIST_SEED), Seed::from(&bump_bytes[..])];
+    let signer = Signer::from(&seeds[..]);
+
+    CreateAccount {
+        from: payer,
+        to: allowlist_account,
+        lamports,
+        space: ALLOWLIST_SIZE as u64,
+        owner: program_id,
+    }
+    .invoke_signed(&[signer])?;
+
+    let mut data = allowlist_account.try_borrow_mut_data()?;
+    Allowlist::new(*admin.key(), bump).pack(&mut data);
+
+    Ok(())
+}
+
+/// Load an allowlist, checking its owner and address
+pub fn load_allowlist(
+    allowlist_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<Allowlist, ProgramError> {
+    if unsafe { allowlist_account.owner() } != program_id {
+        return Err(GoltError::InvalidProgramId.into());
+    }
+    let data = allowlist_account.try_borrow_data()?;
+    let allowlist = Allowlist::unpack(&data).ok_or(GoltError::InvalidDiscriminator)?;
+    crate::pda::verify_pda_with_bump(
+        allowlist_account.key(),
+        &[ALLOWLIST_SEED],
+        allowlist.bump,
+        program_id,
+    )?;
+    Ok(allowlist)
+}
+
+/// Add a system to the allowlist; `admin` must sign
+pub fn add_allowed_system(
+    allowlist_account: &AccountInfo,
+    admin: &AccountInfo,
+    system_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    update_allowlist(allowlist_account, admin, program_id, |allowlist| {
+        allowlist.add(system_program_id)
+    })
+}
+
+/// Remove a system from the allowlist; `admin` must sign
+pub fn remove_allowed_system(
+    allowlist_account: &AccountInfo,
+    admin: &AccountInfo,
+    system_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    update_allowlist(allowlist_account, admin, program_id, |allowlist| {
+        allowlist.remove(system_program_id)
+    })
+}
+
+/// Require the instruction to be signed by an allowlisted system
+///
+/// `system_signer` must be a signer and match the `["system_signer"]` PDA of
+/// a system in the allowlist.
+pub fn require_allowed_system(
+    allowlist_account: &AccountInfo,
+    system_signer: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if !system_signer.is_signer() {
+        return Err(GoltError::AccountNotSigner.into());
+    }
+    let allowlist = load_allowlist(allowlist_account, program_id)?;
+    if !allowlist.is_allowed_signer(system_signer.key()) {
+        return Err(GoltError::SystemNotAllowed.into());
+    }
+    Ok(())
+}
+
+/// Handle the allowlist admin instructions of a component program
+///
+/// Returns `None` if `instruction_data` is not one of them. Accounts:
+/// - `INIT_ALLOWLIST_DISCRIMINATOR`: `[payer (signer, writable), admin (signer,
+///   upgrade authority), allowlist (writable), program data, system program]`
+/// - `ADD_SYSTEM_DISCRIMINATOR` / `REMOVE_SYSTEM_DISCRIMINATOR` + 32-byte system
+///   program ID: `[admin (signer), allowlist (writable)]`
+pub fn process_allowlist_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Option<Result<(), ProgramError>> {
+    let (&tag, rest) = instruction_data.split_first()?;
+    match tag {
+        INIT_ALLOWLIST_DISCRIMINATOR => Some(process_init_allowlist(program_id, accounts)),
+        ADD_SYSTEM_DISCRIMINATOR | REMOVE_SYSTEM_DISCRIMINATOR => {
+            Some(process_update_allowlist(program_id, accounts, tag, rest))
+        }
+        _ => None,
+    }
+}
+
+/// Handle an allowlisted system writing a whole `C` back to its account
+///
+/// Returns `None` if `instruction_data` is not `WRITE_COMPONENT_DISCRIMINATOR`.
+/// Accounts: `[component (writable), allowlist, system signer PDA (signer)]`.
+/// The data after the tag is the packed component; it must unpack as a `C`
+/// and keep the stored `#[entity]` key and `#[pda_bump]` byte.
+pub fn process_component_write<C: EntityComponent>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Option<Result<(), ProgramError>> {
+    let (&tag, rest) = instruction_data.split_first()?;
+    if tag != WRITE_COMPONENT_DISCRIMINATOR {
+        return None;
+    }
+    Some(process_write::<C>(program_id, accounts, rest))
+}
+
+fn process_write<C: EntityComponent>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> Result<(), ProgramError> {
+    let mut ctx = AccountContext::new(accounts);
+    let account = ctx.next_writable()?;
+    let allowlist = ctx.next()?;
+    let system_signer = ctx.next()?;
+    check_component_account::<C>(account, program_id)?;
+    require_allowed_system(allowlist, system_signer, program_id)?;
+
+    let component = C::unpack(data).ok_or(ProgramError::InvalidInstructionData)?;
+    component.validate()?;
+
+    let mut stored = account.try_borrow_mut_data()?;
+    // The PDA is derived from the entity, so a write can't move the
+    // component to another entity
+    if C::entity_key(data) != C::entity_key(&stored) {
+        return Err(GoltError::EntityMismatch.into());
+    }
+    // The bump ties the account to its PDA, so a write can't change it
+    if let Some(offset) = C::BUMP_OFFSET {
+        if data.get(offset) != stored.get(offset) {
+            return Err(GoltError::InvalidPda.into());
+        }
+    }
+    component.pack(&mut stored);
+    Ok(())
+}
+
+fn process_init_allowlist(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<(), ProgramError> {
+    let mut ctx = AccountContext::new(accounts);
+    let payer = ctx.next_signer_writable()?;
+    let admin = ctx.next_signer()?;
+    let allowlist = ctx.next_writable()?;
+    let program_data = ctx.next()?;
+    ctx.next_program(&program_ids::SYSTEM)?;
+    init_allowlist(payer, allowlist, admin, program_data, program_id)
+}
+
+fn process_update_allowlist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tag: u8,
+    rest: &[u8],
+) -> Result<(), ProgramError> {
+    let system: Pubkey = rest
+        .get(..32)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    let mut ctx = AccountContext::new(accounts);
+    let admin = ctx.next()?;
+    let allowlist = ctx.next()?;
+    if tag == ADD_SYSTEM_DISCRIMINATOR {
+        add_allowed_system(allowlist, admin, &system, program_id)
+    } else {
+        remove_allowed_system(allowlist, admin, &system, program_id)
+    }
+}
+
+fn update_allowlist(
+    allowlist_account: &AccountInfo,
+    admin: &AccountInfo,
+    program_id: &Pubkey,
+    update: impl FnOnce(&mut Allowlist) -> Result<(), GoltError>,
+) -> Result<(), ProgramError> {
+    if !admin.is_signer() {
+        return Err(GoltError::AccountNotSigner.into());
+    }
+    if !allowlist_account.is_writable() {
+        return Err(GoltError::AccountNotWritable.into());
+    }
+    let mut allowlist = load_allowlist(allowlist_account, program_id)?;
+    if &allowlist.admin != admin.key() {
+        return Err(GoltError::InvalidAuthority.into());
+    }
+
+    update(&mut allowlist)?;
+
+    let mut data = allowlist_account.try_borrow_mut_data()?;
+    allowlist.pack(&mut data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::Component;
+
+    use super::*;
+    use crate::test_utils::TestAccount;
+    use crate::{Component, CLOSED_ACCOUNT_DISCRIMINATOR};
+
+    const PROGRAM_ID: Pubkey = [5; 32];
+    const ADMIN: Pubkey = [2; 32];
+
+    fn system(n: u8) -> Pubkey {
+        [0x40 + n; 32]
+    }
+
+    #[test]
+    fn allowlist_pack_unpack() {
+        let mut allowlist = Allowlist::new(ADMIN, 254);
+        allowlist.add(&system(1)).unwrap();
+        allowlist.add(&system(2)).unwrap();
+
+        let mut data = vec![0; ALLOWLIST_SIZE];
+        allowlist.pack(&mut data);
+        assert_eq!(data[0..8], ALLOWLIST_DISCRIMINATOR);
+        assert_eq!(data[8..40], ADMIN);
+        assert_eq!(data[40], 254);
+        assert_eq!(data[41], 2);
+        assert_eq!(data[42..74], system(1));
+        assert_eq!(data[74..106], derive_system_signer(&system(1)).0);
+        assert_eq!(Allowlist::unpack(&data), Some(allowlist));
+
+        assert_eq!(Allowlist::unpack(&data[..ALLOWLIST_SIZE - 1]), None);
+        let mut wrong = data.clone();
+        wrong[0] ^= 1;
+        assert_eq!(Allowlist::unpack(&wrong), None);
+        let mut too_long = data.clone();
+        too_long[41] = MAX_ALLOWED_SYSTEMS as u8 + 1;
+        assert_eq!(Allowlist::unpack(&too_long), None);
+    }
+
+    #[test]
+    fn allowlist_add_remove() {
+        let mut allowlist = Allowlist::new(ADMIN, 254);
+        allowlist.add(&system(1)).unwrap();
+        // Adding a listed system does nothing
+        allowlist.add(&system(1)).unwrap();
+        assert_eq!(allowlist.systems().len(), 1);
+
+        let signer = derive_system_signer(&system(1)).0;
+        assert!(allowlist.is_allowed_signer(&signer));
+        // Systems sign with their signer PDA, not their program ID
+        assert!(!allowlist.is_allowed_signer(&system(1)));
+
+        for n in 2..=MAX_ALLOWED_SYSTEMS as u8 {
+            allowlist.add(&system(n)).unwrap();
+        }
+        assert_eq!(allowlist.add(&system(0)), Err(GoltError::AllowlistFull));
+
+        // Removing keeps the others in order
+        allowlist.remove(&system(1)).unwrap();
+        assert!(!allowlist.is_allowed_signer(&signer));
+        let ids: Vec<Pubkey> = allowlist.systems().iter().map(|s| s.program_id).collect();
+        let expected: Vec<Pubkey> = (2..=MAX_ALLOWED_SYSTEMS as u8).map(system).collect();
+        assert_eq!(ids, expected);
+        assert_eq!(
+            allowlist.remove(&system(1)),
+            Err(GoltError::SystemNotAllowed)
+        );
+
+        // The freed slot is cleared, so packing is unchanged by past entries
+        let mut data = vec![0; ALLOWLIST_SIZE];
+        allowlist.pack(&mut data);
+        assert!(data[ALLOWLIST_SIZE - 64..].iter().all(|&b| b == 0));
+    }
+
+    /// ProgramData account of `PROGRAM_ID` with the given upgrade authority
+    fn program_data(authority: Option<Pubkey>) -> TestAccount {
+        let (address, _) = derive_pda(&[PROGRAM_ID.as_ref()], &program_ids::BPF_LOADER_UPGRADEABLE);
+        let mut data = vec![0; 45];
+        data[0..4].copy_from_slice(&3u32.to_le_bytes());
+        data[4..12].copy_from_slice(&42u64.to_le_bytes());
+        if let Some(authority) = authority {
+            data[12] = 1;
+            data[13..45].copy_from_slice(&authority);
+        }
+        TestAccount::new(address, program_ids::BPF_LOADER_UPGRADEABLE, &data)
+    }
+
+    fn check(
+        program_data: &mut TestAccount,
+        mut authority: TestAccount,
+    ) -> Result<(), ProgramError> {
+        require_upgrade_authority(&program_data.info(), &authority.info(), &PROGRAM_ID)
+    }
+
+    #[test]
+    fn upgrade_authority() {
+        let admin = || TestAccount::new(ADMIN, [0; 32], &[]).signer();
+
+        assert_eq!(check(&mut program_data(Some(ADMIN)), admin()), Ok(()));
+        assert_eq!(
+            check(
+                &mut program_data(Some(ADMIN)),
+                TestAccount::new(ADMIN, [0; 32], &[])
+            ),
+            Err(GoltError::AccountNotSigner.into())
+        );
+        assert_eq!(
+            check(&mut program_data(Some([7; 32])), admin()),
+            Err(GoltError::InvalidAuthority.into())
+        );
+        // Immutable programs have no upgrade authority
+        assert_eq!(
+            check(&mut program_data(None), admin()),
+            Err(GoltError::InvalidAuthority.into())
+        );
+    }
+
+    #[test]
+    fn upgrade_authority_program_data_parsing() {
+        let admin = || TestAccount::new(ADMIN, [0; 32], &[]).signer();
+        let (address, _) = derive_pda(&[PROGRAM_ID.as_ref()], &program_ids::BPF_LOADER_UPGRADEABLE);
+        let mut valid = vec![0; 45];
+        valid[0..4].copy_from_slice(&3u32.to_le_bytes());
+        valid[12] = 1;
+        valid[13..45].copy_from_slice(&ADMIN);
+
+        // Not owned by the upgradeable loader
+        let mut account = TestAccount::new(address, [0; 32], &valid);
+        assert_eq!(
+            check(&mut account, admin()),
+            Err(GoltError::InvalidProgramId.into())
+        );
+
+        // Another program's ProgramData
+        let (other, _) = derive_pda(&[[6; 32].as_ref()], &program_ids::BPF_LOADER_UPGRADEABLE);
+        let mut account = TestAccount::new(other, program_ids::BPF_LOADER_UPGRADEABLE, &valid);
+        assert_eq!(
+            check(&mut account, admin()),
+            Err(GoltError::InvalidPda.into())
+        );
+
+        // Program (tag 2) rather than ProgramData (tag 3)
+        let mut program = valid.clone();
+        program[0..4].copy_from_slice(&2u32.to_le_bytes());
+        let mut account = TestAccount::new(address, program_ids::BPF_LOADER_UPGRADEABLE, &program);
+        assert_eq!(
+            check(&mut account, admin()),
+            Err(GoltError::InvalidAccountData.into())
+        );
+
+        // Too short to hold the authority
+        let mut account =
+            TestAccount::new(address, program_ids::BPF_LOADER_UPGRADEABLE, &valid[..44]);
+        assert_eq!(
+            check(&mut account, admin()),
+            Err(GoltError::InvalidAccountData.into())
+        );
+
+        // Trailing program bytes are ignored
+        let mut deployed = valid.clone();
+        deployed.extend_from_slice(&[0xaa; 64]);
+        let mut account = TestAccount::new(address, program_ids::BPF_LOADER_UPGRADEABLE, &deployed);
+        assert_eq!(check(&mut account, admin()), Ok(()));
+    }
+
+    /// The program's allowlist, administered by `ADMIN` and listing `systems`
+    fn allowlist_account(owner: Pubkey, systems: &[Pubkey]) -> TestAccount {
+        let (address, bump) = derive_allowlist_pda(&PROGRAM_ID);
+        let mut allowlist = Allowlist::new(ADMIN, bump);
+        for system in systems {
+            allowlist.add(system).unwrap();
+        }
+        let mut data = vec![0; ALLOWLIST_SIZE];
+        allowlist.pack(&mut data);
+        TestAccount::new(address, owner, &data)
+    }
+
+    fn stored_allowlist(account: &mut TestAccount) -> Option<Allowlist> {
+        Allowlist::unpack(&account.info().try_borrow_data().unwrap())
+    }
+
+    fn run(accounts: &mut [TestAccount], data: &[u8]) -> Option<Result<(), ProgramError>> {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        process_allowlist_instruction(&PROGRAM_ID, &infos, data)
+    }
+
+    #[test]
+    fn init_allowlist_requires_upgrade_authority() {
+        let (address, bump) = derive_allowlist_pda(&PROGRAM_ID);
+        let init = |admin: Pubkey, allowlist: TestAccount| {
+            let mut accounts = [
+                TestAccount::new([1; 32], [0; 32], &[]).signer(),
+                TestAccount::with_flags(admin, [0; 32], 1, true, false, &[]),
+                allowlist,
+                program_data(Some(ADMIN)),
+                TestAccount::with_flags(program_ids::SYSTEM, [0; 32], 1, false, false, &[]),
+            ];
+            let result = run(&mut accounts, &[INIT_ALLOWLIST_DISCRIMINATOR]).unwrap();
+            (result, stored_allowlist(&mut accounts[2]))
+        };
+        // The system program is a no-op off-chain, so the account is pre-sized
+        let created = || TestAccount::new(address, PROGRAM_ID, &[0; ALLOWLIST_SIZE]);
+
+        assert_eq!(
+            init(ADMIN, created()),
+            (Ok(()), Some(Allowlist::new(ADMIN, bump)))
+        );
+        assert_eq!(
+            init([7; 32], created()),
+            (Err(GoltError::InvalidAuthority.into()), None)
+        );
+        let elsewhere = TestAccount::new([8; 32], PROGRAM_ID, &[0; ALLOWLIST_SIZE]);
+        assert_eq!(init(ADMIN, elsewhere).0, Err(GoltError::InvalidPda.into()));
+        assert_eq!(
+            init(ADMIN, allowlist_account(PROGRAM_ID, &[system(1)])).0,
+            Err(GoltError::AlreadyInitialized.into())
+        );
+    }
+
+    #[test]
+    fn admin_adds_and_removes_systems() {
+        let update = |tag: u8, admin: TestAccount, allowlist: TestAccount| {
+            let mut data = vec![tag];
+            data.extend(system(1));
+            let mut accounts = [admin, allowlist];
+            let result = run(&mut accounts, &data).unwrap();
+            (result, stored_allowlist(&mut accounts[1]).unwrap())
+        };
+        let admin = || TestAccount::new(ADMIN, [0; 32], &[]).signer();
+        let signer = derive_system_signer(&system(1)).0;
+
+        let (result, allowlist) = update(
+            ADD_SYSTEM_DISCRIMINATOR,
+            admin(),
+            allowlist_account(PROGRAM_ID, &[]),
+        );
+        assert_eq!(result, Ok(()));
+        assert!(allowlist.is_allowed_signer(&signer));
+
+        let (result, allowlist) = update(
+            REMOVE_SYSTEM_DISCRIMINATOR,
+            admin(),
+            allowlist_account(PROGRAM_ID, &[system(1)]),
+        );
+        assert_eq!(result, Ok(()));
+        assert!(allowlist.systems().is_empty());
+
+        // Only the admin, and only with its signature
+        let (result, allowlist) = update(
+            ADD_SYSTEM_DISCRIMINATOR,
+            TestAccount::new([7; 32], [0; 32], &[]).signer(),
+            allowlist_account(PROGRAM_ID, &[]),
+        );
+        assert_eq!(result, Err(GoltError::InvalidAuthority.into()));
+        assert!(allowlist.systems().is_empty());
+        let (result, _) = update(
+            ADD_SYSTEM_DISCRIMINATOR,
+            TestAccount::new(ADMIN, [0; 32], &[]),
+            allowlist_account(PROGRAM_ID, &[]),
+        );
+        assert_eq!(result, Err(GoltError::AccountNotSigner.into()));
+    }
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "health")]
+    struct Health {
+        #[entity]
+        entity: [u8; 32],
+        current: u32,
+        #[pda_bump]
+        bump: u8,
+    }
+
+    fn health(current: u32) -> Health {
+        Health {
+            entity: [3; 32],
+            current,
+            bump: 253,
+        }
+    }
+
+    fn packed(health: &Health) -> Vec<u8> {
+        let mut data = vec![0; Health::SIZE];
+        health.pack(&mut data);
+        data
+    }
+
+    /// The signer PDA of `system(n)`, signing unless `signed` is false
+    fn system_signer(n: u8, signed: bool) -> TestAccount {
+        let (key, _) = derive_system_signer(&system(n));
+        TestAccount::with_flags(key, [0; 32], 1, signed, false, &[])
+    }
+
+    /// Write `new` over a component account holding `stored`, returning the
+    /// result and the account data afterwards
+    fn write(
+        owner: Pubkey,
+        stored: &[u8],
+        allowlist: TestAccount,
+        signer: TestAccount,
+        new: &[u8],
+    ) -> (Result<(), ProgramError>, Vec<u8>) {
+        let mut data = vec![WRITE_COMPONENT_DISCRIMINATOR];
+        data.extend_from_slice(new);
+        let mut accounts = [TestAccount::new([4; 32], owner, stored), allowlist, signer];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        let result = process_component_write::<Health>(&PROGRAM_ID, &infos, &data).unwrap();
+        let after = infos[0].try_borrow_data().unwrap().to_vec();
+        (result, after)
+    }
+
+    #[test]
+    fn system_write_updates_the_component() {
+        let (result, after) = write(
+            PROGRAM_ID,
+            &packed(&health(10)),
+            allowlist_account(PROGRAM_ID, &[system(1)]),
+            system_signer(1, true),
+            &packed(&health(4)),
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(after, packed(&health(4)));
+
+        // Other instructions are left to the caller
+        assert_eq!(
+            process_component_write::<Health>(&PROGRAM_ID, &[], &[0]),
+            None
+        );
+    }
+
+    #[test]
+    fn system_write_checks() {
+        let stored = packed(&health(10));
+        let new = packed(&health(4));
+        let allowlist = || allowlist_account(PROGRAM_ID, &[system(1)]);
+        let rejected = |owner, stored: &[u8], allowlist, signer, new: &[u8]| {
+            let (result, after) = write(owner, stored, allowlist, signer, new);
+            assert_eq!(after, stored, "the component was written");
+            result.unwrap_err()
+        };
+
+        // The system must sign, be allowlisted, and the allowlist must be the program's
+        assert_eq!(
+            rejected(
+                PROGRAM_ID,
+                &stored,
+                allowlist(),
+                system_signer(1, false),
+                &new
+            ),
+            GoltError::AccountNotSigner.into()
+        );
+        assert_eq!(
+            rejected(
+                PROGRAM_ID,
+                &stored,
+                allowlist(),
+                system_signer(2, true),
+                &new
+            ),
+            GoltError::SystemNotAllowed.into()
+        );
+        let foreign_allowlist = allowlist_account([9; 32], &[system(1)]);
+        assert_eq!(
+            rejected(
+                PROGRAM_ID,
+                &stored,
+                foreign_allowlist,
+                system_signer(1, true),
+                &new
+            ),
+            GoltError::InvalidProgramId.into()
+        );
+
+        // The write can't move the component to another entity or PDA
+        let moved = packed(&Health {
+            entity: [6; 32],
+            ..health(4)
+        });
+        assert_eq!(
+            rejected(
+                PROGRAM_ID,
+                &stored,
+                allowlist(),
+                system_signer(1, true),
+                &moved
+            ),
+            GoltError::EntityMismatch.into()
+        );
+        let rebumped = packed(&Health {
+            bump: 1,
+            ..health(4)
+        });
+        assert_eq!(
+            rejected(
+                PROGRAM_ID,
+                &stored,
+                allowlist(),
+                system_signer(1, true),
+                &rebumped
+            ),
+            GoltError::InvalidPda.into()
+        );
+
+        // Only live components of this program
+        let mut closed = vec![0; Health::SIZE];
+        closed[..8].copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);
+        assert_eq!(
+            rejected(
+                PROGRAM_ID,
+                &closed,
+                allowlist(),
+                system_signer(1, true),
+                &new
+            ),
+            GoltError::InvalidDiscriminator.into()
+        );
+        assert_eq!(
+            rejected([9; 32], &stored, allowlist(), system_signer(1, true), &new),
+            GoltError::InvalidProgramId.into()
+        );
+
+        assert_eq!(
+            rejected(
+                PROGRAM_ID,
+                &stored,
+                allowlist(),
+                system_signer(1, true),
+                &new[..8]
+            ),
+            ProgramError::InvalidInstructionData
+        );
+    }
+}
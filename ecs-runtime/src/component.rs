@@ -1,6 +1,8 @@
 //! Component trait and utilities
 
-use pinocchio::pubkey::Pubkey;
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::GoltError;
 
 /// Trait implemented by all ECS components
 pub trait Component: Sized {
@@ -13,17 +15,61 @@ pub trait Component: Sized {
     /// Total size of the component in bytes (including discriminator)
     const SIZE: usize;
 
+    /// Layout version stored in the byte after the discriminator (0 = unversioned)
+    const VERSION: u8 = 0;
+
+    /// Size of accounts written before the component was versioned
+    ///
+    /// Those accounts have no version byte, so an account of exactly this
+    /// size is read as version 0 whatever its ninth byte holds.
+    const LEGACY_SIZE: Option<usize> = None;
+
+    /// Offset of the stored `#[pda_bump]` byte in the account data, if any
+    const BUMP_OFFSET: Option<usize> = None;
+
+    /// Offset of the stored `#[entity]` key in the account data, if any
+    ///
+    /// `EntityComponent::ENTITY_OFFSET` for entity components, so that code
+    /// generic over any component (like `Query`) can compare entities.
+    const ENTITY_KEY_OFFSET: Option<usize> = None;
+
+    /// Offset and size of each field in the account data, in declaration order
+    const LAYOUT: &'static [FieldLayout] = &[];
+
     /// Unpack component from raw account data
     fn unpack(data: &[u8]) -> Option<Self>;
 
     /// Pack component into raw account data
     fn pack(&self, data: &mut [u8]);
 
+    /// Check the component fits its layout before it is written
+    ///
+    /// `#[derive(Component)]` checks `#[max_len]` capacities here; `pack`
+    /// itself never writes past a field's region.
+    fn validate(&self) -> Result<(), GoltError> {
+        Ok(())
+    }
+
     /// Derive the PDA for this component
+    ///
+    /// Searches for the bump on-chain; use `verify_pda_with_bump` to check
+    /// an existing account.
     fn derive_pda(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
         let mut all_seeds = vec![Self::SEED];
         all_seeds.extend_from_slice(seeds);
-        pinocchio::pubkey::find_program_address(&all_seeds, program_id)
+        crate::pda::derive_pda(&all_seeds, program_id)
+    }
+
+    /// Verify the PDA for this component using a stored canonical bump
+    fn verify_pda_with_bump(
+        account_key: &Pubkey,
+        seeds: &[&[u8]],
+        bump: u8,
+        program_id: &Pubkey,
+    ) -> Result<(), GoltError> {
+        let mut all_seeds = vec![Self::SEED];
+        all_seeds.extend_from_slice(seeds);
+        crate::pda::verify_pda_with_bump(account_key, &all_seeds, bump, program_id)
     }
 
     /// Verify the discriminator matches
@@ -35,10 +81,98 @@ pub trait Component: Sized {
     }
 }
 
+/// Where a field is stored in a component account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub name: &'static str,
+    /// Byte offset from the start of the account data
+    pub offset: usize,
+    /// Size in bytes, the whole `#[max_len]` region for `Vec` / `String`
+    /// and the shared word for bit fields
+    pub size: usize,
+    /// Bits of the word at `offset` holding a `#[bits(N)]` / `#[flags]` field
+    pub bits: Option<BitRange>,
+}
+
+/// Bits of a little-endian word holding a bit-packed field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitRange {
+    /// Bits below the field
+    pub shift: u8,
+    pub width: u8,
+}
+
+/// Component that stores the key of the entity it belongs to
+///
+/// Implemented by `#[derive(Component)]` for structs with an `#[entity]` field,
+/// which also sets `Component::ENTITY_KEY_OFFSET` to `ENTITY_OFFSET`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no `#[entity]` field",
+    note = "`#[component_instructions]` and `#[system]` need components that store their entity key"
+)]
+pub trait EntityComponent: Component {
+    /// Offset of the 32-byte entity key in the account data
+    const ENTITY_OFFSET: usize;
+
+    /// Read the entity key from raw account data
+    fn entity_key(data: &[u8]) -> Option<&Pubkey> {
+        data.get(Self::ENTITY_OFFSET..Self::ENTITY_OFFSET + 32)?
+            .try_into()
+            .ok()
+    }
+}
+
+/// Component whose program accepts whole-component writes from allowlisted systems
+///
+/// Implemented by `#[component_instructions]` with its `program_id`; the
+/// program's processor handles `WRITE_COMPONENT_DISCRIMINATOR`. `#[system]` functions write their
+/// `&mut` components back through it.
+pub trait OwnedComponent: Component {
+    /// ID of the component program that owns the accounts
+    const OWNER: Pubkey;
+}
+
+/// Verify a component account's address using the bump stored in its data
+///
+/// `seeds` excludes the component's `SEED` prefix and the bump. Components
+/// without a `#[pda_bump]` field fall back to `find_program_address`.
+pub fn verify_component_pda<C: Component>(
+    account: &AccountInfo,
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<u8, ProgramError> {
+    match C::BUMP_OFFSET {
+        Some(offset) => {
+            let bump = *account
+                .try_borrow_data()?
+                .get(offset)
+                .ok_or(GoltError::InvalidAccountData)?;
+            C::verify_pda_with_bump(account.key(), seeds, bump, program_id)?;
+            Ok(bump)
+        }
+        None => {
+            let (expected, bump) = C::derive_pda(seeds, program_id);
+            if account.key() != &expected {
+                return Err(GoltError::InvalidPda.into());
+            }
+            Ok(bump)
+        }
+    }
+}
+
+/// Trait for versioned components that can upgrade older on-chain layouts
+///
+/// Implemented by `#[derive(Component)]` when `migrate = "path"` is given.
+pub trait Migrate: Component {
+    /// Decode account data written with layout `version` into the current layout
+    fn migrate(version: u8, data: &[u8]) -> Option<Self>;
+}
+
 /// Trait for components that can be delegated to Ephemeral Rollups
 ///
 /// Implement this trait for components that need to be delegated to MagicBlock's
-/// Ephemeral Rollups for fast, gasless gameplay.
+/// Ephemeral Rollups for fast, gasless gameplay. `#[component(delegatable)]`
+/// implements it from the `#[entity]` and `#[pda_bump]` fields.
 ///
 /// # Example
 /// ```ignore
@@ -75,3 +209,311 @@ macro_rules! component_size {
         std::mem::size_of::<$t>() + component_size!($($rest),*)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::Component;
+
+    use super::*;
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "inventory")]
+    struct Inventory {
+        #[entity]
+        entity: [u8; 32],
+        #[max_len(3)]
+        items: Vec<u32>,
+        #[max_len(5)]
+        name: String,
+        gold: u64,
+    }
+
+    fn inventory(items: Vec<u32>, name: &str) -> Inventory {
+        Inventory {
+            entity: [2; 32],
+            items,
+            name: name.to_string(),
+            gold: 9,
+        }
+    }
+
+    fn packed(inventory: &Inventory) -> Vec<u8> {
+        let mut data = vec![0; Inventory::SIZE];
+        inventory.pack(&mut data);
+        data
+    }
+
+    #[test]
+    fn max_len_regions() {
+        assert_eq!(Inventory::ITEMS_MAX_LEN, 3);
+        assert_eq!(Inventory::NAME_MAX_LEN, 5);
+        // u32 length, then room for every element
+        assert_eq!(Inventory::SIZE, 8 + 32 + (4 + 3 * 4) + (4 + 5) + 8);
+
+        let full = inventory(vec![1, 2, 3], "sword");
+        let data = packed(&full);
+        assert_eq!(Inventory::unpack(&data), Some(full));
+        // Fields after a region stay at a fixed offset
+        assert_eq!(data[65..73], 9u64.to_le_bytes());
+
+        // Shrinking clears the rest of the region
+        let mut data = data;
+        inventory(vec![1], "ax").pack(&mut data);
+        assert_eq!(data[40..44], 1u32.to_le_bytes());
+        assert_eq!(data[48..56], [0; 8]);
+        assert_eq!(data[60..65], *b"ax\0\0\0");
+        assert_eq!(Inventory::unpack(&data), Some(inventory(vec![1], "ax")));
+    }
+
+    #[test]
+    fn max_len_rejects_overflow() {
+        let mut inv = inventory(Vec::new(), "");
+        for item in 1..=3 {
+            assert_eq!(inv.push_items(item), Ok(()));
+        }
+        assert_eq!(inv.push_items(4), Err(GoltError::CapacityExceeded));
+        assert_eq!(inv.items, [1, 2, 3]);
+
+        assert_eq!(inv.set_name("sword"), Ok(()));
+        assert_eq!(inv.set_name("shield"), Err(GoltError::CapacityExceeded));
+        assert_eq!(inv.name, "sword");
+        assert_eq!(inv.validate(), Ok(()));
+
+        // Direct writes past capacity are caught before the component is written
+        inv.items.push(4);
+        assert_eq!(inv.validate(), Err(GoltError::CapacityExceeded));
+        let long_name = inventory(Vec::new(), "shield");
+        assert_eq!(long_name.validate(), Err(GoltError::CapacityExceeded));
+    }
+
+    #[test]
+    fn max_len_unpack_rejects_bad_regions() {
+        let data = packed(&inventory(vec![1], "ax"));
+
+        // Stored length past the capacity
+        let mut long = data.clone();
+        long[40..44].copy_from_slice(&4u32.to_le_bytes());
+        assert_eq!(Inventory::unpack(&long), None);
+        let mut long = data.clone();
+        long[56..60].copy_from_slice(&6u32.to_le_bytes());
+        assert_eq!(Inventory::unpack(&long), None);
+
+        // Names must be UTF-8
+        let mut invalid = data;
+        invalid[60] = 0xff;
+        assert_eq!(Inventory::unpack(&invalid), None);
+    }
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "stats", reserved = 32)]
+    struct Stats {
+        #[entity]
+        entity: [u8; 32],
+        #[offset(48)]
+        level: u16,
+        #[padding(2)]
+        xp: u32,
+        #[pda_bump]
+        bump: u8,
+    }
+
+    #[test]
+    fn explicit_layout() {
+        let field = |name, offset, size| FieldLayout {
+            name,
+            offset,
+            size,
+            bits: None,
+        };
+        assert_eq!(
+            Stats::LAYOUT,
+            [
+                field("entity", 8, 32),
+                field("level", 48, 2),
+                field("xp", 52, 4),
+                field("bump", 56, 1),
+            ]
+        );
+        assert_eq!(Stats::SIZE, 57 + 32);
+        assert_eq!(Stats::BUMP_OFFSET, Some(56));
+
+        let stats = Stats {
+            entity: [2; 32],
+            level: 7,
+            xp: 300,
+            bump: 254,
+        };
+        // Gaps and reserved bytes are zeroed, whatever was there before
+        let mut data = vec![0xaa; Stats::SIZE];
+        stats.pack(&mut data);
+        assert_eq!(data[0..8], Stats::DISCRIMINATOR);
+        assert_eq!(data[40..48], [0; 8]);
+        assert_eq!(data[48..50], 7u16.to_le_bytes());
+        assert_eq!(data[50..52], [0; 2]);
+        assert_eq!(data[52..56], 300u32.to_le_bytes());
+        assert_eq!(data[56], 254);
+        assert_eq!(data[57..], [0; 32]);
+        assert_eq!(Stats::unpack(&data), Some(stats));
+    }
+
+    #[derive(Component, Clone, Debug, Default, PartialEq)]
+    #[component(seed = "status")]
+    struct Status {
+        #[entity]
+        entity: [u8; 32],
+        // A u8 filled exactly
+        #[flags]
+        alive: bool,
+        #[bits(7)]
+        level: u8,
+        hp: u16,
+        // A u16 with a field across the byte boundary
+        #[bits(3)]
+        team: u8,
+        #[bits(9)]
+        rank: u16,
+        gold: u8,
+        #[bits(31)]
+        xp: u32,
+        #[flags]
+        elite: bool,
+        mana: u8,
+        // A full u64, then groups split past 64 bits
+        #[bits(60)]
+        stamp: u64,
+        #[bits(4)]
+        tier: u8,
+        #[bits(64)]
+        nonce: u64,
+        #[flags]
+        last: bool,
+    }
+
+    fn status_max() -> Status {
+        Status {
+            entity: [2; 32],
+            alive: true,
+            level: 127,
+            hp: 0x1234,
+            team: 7,
+            rank: 511,
+            gold: 0x56,
+            xp: u32::MAX >> 1,
+            elite: true,
+            mana: 0x78,
+            stamp: (1 << 60) - 1,
+            tier: 15,
+            nonce: u64::MAX,
+            last: true,
+        }
+    }
+
+    #[test]
+    fn bit_field_words() {
+        let bits = |name| {
+            let field = Status::LAYOUT.iter().find(|f| f.name == name).unwrap();
+            let bits = field.bits.unwrap();
+            (field.offset, field.size, bits.shift, bits.width)
+        };
+        assert_eq!(bits("alive"), (40, 1, 0, 1));
+        assert_eq!(bits("level"), (40, 1, 1, 7));
+        assert_eq!(bits("team"), (43, 2, 0, 3));
+        assert_eq!(bits("rank"), (43, 2, 3, 9));
+        assert_eq!(bits("xp"), (46, 4, 0, 31));
+        assert_eq!(bits("elite"), (46, 4, 31, 1));
+        assert_eq!(bits("stamp"), (51, 8, 0, 60));
+        assert_eq!(bits("tier"), (51, 8, 60, 4));
+        assert_eq!(bits("nonce"), (59, 8, 0, 64));
+        assert_eq!(bits("last"), (67, 1, 0, 1));
+        assert_eq!(Status::SIZE, 68);
+
+        // Every bit set fills each word and leaves the fields between them alone
+        let mut data = vec![0; Status::SIZE];
+        status_max().pack(&mut data);
+        assert_eq!(data[40], 0xff);
+        assert_eq!(data[41..43], 0x1234u16.to_le_bytes());
+        assert_eq!(data[43..45], [0xff, 0x0f]);
+        assert_eq!(data[45], 0x56);
+        assert_eq!(data[46..50], [0xff; 4]);
+        assert_eq!(data[50], 0x78);
+        assert_eq!(data[51..67], [0xff; 16]);
+        assert_eq!(data[67], 1);
+        assert_eq!(Status::unpack(&data), Some(status_max()));
+
+        // Packing clears the words first
+        let top = Status {
+            rank: 1 << 8,
+            tier: 1 << 3,
+            ..Default::default()
+        };
+        top.pack(&mut data);
+        assert_eq!(data[40], 0);
+        assert_eq!(data[43..45], [0, 0x08]);
+        assert_eq!(data[51..59], (1u64 << 63).to_le_bytes());
+        assert_eq!(data[59..68], [0; 9]);
+        assert_eq!(Status::unpack(&data), Some(top));
+    }
+
+    #[test]
+    fn bit_field_accessors() {
+        let mut data = vec![0; Status::SIZE];
+        status_max().pack(&mut data);
+
+        // Writes keep the other fields of the word
+        Status::write_rank(&mut data, 0);
+        assert_eq!(data[43..45], [0x07, 0]);
+        assert_eq!(Status::read_team(&data), 7);
+        Status::write_elite(&mut data, false);
+        assert_eq!(Status::read_xp(&data), u32::MAX >> 1);
+        assert!(!Status::read_elite(&data));
+        Status::write_stamp(&mut data, 0);
+        assert_eq!(Status::read_tier(&data), 15);
+        Status::write_nonce(&mut data, 5);
+        assert!(Status::read_last(&data));
+
+        // and keep only the field's low bits
+        Status::write_level(&mut data, 0xff);
+        assert_eq!(Status::read_level(&data), 127);
+        assert!(Status::read_alive(&data));
+        Status::write_tier(&mut data, 0x1f);
+        assert_eq!(Status::read_tier(&data), 15);
+        assert_eq!(Status::read_stamp(&data), 0);
+
+        assert_eq!(
+            Status::unpack(&data),
+            Some(Status {
+                rank: 0,
+                elite: false,
+                stamp: 0,
+                nonce: 5,
+                ..status_max()
+            })
+        );
+    }
+
+    #[test]
+    fn bit_fields_reject_wide_values() {
+        assert_eq!(status_max().validate(), Ok(()));
+        let too_wide = [
+            Status {
+                level: 128,
+                ..status_max()
+            },
+            Status {
+                rank: 512,
+                ..status_max()
+            },
+            Status {
+                xp: 1 << 31,
+                ..status_max()
+            },
+            Status {
+                tier: 16,
+                ..status_max()
+            },
+        ];
+        for status in too_wide {
+            assert_eq!(status.validate(), Err(GoltError::CapacityExceeded));
+        }
+    }
+}
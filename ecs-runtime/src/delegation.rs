@@ -12,7 +12,9 @@
 //! 1. Call `delegate_account` with the component PDA and config
 //! 2. Account ownership transfers to delegation program
 //! 3. ER validator can now process transactions on the account
-//! 4. Call `undelegate` (or schedule commit+undelegate) to return to L1
+//! 4. Schedule a commit and undelegation on the ER to return to L1
+//! 5. The delegation program calls back with `EXTERNAL_UNDELEGATE_DISCRIMINATOR`,
+//!    and `undelegate` recreates the account from the committed buffer
 //!
 //! # Example
 //! ```ignore
@@ -33,13 +35,24 @@
 //!     )
 //! }
 //! ```
+//!
+//! Components with `#[component(delegatable)]` get this as
+//! `Name::process_delegate`, built on `process_delegate` below. Delegating
+//! and undelegating a component takes the signature of its entity's owner
+//! or of an allowlisted system.
+
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
 
-use pinocchio::{account_info::AccountInfo, pubkey::Pubkey};
+use crate::{
+    check_component_account, derive_pda, load_component, program_ids, require_allowed_system,
+    require_entity_owner, verify_pda_with_bump, AccountContext, Delegatable, GoltError,
+};
 
 // Re-export from ephemeral-rollups-pinocchio
 pub use ephemeral_rollups_pinocchio::{
+    consts::{EXTERNAL_UNDELEGATE_DISCRIMINATOR, MAGIC_CONTEXT_ID, MAGIC_PROGRAM_ID},
     id as DELEGATION_PROGRAM_ID,
-    instruction::{delegate_account, undelegate},
+    instruction::{commit_and_undelegate_accounts, delegate_account, undelegate},
     pda::{
         delegation_metadata_pda_from_delegated_account,
         delegation_record_pda_from_delegated_account,
@@ -86,15 +99,526 @@ pub mod pda {
 
 /// Instruction discriminators for delegation callback handling
 pub mod discriminators {
-    /// Discriminator for undelegate callback (0xc4 prefix)
-    pub const UNDELEGATE_CALLBACK: u8 = 0xc4;
+    /// Prefix of the delegation program's undelegate callback
+    ///
+    /// The callback is recognised by all eight bytes, not just the first.
+    pub const UNDELEGATE_CALLBACK: [u8; 8] = super::EXTERNAL_UNDELEGATE_DISCRIMINATOR;
 }
 
+/// PDA seed of the delegation program's undelegate buffer for an account
+pub const UNDELEGATE_BUFFER_SEED: &[u8] = b"undelegate-buffer";
+
+/// Instruction tag of the delegate instruction, as in the CLI templates
+pub const DELEGATE_DISCRIMINATOR: u8 = 253;
+
+/// Instruction tag of the explicit undelegate instruction
+pub const UNDELEGATE_DISCRIMINATOR: u8 = 254;
+
 /// Check if instruction data is an undelegate callback
 ///
-/// The delegation program calls back with a specific discriminator.
-/// Use this to detect and handle undelegate callbacks in your processor.
+/// The delegation program calls back with the 8-byte
+/// `EXTERNAL_UNDELEGATE_DISCRIMINATOR`, followed by the account's seeds.
+/// Earlier versions only compared the first byte (`0xc4`), which also matched
+/// unrelated instructions. Use this to detect and handle undelegate callbacks
+/// in your processor.
 #[inline]
 pub fn is_undelegate_callback(instruction_data: &[u8]) -> bool {
-    !instruction_data.is_empty() && instruction_data[0] == discriminators::UNDELEGATE_CALLBACK
+    instruction_data.starts_with(&EXTERNAL_UNDELEGATE_DISCRIMINATOR)
+}
+
+/// Handle the delegation instructions of a `Delegatable` component program
+///
+/// Returns `None` if `instruction_data` is not one of them:
+/// - `DELEGATE_DISCRIMINATOR` + `commit_frequency_ms: u32` + 32-byte validator:
+///   see `process_delegate`
+/// - `UNDELEGATE_DISCRIMINATOR`: see `process_undelegate`
+/// - the delegation program's undelegate callback: see
+///   `process_undelegate_callback`
+///
+/// `registry_program_id` is the entity registry holding the components' entities.
+pub fn process_delegation_instruction<C: Delegatable>(
+    program_id: &Pubkey,
+    registry_program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Option<Result<(), ProgramError>> {
+    if is_undelegate_callback(instruction_data) {
+        return Some(process_undelegate_callback::<C>(
+            program_id,
+            accounts,
+            instruction_data,
+        ));
+    }
+    let (&tag, rest) = instruction_data.split_first()?;
+    match tag {
+        DELEGATE_DISCRIMINATOR => Some(match (rest.get(..4), rest.get(4..36)) {
+            (Some(frequency), Some(validator)) => process_delegate::<C>(
+                program_id,
+                registry_program_id,
+                accounts,
+                u32::from_le_bytes(frequency.try_into().unwrap()),
+                validator.try_into().unwrap(),
+            ),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }),
+        UNDELEGATE_DISCRIMINATOR => Some(process_undelegate::<C>(
+            program_id,
+            registry_program_id,
+            accounts,
+        )),
+        _ => None,
+    }
+}
+
+/// Check that the owner of the component's entity, or an allowlisted system,
+/// signed for it
+///
+/// `entity_or_allowlist` is the component's entity, whose owner must be
+/// `authority`, or else the component's allowlist, which must list
+/// `authority` as a system signer PDA.
+fn require_delegation_authority<C: Delegatable>(
+    component: &C,
+    authority: &AccountInfo,
+    entity_or_allowlist: &AccountInfo,
+    program_id: &Pubkey,
+    registry_program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if entity_or_allowlist.key() == component.get_entity() {
+        require_entity_owner(entity_or_allowlist, authority, registry_program_id)
+    } else {
+        require_allowed_system(entity_or_allowlist, authority, program_id)
+    }
+}
+
+/// Delegate a `C` account to an Ephemeral Rollup validator
+///
+/// Accounts: `[payer (signer), authority (signer), entity or allowlist,
+/// component (writable), buffer PDA, delegation record PDA, delegation
+/// metadata PDA, owner program (this program), system program, delegation
+/// program]`. The authority must own the component's entity, or be a system
+/// signer PDA in the allowlist. The component's PDA is checked against its
+/// stored entity and bump before the delegation program takes it over.
+pub fn process_delegate<C: Delegatable>(
+    program_id: &Pubkey,
+    registry_program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    commit_frequency_ms: u32,
+    validator: Pubkey,
+) -> Result<(), ProgramError> {
+    let mut ctx = AccountContext::new(accounts);
+    let payer = ctx.next_signer()?;
+    let authority = ctx.next()?;
+    let entity_or_allowlist = ctx.next()?;
+    let component_account = ctx.next_writable()?;
+    let buffer = ctx.next()?;
+    let delegation_record = ctx.next()?;
+    let delegation_metadata = ctx.next()?;
+    let owner_program = ctx.next_program(program_id)?;
+    ctx.next_program(&program_ids::SYSTEM)?;
+    ctx.next_program(&DELEGATION_PROGRAM)?;
+
+    check_component_account::<C>(component_account, program_id)?;
+    let component = load_component::<C>(component_account)?;
+    require_delegation_authority(
+        &component,
+        authority,
+        entity_or_allowlist,
+        program_id,
+        registry_program_id,
+    )?;
+    let bump = component.get_bump();
+    C::verify_pda_with_bump(
+        component_account.key(),
+        &[component.get_entity().as_ref()],
+        bump,
+        program_id,
+    )?;
+
+    let config = DelegateConfig {
+        commit_frequency_ms,
+        validator: Some(validator),
+    };
+    delegate_account(
+        &[
+            payer,
+            component_account,
+            owner_program,
+            buffer,
+            delegation_record,
+            delegation_metadata,
+        ],
+        &component.delegation_seeds(),
+        bump,
+        config,
+    )
+}
+
+/// Commit a delegated `C` account and hand it back to the base layer
+///
+/// Runs on the Ephemeral Rollup. Accounts: `[payer (signer), authority
+/// (signer), entity or allowlist, component (writable), magic context
+/// (writable), magic program]`, with the authority checked as in
+/// `process_delegate`. The delegation program then calls back into
+/// `process_undelegate_callback` on the base layer.
+pub fn process_undelegate<C: Delegatable>(
+    program_id: &Pubkey,
+    registry_program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    let mut ctx = AccountContext::new(accounts);
+    let payer = ctx.next_signer()?;
+    let authority = ctx.next()?;
+    let entity_or_allowlist = ctx.next()?;
+    let component_account = ctx.next_writable()?;
+    let magic_context = ctx.next_writable()?;
+    let magic_program = ctx.next_program(&MAGIC_PROGRAM_ID)?;
+    if magic_context.key() != &MAGIC_CONTEXT_ID {
+        return Err(GoltError::InvalidAccountData.into());
+    }
+
+    check_component_account::<C>(component_account, program_id)?;
+    let component = load_component::<C>(component_account)?;
+    require_delegation_authority(
+        &component,
+        authority,
+        entity_or_allowlist,
+        program_id,
+        registry_program_id,
+    )?;
+
+    commit_and_undelegate_accounts(
+        payer,
+        core::slice::from_ref(component_account),
+        magic_context,
+        magic_program,
+    )
+}
+
+/// Recreate an undelegated component account from the delegation program's buffer
+///
+/// Handles `EXTERNAL_UNDELEGATE_DISCRIMINATOR` followed by the account's PDA
+/// seeds, Borsh-encoded as `Vec<Vec<u8>>`. Accounts: `[component (writable),
+/// buffer (signer), payer (signer, writable), system program]`. The buffer
+/// must be the delegation program's undelegate buffer for the component, so
+/// only the delegation program can sign for it, and must hold a `C` whose
+/// stored bump, with the seeds, derives the component's address.
+pub fn process_undelegate_callback<C: Delegatable>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let callback_args = instruction_data
+        .strip_prefix(&EXTERNAL_UNDELEGATE_DISCRIMINATOR)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let mut ctx = AccountContext::new(accounts);
+    let component_account = ctx.next_writable()?;
+    let buffer = ctx.next_signer()?;
+    let payer = ctx.next_signer_writable()?;
+    ctx.next_program(&program_ids::SYSTEM)?;
+
+    if unsafe { buffer.owner() } != &DELEGATION_PROGRAM {
+        return Err(GoltError::InvalidProgramId.into());
+    }
+    let (expected_buffer, _) = derive_pda(
+        &[UNDELEGATE_BUFFER_SEED, component_account.key().as_ref()],
+        &DELEGATION_PROGRAM,
+    );
+    if buffer.key() != &expected_buffer {
+        return Err(GoltError::InvalidPda.into());
+    }
+
+    let component = load_component::<C>(buffer)?;
+    let seeds = parse_seeds(callback_args)?;
+    verify_pda_with_bump(
+        component_account.key(),
+        &seeds,
+        component.get_bump(),
+        program_id,
+    )?;
+
+    undelegate(component_account, program_id, buffer, payer, callback_args)
+}
+
+/// Split Borsh-encoded `Vec<Vec<u8>>` seeds, as `undelegate` reads them
+fn parse_seeds(mut data: &[u8]) -> Result<Vec<&[u8]>, ProgramError> {
+    fn read_len(data: &mut &[u8]) -> Result<usize, ProgramError> {
+        let (len, rest) = data
+            .split_first_chunk::<4>()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        *data = rest;
+        Ok(u32::from_le_bytes(*len) as usize)
+    }
+
+    let count = read_len(&mut data)?;
+    let mut seeds = Vec::with_capacity(count.min(16));
+    for _ in 0..count {
+        let len = read_len(&mut data)?;
+        if data.len() < len {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let (seed, rest) = data.split_at(len);
+        seeds.push(seed);
+        data = rest;
+    }
+    if !data.is_empty() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(seeds)
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::Component;
+
+    use super::*;
+    use crate::test_utils::TestAccount;
+    use crate::{Allowlist, Component, ALLOWLIST_SEED, ALLOWLIST_SIZE, ENTITY_DISCRIMINATOR};
+
+    const PROGRAM_ID: Pubkey = [5; 32];
+    const REGISTRY_ID: Pubkey = [1; 32];
+    const OWNER: Pubkey = [2; 32];
+    const ENTITY: Pubkey = [3; 32];
+    const SYSTEM_ID: Pubkey = [4; 32];
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "health", delegatable)]
+    struct Health {
+        #[entity]
+        entity: [u8; 32],
+        current: u32,
+        #[pda_bump]
+        bump: u8,
+    }
+
+    fn health_data() -> (Pubkey, Vec<u8>) {
+        let (address, bump) = Health::derive_pda(&[&ENTITY], &PROGRAM_ID);
+        let mut data = vec![0; Health::SIZE];
+        Health {
+            entity: ENTITY,
+            current: 7,
+            bump,
+        }
+        .pack(&mut data);
+        (address, data)
+    }
+
+    /// Entity account as the golt-registry program writes it
+    fn registry_entity() -> TestAccount {
+        let mut data = vec![0; 56];
+        data[0..8].copy_from_slice(&ENTITY_DISCRIMINATOR);
+        data[16..48].copy_from_slice(&OWNER);
+        data[48] = 1;
+        TestAccount::new(ENTITY, REGISTRY_ID, &data)
+    }
+
+    fn allowlist() -> TestAccount {
+        let (address, bump) = derive_pda(&[ALLOWLIST_SEED], &PROGRAM_ID);
+        let mut allowlist = Allowlist::new(OWNER, bump);
+        allowlist.add(&SYSTEM_ID).unwrap();
+        let mut data = vec![0; ALLOWLIST_SIZE];
+        allowlist.pack(&mut data);
+        TestAccount::new(address, PROGRAM_ID, &data)
+    }
+
+    /// `process_undelegate` accounts, after the authority and its entity or allowlist
+    fn undelegate(authority: TestAccount, entity_or_allowlist: TestAccount) -> Vec<TestAccount> {
+        let (address, data) = health_data();
+        vec![
+            TestAccount::new([9; 32], [0; 32], &[]).signer(),
+            authority,
+            entity_or_allowlist,
+            TestAccount::new(address, PROGRAM_ID, &data),
+            TestAccount::new(MAGIC_CONTEXT_ID, MAGIC_PROGRAM_ID, &[]),
+            TestAccount::new(MAGIC_PROGRAM_ID, [0; 32], &[]),
+        ]
+    }
+
+    fn run(accounts: &mut [TestAccount], data: &[u8]) -> Option<Result<(), ProgramError>> {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        process_delegation_instruction::<Health>(&PROGRAM_ID, &REGISTRY_ID, &infos, data)
+    }
+
+    #[test]
+    fn dispatch() {
+        assert_eq!(run(&mut [], &[]), None);
+        assert_eq!(run(&mut [], &[1, 2, 3]), None);
+        // A lone 0xc4 is not the callback
+        assert_eq!(run(&mut [], &[0xc4]), None);
+        assert_eq!(
+            run(&mut [], &[DELEGATE_DISCRIMINATOR, 0, 0, 0]),
+            Some(Err(ProgramError::InvalidInstructionData))
+        );
+        let mut delegate = vec![DELEGATE_DISCRIMINATOR];
+        delegate.extend_from_slice(&[0; 36]);
+        assert_eq!(
+            run(&mut [], &delegate),
+            Some(Err(ProgramError::NotEnoughAccountKeys))
+        );
+        assert_eq!(
+            run(&mut [], &[UNDELEGATE_DISCRIMINATOR]),
+            Some(Err(ProgramError::NotEnoughAccountKeys))
+        );
+        assert!(is_undelegate_callback(&EXTERNAL_UNDELEGATE_DISCRIMINATOR));
+        assert_eq!(
+            run(&mut [], &EXTERNAL_UNDELEGATE_DISCRIMINATOR),
+            Some(Err(ProgramError::NotEnoughAccountKeys))
+        );
+
+        let owner = TestAccount::new(OWNER, [0; 32], &[]).signer();
+        let mut accounts = undelegate(owner, registry_entity());
+        assert_eq!(
+            run(&mut accounts, &[UNDELEGATE_DISCRIMINATOR]),
+            Some(Ok(()))
+        );
+    }
+
+    #[test]
+    fn undelegate_authority() {
+        let owner = || TestAccount::new(OWNER, [0; 32], &[]).signer();
+        let undelegate_with = |mut accounts: Vec<TestAccount>| {
+            run(&mut accounts, &[UNDELEGATE_DISCRIMINATOR]).unwrap()
+        };
+
+        assert_eq!(
+            undelegate_with(undelegate(owner(), registry_entity())),
+            Ok(())
+        );
+        assert_eq!(
+            undelegate_with(undelegate(
+                TestAccount::new(OWNER, [0; 32], &[]),
+                registry_entity()
+            )),
+            Err(GoltError::AccountNotSigner.into())
+        );
+        assert_eq!(
+            undelegate_with(undelegate(
+                TestAccount::new([8; 32], [0; 32], &[]).signer(),
+                registry_entity()
+            )),
+            Err(GoltError::InvalidAuthority.into())
+        );
+
+        // Allowlisted systems sign with their signer PDA
+        let (system_signer, _) = crate::derive_system_signer(&SYSTEM_ID);
+        let signer = || TestAccount::new(system_signer, [0; 32], &[]).signer();
+        assert_eq!(undelegate_with(undelegate(signer(), allowlist())), Ok(()));
+        // The entity owner is not a system
+        assert_eq!(
+            undelegate_with(undelegate(owner(), allowlist())),
+            Err(GoltError::SystemNotAllowed.into())
+        );
+        // An account that is neither the entity nor the allowlist
+        let (_, data) = health_data();
+        assert_eq!(
+            undelegate_with(undelegate(
+                signer(),
+                TestAccount::new([7; 32], PROGRAM_ID, &data)
+            )),
+            Err(GoltError::InvalidDiscriminator.into())
+        );
+
+        // Only the magic context can be committed through
+        let mut accounts = undelegate(owner(), registry_entity());
+        accounts[4] = TestAccount::new([7; 32], MAGIC_PROGRAM_ID, &[]);
+        assert_eq!(
+            undelegate_with(accounts),
+            Err(GoltError::InvalidAccountData.into())
+        );
+    }
+
+    #[test]
+    fn delegate_authority() {
+        let (address, data) = health_data();
+        let delegate = |authority: TestAccount| {
+            let mut accounts = vec![
+                TestAccount::new([9; 32], [0; 32], &[]).signer(),
+                authority,
+                registry_entity(),
+                TestAccount::new(address, PROGRAM_ID, &data),
+                TestAccount::new([10; 32], [0; 32], &[]),
+                TestAccount::new([11; 32], [0; 32], &[]),
+                TestAccount::new([12; 32], [0; 32], &[]),
+                TestAccount::new(PROGRAM_ID, [0; 32], &[]),
+                TestAccount::new(program_ids::SYSTEM, [0; 32], &[]),
+                TestAccount::new(DELEGATION_PROGRAM, [0; 32], &[]),
+            ];
+            let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+            process_delegate::<Health>(&PROGRAM_ID, &REGISTRY_ID, &infos, 1000, [13; 32])
+        };
+
+        assert_eq!(
+            delegate(TestAccount::new(OWNER, [0; 32], &[])),
+            Err(GoltError::AccountNotSigner.into())
+        );
+        assert_eq!(
+            delegate(TestAccount::new([8; 32], [0; 32], &[]).signer()),
+            Err(GoltError::InvalidAuthority.into())
+        );
+    }
+
+    #[test]
+    fn undelegate_callback_checks_the_buffer() {
+        let (address, data) = health_data();
+        let (buffer_key, _) = derive_pda(&[UNDELEGATE_BUFFER_SEED, &address], &DELEGATION_PROGRAM);
+        let mut args = EXTERNAL_UNDELEGATE_DISCRIMINATOR.to_vec();
+        args.extend_from_slice(&2u32.to_le_bytes());
+        for seed in [&b"health"[..], &ENTITY] {
+            args.extend_from_slice(&(seed.len() as u32).to_le_bytes());
+            args.extend_from_slice(seed);
+        }
+        let callback = |buffer: TestAccount, args: &[u8]| {
+            let mut accounts = [
+                TestAccount::new(address, [0; 32], &[]),
+                buffer,
+                TestAccount::new([9; 32], [0; 32], &[]).signer(),
+                TestAccount::new(program_ids::SYSTEM, [0; 32], &[]),
+            ];
+            let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+            process_undelegate_callback::<Health>(&PROGRAM_ID, &infos, args)
+        };
+
+        // Any keypair can sign, but only the delegation program owns the buffer
+        assert_eq!(
+            callback(TestAccount::new([8; 32], [0; 32], &data).signer(), &args),
+            Err(GoltError::InvalidProgramId.into())
+        );
+        assert_eq!(
+            callback(
+                TestAccount::new([8; 32], DELEGATION_PROGRAM, &data).signer(),
+                &args
+            ),
+            Err(GoltError::InvalidPda.into())
+        );
+        assert_eq!(
+            callback(
+                TestAccount::new(buffer_key, DELEGATION_PROGRAM, &data),
+                &args
+            ),
+            Err(GoltError::AccountNotSigner.into())
+        );
+
+        let buffer = || TestAccount::new(buffer_key, DELEGATION_PROGRAM, &data).signer();
+        // Seeds of another account
+        let mut other = EXTERNAL_UNDELEGATE_DISCRIMINATOR.to_vec();
+        other.extend_from_slice(&1u32.to_le_bytes());
+        other.extend_from_slice(&6u32.to_le_bytes());
+        other.extend_from_slice(b"health");
+        assert_eq!(
+            callback(buffer(), &other),
+            Err(GoltError::InvalidPda.into())
+        );
+        // Truncated seeds
+        assert_eq!(
+            callback(buffer(), &args[..args.len() - 1]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        // A buffer that doesn't hold a `Health`
+        assert_eq!(
+            callback(
+                TestAccount::new(buffer_key, DELEGATION_PROGRAM, &[0; 8]).signer(),
+                &args
+            ),
+            Err(GoltError::InvalidAccountData.into())
+        );
+    }
 }
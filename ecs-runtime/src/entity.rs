@@ -9,7 +9,10 @@ use pinocchio::{
 };
 use pinocchio_system::instructions::CreateAccount;
 
-use crate::GoltError;
+use crate::{
+    check_component_account, load_component, load_component_mut, ComponentMut, EntityComponent,
+    GoltError,
+};
 
 /// Entity discriminator (first 8 bytes of SHA256("entity"))
 pub const ENTITY_DISCRIMINATOR: [u8; 8] = [0x65, 0x6e, 0x74, 0x69, 0x74, 0x79, 0x00, 0x00];
@@ -17,6 +20,12 @@ pub const ENTITY_DISCRIMINATOR: [u8; 8] = [0x65, 0x6e, 0x74, 0x69, 0x74, 0x79, 0
 /// PDA seed prefix for entities
 pub const ENTITY_SEED: &[u8] = b"entity";
 
+/// Offset of the owner key in entity accounts of the golt-registry program
+///
+/// The registry lays entities out as discriminator (8), ID (8), owner (32),
+/// active (1), bump (1) and 6 reserved bytes.
+pub const REGISTRY_ENTITY_OWNER_OFFSET: usize = 16;
+
 /// Size of Entity struct in bytes:
 /// - 8 bytes discriminator
 /// - 32 bytes owner pubkey
@@ -86,10 +95,7 @@ pub trait EntityRegistry {
     /// Derive the PDA for an entity given its ID
     fn derive_entity_pda(entity_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
         let entity_id_bytes = entity_id.to_le_bytes();
-        pinocchio::pubkey::find_program_address(
-            &[ENTITY_SEED, &entity_id_bytes],
-            program_id,
-        )
+        crate::pda::derive_pda(&[ENTITY_SEED, &entity_id_bytes], program_id)
     }
 
     /// Verify an entity PDA
@@ -104,15 +110,28 @@ pub trait EntityRegistry {
         }
         Ok(bump)
     }
+
+    /// Verify an entity PDA using the bump stored in the entity account
+    fn verify_entity_pda_with_bump(
+        account_key: &Pubkey,
+        entity_id: u64,
+        bump: u8,
+        program_id: &Pubkey,
+    ) -> Result<(), GoltError> {
+        let entity_id_bytes = entity_id.to_le_bytes();
+        crate::pda::verify_pda_with_bump(
+            account_key,
+            &[ENTITY_SEED, &entity_id_bytes],
+            bump,
+            program_id,
+        )
+    }
 }
 
 /// Derive the PDA for an entity given its ID
 pub fn derive_entity_pda(entity_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
     let entity_id_bytes = entity_id.to_le_bytes();
-    pinocchio::pubkey::find_program_address(
-        &[ENTITY_SEED, &entity_id_bytes],
-        program_id,
-    )
+    crate::pda::derive_pda(&[ENTITY_SEED, &entity_id_bytes], program_id)
 }
 
 /// Create a new entity account
@@ -216,3 +235,197 @@ pub fn load_entity(entity_account: &AccountInfo) -> Result<Entity, ProgramError>
     let data = entity_account.try_borrow_data()?;
     Entity::unpack(&data).ok_or(GoltError::InvalidAccountData.into())
 }
+
+/// Check that a component belongs to an active entity
+///
+/// `component_account` must be owned by `program_id` and hold a `C`.
+/// `entity_account` must be owned by the registry program, hold an active
+/// entity, and its key must match the component's `#[entity]` field.
+pub fn check_component_entity<C: EntityComponent>(
+    component_account: &AccountInfo,
+    entity_account: &AccountInfo,
+    program_id: &Pubkey,
+    registry_program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    check_component_account::<C>(component_account, program_id)?;
+    if unsafe { entity_account.owner() } != registry_program_id {
+        return Err(GoltError::InvalidProgramId.into());
+    }
+    if !is_entity_active(entity_account)? {
+        return Err(GoltError::EntityNotActive.into());
+    }
+
+    let data = component_account.try_borrow_data()?;
+    let entity_key = C::entity_key(&data).ok_or(GoltError::InvalidAccountData)?;
+    if entity_key != entity_account.key() {
+        return Err(GoltError::EntityMismatch.into());
+    }
+    Ok(())
+}
+
+/// Check that the owner of an active registry entity signed
+///
+/// `entity_account` must be owned by the registry program and hold an active
+/// entity whose owner is `owner`. Entities are registry PDAs and can't sign
+/// themselves, so instructions acting for an entity take its owner instead.
+pub fn require_entity_owner(
+    entity_account: &AccountInfo,
+    owner: &AccountInfo,
+    registry_program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    if !owner.is_signer() {
+        return Err(GoltError::AccountNotSigner.into());
+    }
+    if unsafe { entity_account.owner() } != registry_program_id {
+        return Err(GoltError::InvalidProgramId.into());
+    }
+    if !is_entity_active(entity_account)? {
+        return Err(GoltError::EntityNotActive.into());
+    }
+    let data = entity_account.try_borrow_data()?;
+    let stored = data
+        .get(REGISTRY_ENTITY_OWNER_OFFSET..REGISTRY_ENTITY_OWNER_OFFSET + 32)
+        .ok_or(GoltError::InvalidAccountData)?;
+    if stored != owner.key() {
+        return Err(GoltError::InvalidAuthority.into());
+    }
+    Ok(())
+}
+
+/// Load a component after checking it belongs to an active entity
+pub fn load_component_for_entity<C: EntityComponent>(
+    component_account: &AccountInfo,
+    entity_account: &AccountInfo,
+    program_id: &Pubkey,
+    registry_program_id: &Pubkey,
+) -> Result<C, ProgramError> {
+    check_component_entity::<C>(
+        component_account,
+        entity_account,
+        program_id,
+        registry_program_id,
+    )?;
+    load_component(component_account)
+}
+
+/// Load a component mutably after checking it belongs to an active entity
+pub fn load_component_for_entity_mut<'a, C: EntityComponent>(
+    component_account: &'a AccountInfo,
+    entity_account: &AccountInfo,
+    program_id: &Pubkey,
+    registry_program_id: &Pubkey,
+) -> Result<ComponentMut<'a, C>, ProgramError> {
+    check_component_entity::<C>(
+        component_account,
+        entity_account,
+        program_id,
+        registry_program_id,
+    )?;
+    load_component_mut(component_account)
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::Component;
+
+    use super::*;
+    use crate::test_utils::TestAccount;
+    use crate::Component;
+
+    const PROGRAM_ID: Pubkey = [5; 32];
+    const REGISTRY_ID: Pubkey = [1; 32];
+    const OWNER: Pubkey = [2; 32];
+    const ENTITY: Pubkey = [3; 32];
+
+    /// Entity account as the golt-registry program writes it
+    fn registry_entity(owner: Pubkey, active: bool) -> TestAccount {
+        let mut data = vec![0; 56];
+        data[0..8].copy_from_slice(&ENTITY_DISCRIMINATOR);
+        data[8..16].copy_from_slice(&7u64.to_le_bytes());
+        data[16..48].copy_from_slice(&owner);
+        data[48] = active as u8;
+        TestAccount::new(ENTITY, REGISTRY_ID, &data)
+    }
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "health")]
+    struct Health {
+        #[entity]
+        entity: [u8; 32],
+        current: u32,
+    }
+
+    fn health_account(owner: Pubkey, entity: Pubkey) -> TestAccount {
+        let mut data = vec![0; Health::SIZE];
+        Health { entity, current: 7 }.pack(&mut data);
+        TestAccount::new([6; 32], owner, &data)
+    }
+
+    #[test]
+    fn component_entity_checks() {
+        let mut entity = registry_entity(OWNER, true);
+        let entity = entity.info();
+        let mut health = health_account(PROGRAM_ID, ENTITY);
+        let health = health.info();
+        assert_eq!(
+            load_component_for_entity::<Health>(&health, &entity, &PROGRAM_ID, &REGISTRY_ID),
+            Ok(Health {
+                entity: ENTITY,
+                current: 7
+            })
+        );
+        assert!(
+            load_component_for_entity_mut::<Health>(&health, &entity, &PROGRAM_ID, &REGISTRY_ID)
+                .is_ok()
+        );
+
+        // Right discriminator and entity bytes, written by another program
+        let mut forged = health_account([9; 32], ENTITY);
+        assert_eq!(
+            check_component_entity::<Health>(&forged.info(), &entity, &PROGRAM_ID, &REGISTRY_ID),
+            Err(GoltError::InvalidProgramId.into())
+        );
+
+        let mut other = health_account(PROGRAM_ID, [4; 32]);
+        assert_eq!(
+            check_component_entity::<Health>(&other.info(), &entity, &PROGRAM_ID, &REGISTRY_ID),
+            Err(GoltError::EntityMismatch.into())
+        );
+
+        let mut inactive = registry_entity(OWNER, false);
+        assert_eq!(
+            check_component_entity::<Health>(&health, &inactive.info(), &PROGRAM_ID, &REGISTRY_ID),
+            Err(GoltError::EntityNotActive.into())
+        );
+    }
+
+    #[test]
+    fn entity_owner_must_sign() {
+        let mut entity = registry_entity(OWNER, true);
+        let mut owner = TestAccount::new(OWNER, [0; 32], &[]).signer();
+        let entity = entity.info();
+        let owner = owner.info();
+        assert_eq!(require_entity_owner(&entity, &owner, &REGISTRY_ID), Ok(()));
+
+        let mut unsigned = TestAccount::new(OWNER, [0; 32], &[]);
+        assert_eq!(
+            require_entity_owner(&entity, &unsigned.info(), &REGISTRY_ID),
+            Err(GoltError::AccountNotSigner.into())
+        );
+        let mut stranger = TestAccount::new([4; 32], [0; 32], &[]).signer();
+        assert_eq!(
+            require_entity_owner(&entity, &stranger.info(), &REGISTRY_ID),
+            Err(GoltError::InvalidAuthority.into())
+        );
+        assert_eq!(
+            require_entity_owner(&entity, &owner, &[5; 32]),
+            Err(GoltError::InvalidProgramId.into())
+        );
+
+        let mut inactive = registry_entity(OWNER, false);
+        assert_eq!(
+            require_entity_owner(&inactive.info(), &owner, &REGISTRY_ID),
+            Err(GoltError::EntityNotActive.into())
+        );
+    }
+}
@@ -1,61 +1,165 @@
 //! Common ECS errors
+//!
+//! Every Golt program gets its own range of `ProgramError::Custom` codes so a
+//! code identifies both the program and the error:
+//!
+//! | Range | Owner |
+//! |-------|-------|
+//! | 1000-1999 | `GoltError` (this crate) |
+//! | 2000-2999 | `RegistryError` (entity registry) |
+//! | 6000+ | Generated programs, 100 codes each, assigned in `golt.toml` |
+//!
+//! `#[derive(ErrorCode)]` numbers an enum's variants from its offset and
+//! builds an `ERROR_TABLE` of names and messages, which the CLI and the
+//! TypeScript bindings use to decode codes.
+
+use pinocchio::log::sol_log;
+
+pub use golt_macros::ErrorCode;
+
+/// First error code of `GoltError`
+pub const RUNTIME_ERROR_OFFSET: u32 = 1000;
+
+/// First error code of the entity registry
+pub const REGISTRY_ERROR_OFFSET: u32 = 2000;
+
+/// First error code handed out to generated programs
+pub const PROGRAM_ERROR_OFFSET: u32 = 6000;
+
+/// Number of error codes reserved for each generated program
+pub const PROGRAM_ERROR_RANGE: u32 = 100;
+
+/// Code, name and message of one error variant
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ErrorInfo {
+    /// `ProgramError::Custom` code
+    pub code: u32,
+    /// Variant name
+    pub name: &'static str,
+    /// Human-readable message
+    pub message: &'static str,
+}
+
+/// Error enum with codes in a fixed range, implemented by `#[derive(ErrorCode)]`
+pub trait ErrorCode: Sized + 'static {
+    /// First code of this enum
+    const OFFSET: u32;
+
+    /// Every variant, in code order
+    const ERROR_TABLE: &'static [ErrorInfo];
+
+    /// The `ProgramError::Custom` code of this error
+    fn code(&self) -> u32;
+
+    /// The error with the given code, if it belongs to this enum
+    fn from_code(code: u32) -> Option<Self>;
+
+    /// Table entry of this error
+    fn info(&self) -> &'static ErrorInfo {
+        &Self::ERROR_TABLE[(self.code() - Self::OFFSET) as usize]
+    }
+
+    /// Variant name
+    fn name(&self) -> &'static str {
+        self.info().name
+    }
+
+    /// Human-readable message
+    fn message(&self) -> &'static str {
+        self.info().message
+    }
+
+    /// Write the code, name and message to the program log
+    fn log(&self) {
+        let info = self.info();
+        sol_log(&format!("Error {} {}: {}", info.code, info.name, info.message));
+    }
+}
 
-use pinocchio::program_error::ProgramError;
-use thiserror::Error;
+/// Look up a code in several error tables
+pub fn find_error(tables: &[&'static [ErrorInfo]], code: u32) -> Option<&'static ErrorInfo> {
+    tables
+        .iter()
+        .find_map(|table| table.iter().find(|info| info.code == code))
+}
 
 /// Common errors shared across all ECS programs
-#[derive(Error, Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, ErrorCode)]
+#[golt_error(offset = 1000)]
 #[repr(u32)]
 pub enum GoltError {
-    #[error("Account not initialized")]
+    /// Account not initialized
     NotInitialized = 1000,
 
-    #[error("Account already initialized")]
+    /// Account already initialized
     AlreadyInitialized = 1001,
 
-    #[error("Invalid authority")]
+    /// Invalid authority
     InvalidAuthority = 1002,
 
-    #[error("Invalid account data")]
+    /// Invalid account data
     InvalidAccountData = 1003,
 
-    #[error("Account not writable")]
+    /// Account not writable
     AccountNotWritable = 1004,
 
-    #[error("Account not signer")]
+    /// Account not signer
     AccountNotSigner = 1005,
 
-    #[error("Invalid program ID")]
+    /// Invalid program ID
     InvalidProgramId = 1006,
 
-    #[error("Invalid PDA")]
+    /// Invalid PDA
     InvalidPda = 1007,
 
-    #[error("Invalid discriminator")]
+    /// Invalid discriminator
     InvalidDiscriminator = 1008,
 
-    #[error("Arithmetic overflow")]
+    /// Arithmetic overflow
     Overflow = 1009,
 
-    #[error("Arithmetic underflow")]
+    /// Arithmetic underflow
     Underflow = 1010,
 
-    #[error("Invalid instruction data")]
+    /// Invalid instruction data
     InvalidInstructionData = 1011,
 
-    #[error("Entity not active")]
+    /// Entity not active
     EntityNotActive = 1012,
 
-    #[error("Component not found")]
+    /// Component not found
     ComponentNotFound = 1013,
-}
 
-impl From<GoltError> for ProgramError {
-    fn from(e: GoltError) -> Self {
-        ProgramError::Custom(e as u32)
-    }
+    /// Account closed
+    AccountClosed = 1014,
+
+    /// Unsupported component version
+    UnsupportedVersion = 1015,
+
+    /// Query accounts misaligned
+    QueryMisaligned = 1016,
+
+    /// Component belongs to a different entity
+    EntityMismatch = 1017,
+
+    /// System not allowed
+    SystemNotAllowed = 1018,
+
+    /// Allowlist full
+    AllowlistFull = 1019,
+
+    /// Division by zero
+    DivisionByZero = 1020,
+
+    /// Field capacity exceeded
+    CapacityExceeded = 1021,
+
+    /// Account passed more than once
+    DuplicateAccount = 1022,
 }
 
+impl std::error::Error for GoltError {}
+
 /// Require a condition to be true
 #[macro_export]
 macro_rules! require {
@@ -0,0 +1,103 @@
+//! Structured program events
+//!
+//! Events are packed like components (8-byte discriminator, then fields
+//! little-endian) and written to the transaction log with `sol_log_data`,
+//! which shows up as a `Program data: <base64>` line. Indexers and the
+//! TypeScript decoders from `golt generate ts` read them from there instead
+//! of diffing account state.
+//!
+//! # Example
+//! ```ignore
+//! #[derive(Event)]
+//! pub struct DamageDealt {
+//!     pub target: [u8; 32],
+//!     pub amount: u32,
+//! }
+//!
+//! emit!(DamageDealt { target: *entity.key(), amount });
+//! ```
+
+/// Trait implemented by all program events
+pub trait Event: Sized {
+    /// Unique 8-byte discriminator
+    const DISCRIMINATOR: [u8; 8];
+
+    /// Size of the packed event in bytes, including the discriminator
+    const SIZE: usize;
+
+    /// Pack the event, discriminator first
+    fn pack(&self, data: &mut [u8]);
+
+    /// Unpack an event from its packed bytes
+    fn unpack(data: &[u8]) -> Option<Self>;
+
+    /// Write the event to the program log
+    fn emit(&self) {
+        let mut data = vec![0u8; Self::SIZE];
+        self.pack(&mut data);
+        pinocchio::log::sol_log_data(&[&data]);
+    }
+}
+
+/// Emit an event to the program log
+#[macro_export]
+macro_rules! emit {
+    ($event:expr) => {
+        $crate::Event::emit(&$event)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::Event;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    #[derive(Event, Debug, PartialEq)]
+    struct OwnershipTransferred {
+        to: [u8; 32],
+    }
+
+    #[derive(Event, Debug, PartialEq)]
+    struct OwnershipRevoked {
+        amount: u32,
+    }
+
+    #[derive(Event, Debug, PartialEq)]
+    #[event(discriminator = "ownership_transferred")]
+    struct Renamed {
+        amount: u32,
+    }
+
+    fn hashed(name: &str) -> [u8; 8] {
+        let hash = Sha256::digest(format!("event:{}", name));
+        hash[..8].try_into().unwrap()
+    }
+
+    #[test]
+    fn discriminators_are_hashed() {
+        assert_eq!(
+            OwnershipTransferred::DISCRIMINATOR,
+            hashed("ownership_transferred")
+        );
+        assert_eq!(OwnershipRevoked::DISCRIMINATOR, hashed("ownership_revoked"));
+        // Padded to 8 bytes both would be "ownershi"
+        assert_ne!(
+            OwnershipTransferred::DISCRIMINATOR,
+            OwnershipRevoked::DISCRIMINATOR
+        );
+        assert_eq!(Renamed::DISCRIMINATOR, hashed("ownership_transferred"));
+    }
+
+    #[test]
+    fn pack_unpack() {
+        let event = OwnershipRevoked { amount: 7 };
+        let mut data = vec![0; OwnershipRevoked::SIZE];
+        event.pack(&mut data);
+        assert_eq!(data[..8], OwnershipRevoked::DISCRIMINATOR);
+        assert_eq!(data[8..], 7u32.to_le_bytes());
+        assert_eq!(OwnershipRevoked::unpack(&data), Some(event));
+        assert_eq!(OwnershipTransferred::unpack(&data), None);
+    }
+}
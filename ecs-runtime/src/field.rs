@@ -0,0 +1,236 @@
+//! Nested component fields
+//!
+//! `#[derive(Component)]` packs primitives, pubkeys and byte arrays itself.
+//! Any other field type must implement `ComponentField`, usually through
+//! `#[derive(ComponentField)]`, which works for structs and for enums with or
+//! without data:
+//!
+//! ```ignore
+//! #[derive(Clone, Copy, ComponentField)]
+//! pub struct Stats {
+//!     pub strength: u16,
+//!     pub agility: u16,
+//! }
+//!
+//! #[derive(Clone, Copy, ComponentField)]
+//! pub enum Status {
+//!     Idle,
+//!     Stunned { turns: u8 },
+//!     Poisoned(u32, Fixed),
+//! }
+//!
+//! #[derive(Component)]
+//! #[component(seed = "hero")]
+//! pub struct Hero {
+//!     pub entity: [u8; 32],
+//!     pub stats: Stats,    // 4 bytes
+//!     pub status: Status,  // 1-byte tag + largest variant (12 bytes)
+//! }
+//! ```
+//!
+//! Structs are their fields packed in order. Enums are a `u8` tag (the
+//! variant index) followed by the variant's fields, zero-padded to the largest
+//! variant so `SIZE` is fixed. A field type without an implementation is a
+//! compile error.
+
+pub use golt_macros::ComponentField;
+
+use crate::Fixed;
+
+/// A fixed-size value that can be nested inside a component
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be stored in a component",
+    note = "add `#[derive(ComponentField)]` to it, or use a supported primitive"
+)]
+pub trait ComponentField: Sized {
+    /// Packed size in bytes
+    const SIZE: usize;
+
+    /// Pack into `data[..Self::SIZE]`
+    fn pack_into(&self, data: &mut [u8]);
+
+    /// Unpack from `data[..Self::SIZE]`, `None` if it is too short or invalid
+    fn unpack_from(data: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_le_field {
+    ($($ty:ty => $size:expr),* $(,)?) => {
+        $(
+            impl ComponentField for $ty {
+                const SIZE: usize = $size;
+
+                fn pack_into(&self, data: &mut [u8]) {
+                    data[..$size].copy_from_slice(&self.to_le_bytes());
+                }
+
+                fn unpack_from(data: &[u8]) -> Option<Self> {
+                    Some(<$ty>::from_le_bytes(data.get(..$size)?.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_le_field! {
+    u8 => 1, i8 => 1,
+    u16 => 2, i16 => 2,
+    u32 => 4, i32 => 4, f32 => 4,
+    u64 => 8, i64 => 8, f64 => 8,
+    u128 => 16, i128 => 16,
+    Fixed => 8,
+}
+
+impl ComponentField for bool {
+    const SIZE: usize = 1;
+
+    fn pack_into(&self, data: &mut [u8]) {
+        data[0] = *self as u8;
+    }
+
+    fn unpack_from(data: &[u8]) -> Option<Self> {
+        Some(*data.first()? != 0)
+    }
+}
+
+impl<T: ComponentField, const N: usize> ComponentField for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn pack_into(&self, data: &mut [u8]) {
+        for (i, item) in self.iter().enumerate() {
+            item.pack_into(&mut data[i * T::SIZE..]);
+        }
+    }
+
+    fn unpack_from(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE {
+            return None;
+        }
+        let items: [Option<T>; N] =
+            core::array::from_fn(|i| T::unpack_from(&data[i * T::SIZE..]));
+        if items.iter().any(Option::is_none) {
+            return None;
+        }
+        Some(items.map(|item| item.unwrap()))
+    }
+}
+
+/// Larger of two sizes; `#[derive(ComponentField)]` uses it for enum `SIZE`
+#[doc(hidden)]
+pub const fn max_size(a: usize, b: usize) -> usize {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::{Component, ComponentField};
+
+    use super::*;
+    use crate::Component;
+
+    #[derive(Clone, Copy, Debug, PartialEq, ComponentField)]
+    struct Stats {
+        strength: u16,
+        agility: u16,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, ComponentField)]
+    enum Status {
+        Idle,
+        Stunned { turns: u8 },
+        Poisoned(u32, Fixed),
+        Buffed(Stats, bool),
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, ComponentField)]
+    enum Direction {
+        North,
+        South,
+    }
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "hero")]
+    struct Hero {
+        #[entity]
+        entity: [u8; 32],
+        stats: Stats,
+        status: Status,
+        facing: [Direction; 2],
+        gold: u64,
+    }
+
+    fn roundtrip<T: ComponentField + PartialEq + core::fmt::Debug>(value: T) -> Vec<u8> {
+        // Stale bytes show what `pack_into` leaves untouched
+        let mut data = vec![0xaa; T::SIZE];
+        value.pack_into(&mut data);
+        assert_eq!(T::unpack_from(&data), Some(value));
+        data
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(Stats::SIZE, 4);
+        // u8 tag, then the largest variant: Poisoned(u32, Fixed)
+        assert_eq!(Status::SIZE, 1 + 12);
+        assert_eq!(Direction::SIZE, 1);
+        assert_eq!(<[Direction; 2]>::SIZE, 2);
+        assert_eq!(Hero::SIZE, 8 + 32 + 4 + 13 + 2 + 8);
+    }
+
+    #[test]
+    fn pack_unpack() {
+        assert_eq!(
+            roundtrip(Stats {
+                strength: 3,
+                agility: 0x0102,
+            }),
+            [3, 0, 2, 1]
+        );
+        assert_eq!(roundtrip(Direction::South), [1]);
+
+        // Smaller variants are zero-padded to `SIZE`
+        assert_eq!(roundtrip(Status::Idle), [0; 13]);
+        let mut stunned = [0; 13];
+        stunned[..2].copy_from_slice(&[1, 4]);
+        assert_eq!(roundtrip(Status::Stunned { turns: 4 }), stunned);
+        let poisoned = roundtrip(Status::Poisoned(7, Fixed::from_int(2)));
+        assert_eq!(poisoned[..5], [2, 7, 0, 0, 0]);
+        assert_eq!(poisoned[5..], Fixed::from_int(2).to_bits().to_le_bytes());
+        let buffed = roundtrip(Status::Buffed(
+            Stats {
+                strength: 1,
+                agility: 2,
+            },
+            true,
+        ));
+        assert_eq!(buffed[..6], [3, 1, 0, 2, 0, 1]);
+        assert_eq!(buffed[6..], [0; 7]);
+
+        let hero = Hero {
+            entity: [2; 32],
+            stats: Stats {
+                strength: 5,
+                agility: 6,
+            },
+            status: Status::Stunned { turns: 1 },
+            facing: [Direction::South, Direction::North],
+            gold: 9,
+        };
+        let mut data = vec![0; Hero::SIZE];
+        hero.pack(&mut data);
+        assert_eq!(data[40..44], [5, 0, 6, 0]);
+        assert_eq!(data[57..59], [1, 0]);
+        assert_eq!(Hero::unpack(&data), Some(hero));
+    }
+
+    #[test]
+    fn unpack_rejects_unknown_tags_and_short_data() {
+        assert_eq!(Status::unpack_from(&[4; 13]), None);
+        assert_eq!(Direction::unpack_from(&[2]), None);
+        assert_eq!(Stats::unpack_from(&[1, 2, 3]), None);
+        assert_eq!(<[Direction; 2]>::unpack_from(&[0, 2]), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! Property-based round-trip checks, with the `arbitrary` feature
+//!
+//! With the feature enabled, `#[derive(Component)]`, `#[derive(ComponentField)]`,
+//! `#[component_instructions]` and `#[system]` implement `Arbitrary` for the
+//! types they generate, keeping `Vec` / `String` fields within `#[max_len]`
+//! and bit fields within their bits. Components and instruction enums also
+//! get a `#[test]` running the matching check below on generated values.
+//!
+//! The same impls let `cargo fuzz` targets build typed inputs from raw bytes,
+//! and `FuzzAccounts` builds the accounts to call a processor with. Off-chain,
+//! PDAs are derived on the host and `rent` returns the cluster's defaults;
+//! CPIs do nothing and return `Ok`.
+//!
+//! # Example
+//! ```ignore
+//! #[test]
+//! fn position_roundtrip() {
+//!     golt_runtime::fuzz::check_component_roundtrip::<Position>();
+//! }
+//! ```
+
+use arbitrary::{Arbitrary, Unstructured};
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::Component;
+#[cfg(not(target_os = "solana"))]
+use crate::test_utils::TestAccount;
+
+/// Values generated by each round-trip check
+pub const ROUNDTRIP_CASES: u64 = 256;
+
+/// Bytes of input behind each generated value
+const INPUT_LEN: usize = 4096;
+
+/// `Vec` of at most `max_len` arbitrary elements
+pub fn arbitrary_vec<'a, T: Arbitrary<'a>>(
+    u: &mut Unstructured<'a>,
+    max_len: usize,
+) -> arbitrary::Result<Vec<T>> {
+    let len = u.int_in_range(0..=max_len)?;
+    (0..len).map(|_| u.arbitrary()).collect()
+}
+
+/// Arbitrary `String` of at most `max_len` bytes, cut on a char boundary
+pub fn arbitrary_string(u: &mut Unstructured<'_>, max_len: usize) -> arbitrary::Result<String> {
+    let mut value: String = u.arbitrary()?;
+    let mut end = value.len().min(max_len);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    value.truncate(end);
+    Ok(value)
+}
+
+/// Most accounts `FuzzAccounts::arbitrary` builds
+#[cfg(not(target_os = "solana"))]
+pub const MAX_FUZZ_ACCOUNTS: usize = 12;
+
+/// An account the processor expects: its key, owner and data
+#[cfg(not(target_os = "solana"))]
+#[derive(Clone, Copy, Debug)]
+pub struct KnownAccount<'a> {
+    /// Account address
+    pub key: Pubkey,
+    /// Owning program
+    pub owner: Pubkey,
+    /// Account data
+    pub data: &'a [u8],
+}
+
+/// Accounts built from fuzz input, for calling a processor off-chain
+///
+/// Half of the accounts are one of `known` (valid components, entities,
+/// ...), sometimes with truncated data. The rest take their key and owner
+/// mostly from `keys` (program IDs, PDAs, ...) and their data from `known`
+/// or arbitrary bytes. Signer and writable flags and lamports are arbitrary.
+/// This gets most inputs past the account checks into the handlers.
+///
+/// # Example
+/// ```ignore
+/// fuzz_target!(|input: &[u8]| {
+///     let mut u = Unstructured::new(input);
+///     let keys = [crate::ID, pinocchio_system::ID];
+///     let Ok(mut accounts) = FuzzAccounts::arbitrary(&mut u, &keys, &[]) else {
+///         return;
+///     };
+///     let _ = process_instruction(&crate::ID, &accounts.infos(), u.take_rest());
+/// });
+/// ```
+#[cfg(not(target_os = "solana"))]
+pub struct FuzzAccounts {
+    accounts: Vec<TestAccount>,
+}
+
+#[cfg(not(target_os = "solana"))]
+impl FuzzAccounts {
+    /// Build up to `MAX_FUZZ_ACCOUNTS` accounts from `u`
+    pub fn arbitrary(
+        u: &mut Unstructured<'_>,
+        keys: &[Pubkey],
+        known: &[KnownAccount<'_>],
+    ) -> arbitrary::Result<Self> {
+        let len = u.int_in_range(0..=MAX_FUZZ_ACCOUNTS)?;
+        let accounts = (0..len)
+            .map(|_| {
+                let (key, owner, data) = if !known.is_empty() && u.arbitrary()? {
+                    let account = u.choose(known)?;
+                    let len = if u.ratio(7, 8)? {
+                        account.data.len()
+                    } else {
+                        u.int_in_range(0..=account.data.len())?
+                    };
+                    (account.key, account.owner, account.data[..len].to_vec())
+                } else {
+                    let key = pick_key(u, keys)?;
+                    let owner = pick_key(u, keys)?;
+                    let data = if !known.is_empty() && u.arbitrary()? {
+                        u.choose(known)?.data.to_vec()
+                    } else {
+                        u.arbitrary()?
+                    };
+                    (key, owner, data)
+                };
+                Ok(TestAccount::with_flags(
+                    key,
+                    owner,
+                    u.arbitrary()?,
+                    u.arbitrary()?,
+                    u.arbitrary()?,
+                    &data,
+                ))
+            })
+            .collect::<arbitrary::Result<_>>()?;
+        Ok(Self { accounts })
+    }
+
+    /// `AccountInfo`s of the accounts, valid while `self` is alive
+    pub fn infos(&mut self) -> Vec<AccountInfo> {
+        self.accounts.iter_mut().map(TestAccount::info).collect()
+    }
+}
+
+/// One of `keys`, or now and then an arbitrary key
+#[cfg(not(target_os = "solana"))]
+fn pick_key(u: &mut Unstructured<'_>, keys: &[Pubkey]) -> arbitrary::Result<Pubkey> {
+    if !keys.is_empty() && u.ratio(7, 8)? {
+        u.choose(keys).copied()
+    } else {
+        u.arbitrary()
+    }
+}
+
+/// Deterministic input bytes for case `case` (xorshift64)
+fn case_input(case: u64) -> Vec<u8> {
+    let mut state = case.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    (0..INPUT_LEN)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+/// Check `pack` / `unpack` of `C` on generated values
+///
+/// For every value `x`, checks that `validate` accepts it, that
+/// `unpack(pack(x))` succeeds and packs back to the same bytes, and that
+/// `pack` writes every byte of `SIZE` (gaps and reserved space included).
+/// Also checks `LAYOUT` lies within `SIZE` and that `unpack` rejects data
+/// one byte short. Panics with the failing case.
+pub fn check_component_roundtrip<C>()
+where
+    C: Component + for<'a> Arbitrary<'a>,
+{
+    let name = core::any::type_name::<C>();
+    for field in C::LAYOUT {
+        assert!(
+            field.offset >= 8 && field.offset + field.size <= C::SIZE,
+            "{}: field `{}` at {}..{} is outside 8..{}",
+            name,
+            field.name,
+            field.offset,
+            field.offset + field.size,
+            C::SIZE
+        );
+    }
+
+    for case in 0..ROUNDTRIP_CASES {
+        let input = case_input(case);
+        let Ok(value) = C::arbitrary(&mut Unstructured::new(&input)) else {
+            continue;
+        };
+        assert!(
+            value.validate().is_ok(),
+            "{}: case {}: generated value fails validate",
+            name,
+            case
+        );
+
+        // Different fill bytes catch regions `pack` leaves untouched
+        let mut packed = vec![0xaa; C::SIZE];
+        value.pack(&mut packed);
+        let unpacked = C::unpack(&packed)
+            .unwrap_or_else(|| panic!("{}: case {}: unpack(pack(x)) failed", name, case));
+        let mut repacked = vec![0x55; C::SIZE];
+        unpacked.pack(&mut repacked);
+        assert!(
+            packed == repacked,
+            "{}: case {}: unpack(pack(x)) != x",
+            name,
+            case
+        );
+
+        assert!(
+            C::unpack(&packed[..C::SIZE - 1]).is_none(),
+            "{}: case {}: unpack accepted {} of {} bytes",
+            name,
+            case,
+            C::SIZE - 1,
+            C::SIZE
+        );
+    }
+}
+
+/// Check an instruction enum's `pack` / `unpack` on generated values
+///
+/// For every instruction `x`, checks that `unpack(pack(x))` succeeds and
+/// packs back to the same bytes, and that `unpack` rejects `pack(x)` cut
+/// short by one byte.
+pub fn check_instruction_roundtrip<I>(
+    pack: fn(&I) -> Vec<u8>,
+    unpack: fn(&[u8]) -> Result<I, ProgramError>,
+) where
+    I: for<'a> Arbitrary<'a>,
+{
+    let name = core::any::type_name::<I>();
+    for case in 0..ROUNDTRIP_CASES {
+        let input = case_input(case);
+        let Ok(instruction) = I::arbitrary(&mut Unstructured::new(&input)) else {
+            continue;
+        };
+
+        let packed = pack(&instruction);
+        let unpacked = unpack(&packed)
+            .unwrap_or_else(|_| panic!("{}: case {}: unpack(pack(x)) failed", name, case));
+        assert!(
+            packed == pack(&unpacked),
+            "{}: case {}: unpack(pack(x)) != x",
+            name,
+            case
+        );
+        assert!(
+            unpack(&packed[..packed.len() - 1]).is_err(),
+            "{}: case {}: unpack accepted a truncated instruction",
+            name,
+            case
+        );
+    }
+}
@@ -109,3 +109,499 @@ pub fn write_i64(data: &mut [u8], offset: usize, value: i64) {
 pub fn write_pubkey(data: &mut [u8], offset: usize, value: &[u8; 32]) {
     data[offset..offset + 32].copy_from_slice(value);
 }
+
+#[cfg(test)]
+mod tests {
+    use pinocchio::{
+        account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+    };
+
+    use crate::test_utils::TestAccount;
+    use crate::{
+        derive_allowlist_pda, derive_system_signer, program_ids, Allowlist, Component, GoltError,
+        ALLOWLIST_SIZE, ENTITY_DISCRIMINATOR, ENTITY_SIZE, REGISTRY_ENTITY_OWNER_OFFSET,
+    };
+
+    mod health {
+        use golt_macros::{component_instructions, Component};
+        use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+
+        use crate::Component;
+
+        pub const PROGRAM_ID: Pubkey = [5; 32];
+        pub const REGISTRY_ID: Pubkey = [1; 32];
+
+        #[derive(Component, Clone, Debug, PartialEq)]
+        #[component(seed = "health")]
+        pub struct Health {
+            #[entity]
+            pub entity: [u8; 32],
+            pub current: u32,
+            #[pda_bump]
+            pub bump: u8,
+        }
+
+        #[component_instructions(program_id = PROGRAM_ID, registry = REGISTRY_ID)]
+        impl Health {
+            #[instruction(tag = 0)]
+            pub fn create(entity: [u8; 32], current: u32) -> Self {
+                Self {
+                    entity,
+                    current,
+                    bump: 0,
+                }
+            }
+
+            #[instruction(tag = 1)]
+            pub fn damage(&mut self, amount: u32) {
+                self.current = self.current.saturating_sub(amount);
+            }
+
+            #[instruction(tag = 2)]
+            pub fn check(&self) -> Result<(), ProgramError> {
+                if self.current == 0 {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Only the generated enum and CPI helpers are used
+    #[allow(dead_code)]
+    mod spells {
+        use golt_macros::system_instructions;
+        use pinocchio::pubkey::Pubkey;
+
+        pub struct Spells;
+
+        #[system_instructions]
+        impl Spells {
+            /// 0. `[signer, writable]` Caster
+            /// 1. `[]` Entity
+            /// 2. `[writable]` Target
+            #[instruction(tag = 7)]
+            pub fn cast(spell: u8, power: u64) -> u64 {
+                spell as u64 * power
+            }
+
+            #[instruction(tag = 8)]
+            pub fn rest() {}
+
+            #[instruction(tag = 9)]
+            #[allow(clippy::too_many_arguments)]
+            pub fn enchant(
+                mana: u128,
+                drift: i128,
+                scale: f32,
+                chance: f64,
+                target: Pubkey,
+                runes: Vec<u8>,
+                name: String,
+                charges: u16,
+            ) {
+                let _ = (mana, drift, scale, chance, target, runes, name, charges);
+            }
+        }
+    }
+
+    // Without a registry: the enum, CPI helpers and `OwnedComponent`, no processor
+    mod mana {
+        use golt_macros::{component_instructions, Component};
+        use pinocchio::pubkey::Pubkey;
+
+        use crate::Component;
+
+        pub const PROGRAM_ID: Pubkey = [6; 32];
+
+        #[derive(Component, Clone, Debug, PartialEq)]
+        #[component(seed = "mana")]
+        pub struct Mana {
+            pub points: u16,
+        }
+
+        #[component_instructions(program_id = PROGRAM_ID)]
+        impl Mana {
+            /// 0. `[writable]` Mana
+            #[instruction(tag = 0)]
+            pub fn drain(&mut self, points: u16) {
+                self.points = self.points.saturating_sub(points);
+            }
+        }
+    }
+
+    fn accounts<const N: usize>(flags: [(bool, bool); N]) -> [TestAccount; N] {
+        let mut key = 10;
+        flags.map(|(is_signer, is_writable)| {
+            key += 1;
+            TestAccount::with_flags([key; 32], [0; 32], 1, is_signer, is_writable, &[])
+        })
+    }
+
+    /// `(key, is_signer, is_writable)` of each meta
+    fn flags(metas: &[pinocchio::instruction::AccountMeta]) -> Vec<(Pubkey, bool, bool)> {
+        metas
+            .iter()
+            .map(|meta| (*meta.pubkey, meta.is_signer, meta.is_writable))
+            .collect()
+    }
+
+    #[test]
+    fn component_cpi_uses_processor_accounts() {
+        use health::{health_cpi, HealthInstruction};
+
+        // Whatever flags the caller's accounts have, the metas follow the processor
+        let mut accounts = accounts([(false, false); 5]);
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        let keys: Vec<Pubkey> = infos.iter().map(|info| *info.key()).collect();
+
+        let create = [&infos[0], &infos[1], &infos[2], &infos[3], &infos[4]];
+        assert_eq!(
+            flags(&health_cpi::create_account_metas(&create)),
+            [
+                (keys[0], true, true),
+                (keys[1], true, false),
+                (keys[2], false, false),
+                (keys[3], false, true),
+                (keys[4], false, false),
+            ]
+        );
+        let damage = [&infos[0], &infos[1], &infos[2]];
+        assert_eq!(
+            flags(&health_cpi::damage_account_metas(&damage)),
+            [
+                (keys[0], false, true),
+                (keys[1], false, false),
+                (keys[2], true, false)
+            ]
+        );
+        assert_eq!(
+            flags(&health_cpi::check_account_metas(&[&infos[0]])),
+            [(keys[0], false, false)]
+        );
+
+        assert_eq!(health_cpi::damage(&damage, 3), Ok(()));
+        assert_eq!(
+            HealthInstruction::Damage { amount: 3 }.pack(),
+            [1, 3, 0, 0, 0]
+        );
+        assert_eq!(HealthInstruction::Check.pack(), [2]);
+        let create = HealthInstruction::Create {
+            entity: [4; 32],
+            current: 9,
+        };
+        assert_eq!(create.pack()[0], 0);
+        assert_eq!(create.pack().len(), 1 + 32 + 4);
+    }
+
+    #[test]
+    fn component_without_registry_has_owner_and_cpi() {
+        use mana::{mana_cpi, Mana, ManaInstruction};
+
+        assert_eq!(<Mana as crate::OwnedComponent>::OWNER, mana::PROGRAM_ID);
+
+        let mut accounts = accounts([(false, false)]);
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        assert_eq!(
+            flags(&mana_cpi::drain_account_metas(&[&infos[0]])),
+            [(*infos[0].key(), false, true)]
+        );
+        assert_eq!(mana_cpi::drain(&[&infos[0]], 4), Ok(()));
+        assert_eq!(ManaInstruction::Drain { points: 4 }.pack(), [0, 4, 0]);
+
+        // Its own processor would call the method
+        let mut mana = Mana { points: 5 };
+        mana.drain(4);
+        assert_eq!(mana.points, 1);
+    }
+
+    #[test]
+    fn system_cpi_uses_documented_accounts() {
+        use spells::{spells_cpi, SpellsInstruction};
+
+        let mut accounts = accounts([(true, false), (false, true), (false, false)]);
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        let keys: Vec<Pubkey> = infos.iter().map(|info| *info.key()).collect();
+        let refs = [&infos[0], &infos[1], &infos[2]];
+
+        // Documented: the doc flags, not the accounts'
+        assert_eq!(
+            flags(&spells_cpi::cast_account_metas(&refs)),
+            [
+                (keys[0], true, true),
+                (keys[1], false, false),
+                (keys[2], false, true)
+            ]
+        );
+        // Undocumented: the accounts' own flags
+        assert_eq!(
+            flags(&spells_cpi::rest_account_metas(&refs)),
+            [
+                (keys[0], true, false),
+                (keys[1], false, true),
+                (keys[2], false, false)
+            ]
+        );
+
+        assert_eq!(spells_cpi::cast(&[9; 32], &refs, 2, 500), Ok(()));
+        let mut cast = vec![7, 2];
+        cast.extend(500u64.to_le_bytes());
+        assert_eq!(
+            SpellsInstruction::Cast {
+                spell: 2,
+                power: 500
+            }
+            .pack(),
+            cast
+        );
+        assert_eq!(SpellsInstruction::Rest.pack(), [8]);
+        assert!(matches!(
+            SpellsInstruction::unpack(&cast),
+            Ok(SpellsInstruction::Cast {
+                spell: 2,
+                power: 500
+            })
+        ));
+    }
+
+    const OWNER: Pubkey = [2; 32];
+    const ENTITY: Pubkey = [3; 32];
+    const SYSTEM: Pubkey = [4; 32];
+
+    /// An active registry entity owned by `OWNER`
+    fn entity_account() -> TestAccount {
+        let mut data = [0; ENTITY_SIZE];
+        data[0..8].copy_from_slice(&ENTITY_DISCRIMINATOR);
+        data[REGISTRY_ENTITY_OWNER_OFFSET..REGISTRY_ENTITY_OWNER_OFFSET + 32]
+            .copy_from_slice(&OWNER);
+        data[48] = 1;
+        TestAccount::new(ENTITY, health::REGISTRY_ID, &data)
+    }
+
+    /// The program's allowlist, listing `SYSTEM`
+    fn allowlist_account() -> TestAccount {
+        let (key, bump) = derive_allowlist_pda(&health::PROGRAM_ID);
+        let mut allowlist = Allowlist::new(OWNER, bump);
+        allowlist.add(&SYSTEM).unwrap();
+        let mut data = vec![0; ALLOWLIST_SIZE];
+        allowlist.pack(&mut data);
+        TestAccount::new(key, health::PROGRAM_ID, &data)
+    }
+
+    fn health_account(key: Pubkey, current: u32) -> TestAccount {
+        let health = health::Health {
+            entity: ENTITY,
+            current,
+            bump: 0,
+        };
+        let mut data = [0; health::Health::SIZE];
+        health.pack(&mut data);
+        TestAccount::new(key, health::PROGRAM_ID, &data)
+    }
+
+    /// Run `data` against `accounts`, returning the result and the data of `accounts[index]`
+    fn process(
+        accounts: &mut [TestAccount],
+        data: &[u8],
+        index: usize,
+    ) -> (ProgramResult, Vec<u8>) {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        let result = health::process_instruction(&health::PROGRAM_ID, &infos, data);
+        let stored = infos[index].try_borrow_data().unwrap().to_vec();
+        (result, stored)
+    }
+
+    #[test]
+    fn processor_dispatches_constructor() {
+        use health::{Health, HealthInstruction};
+
+        let (pda, bump) = Health::derive_pda(&[&ENTITY], &health::PROGRAM_ID);
+        let create = |key: Pubkey, entity: Pubkey| {
+            // The system program is a no-op off-chain, so the account is pre-sized
+            let mut accounts = [
+                TestAccount::new([1; 32], [0; 32], &[]).signer(),
+                TestAccount::with_flags(OWNER, [0; 32], 1, true, false, &[]),
+                entity_account(),
+                TestAccount::new(key, health::PROGRAM_ID, &[0; Health::SIZE]),
+                TestAccount::with_flags(program_ids::SYSTEM, [0; 32], 1, false, false, &[]),
+            ];
+            let data = HealthInstruction::Create {
+                entity,
+                current: 30,
+            }
+            .pack();
+            process(&mut accounts, &data, 3)
+        };
+
+        let (result, stored) = create(pda, ENTITY);
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            Health::unpack(&stored),
+            Some(Health {
+                entity: ENTITY,
+                current: 30,
+                bump,
+            })
+        );
+
+        assert_eq!(create([8; 32], ENTITY).0, Err(GoltError::InvalidPda.into()));
+        // The constructor must store the entity whose PDA it was created at
+        assert_eq!(
+            create(pda, [9; 32]).0,
+            Err(GoltError::EntityMismatch.into())
+        );
+    }
+
+    #[test]
+    fn processor_dispatches_methods() {
+        use health::HealthInstruction;
+
+        let component = [6; 32];
+        let damage = HealthInstruction::Damage { amount: 12 }.pack();
+        let damage_as = |system: &Pubkey| {
+            let (signer, _) = derive_system_signer(system);
+            let mut accounts = [
+                health_account(component, 30),
+                allowlist_account(),
+                TestAccount::with_flags(signer, [0; 32], 1, true, false, &[]),
+            ];
+            process(&mut accounts, &damage, 0)
+        };
+
+        // `&mut self`: changes are saved
+        let (result, stored) = damage_as(&SYSTEM);
+        assert_eq!(result, Ok(()));
+        assert_eq!(health::Health::unpack(&stored).unwrap().current, 18);
+
+        let (result, stored) = damage_as(&[7; 32]);
+        assert_eq!(result, Err(GoltError::SystemNotAllowed.into()));
+        assert_eq!(health::Health::unpack(&stored).unwrap().current, 30);
+
+        // `&self`: the method's error is returned
+        let check = HealthInstruction::Check.pack();
+        assert_eq!(
+            process(&mut [health_account(component, 5)], &check, 0).0,
+            Ok(())
+        );
+        assert_eq!(
+            process(&mut [health_account(component, 0)], &check, 0).0,
+            Err(ProgramError::InvalidArgument)
+        );
+
+        assert_eq!(
+            process(&mut [health_account(component, 5)], &[3], 0).0,
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    fn enchant() -> spells::SpellsInstruction {
+        spells::SpellsInstruction::Enchant {
+            mana: u128::MAX - 1,
+            drift: -5,
+            scale: 1.5,
+            chance: 0.25,
+            target: [7; 32],
+            runes: vec![1, 2, 3],
+            name: "fïre".to_string(),
+            charges: 513,
+        }
+    }
+
+    #[test]
+    fn params_pack_unpack() {
+        use spells::SpellsInstruction;
+
+        let mut expected = vec![9];
+        expected.extend((u128::MAX - 1).to_le_bytes());
+        expected.extend((-5i128).to_le_bytes());
+        expected.extend(1.5f32.to_le_bytes());
+        expected.extend(0.25f64.to_le_bytes());
+        expected.extend([7; 32]);
+        expected.extend(3u32.to_le_bytes());
+        expected.extend([1, 2, 3]);
+        expected.extend(5u32.to_le_bytes());
+        expected.extend("fïre".as_bytes());
+        expected.extend(513u16.to_le_bytes());
+
+        let data = enchant().pack();
+        assert_eq!(data, expected);
+        match SpellsInstruction::unpack(&data) {
+            Ok(SpellsInstruction::Enchant {
+                mana,
+                drift,
+                scale,
+                chance,
+                target,
+                runes,
+                name,
+                charges,
+            }) => {
+                assert_eq!(mana, u128::MAX - 1);
+                assert_eq!(drift, -5);
+                assert_eq!(scale, 1.5);
+                assert_eq!(chance, 0.25);
+                assert_eq!(target, [7; 32]);
+                assert_eq!(runes, [1, 2, 3]);
+                assert_eq!(name, "fïre");
+                assert_eq!(charges, 513);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+
+        // Empty variable-length params still take their length prefix
+        let empty = SpellsInstruction::Enchant {
+            mana: 0,
+            drift: 0,
+            scale: 0.0,
+            chance: 0.0,
+            target: [0; 32],
+            runes: Vec::new(),
+            name: String::new(),
+            charges: 0,
+        };
+        let min_len = 1 + 16 + 16 + 4 + 8 + 32 + 4 + 4 + 2;
+        assert_eq!(empty.pack().len(), min_len);
+        assert!(SpellsInstruction::unpack(&empty.pack()).is_ok());
+    }
+
+    #[test]
+    fn params_unpack_rejects_bad_data() {
+        use spells::SpellsInstruction;
+
+        let data = enchant().pack();
+        // Every truncation fails, whether below the minimum size or inside a
+        // variable-length param
+        for len in 0..data.len() {
+            assert!(
+                matches!(
+                    SpellsInstruction::unpack(&data[..len]),
+                    Err(ProgramError::InvalidInstructionData)
+                ),
+                "accepted {len} bytes"
+            );
+        }
+
+        // A length prefix running past the params after it
+        let runes_len = 1 + 16 + 16 + 4 + 8 + 32;
+        let mut long = data.clone();
+        long[runes_len..runes_len + 4].copy_from_slice(&9u32.to_le_bytes());
+        assert!(matches!(
+            SpellsInstruction::unpack(&long),
+            Err(ProgramError::InvalidInstructionData)
+        ));
+        long[runes_len..runes_len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            SpellsInstruction::unpack(&long),
+            Err(ProgramError::InvalidInstructionData)
+        ));
+
+        // A `String` must be UTF-8
+        let name = runes_len + 4 + 3 + 4;
+        let mut invalid = data.clone();
+        invalid[name] = 0xff;
+        assert!(matches!(
+            SpellsInstruction::unpack(&invalid),
+            Err(ProgramError::InvalidInstructionData)
+        ));
+    }
+}
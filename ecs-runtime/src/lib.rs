@@ -6,30 +6,82 @@
 //! # Features
 //!
 //! - **Component trait**: Define ECS components with pack/unpack
+//! - **Nested fields**: Structs and enums packed inside components
 //! - **Delegation**: Delegate accounts to MagicBlock Ephemeral Rollups
 //! - **Account helpers**: Type-safe account validation
+//! - **System allowlists**: Restrict component writes to approved systems
 //! - **PDA utilities**: Derive and verify PDAs
+//! - **Zero-copy views**: Read and write component fields in place
+//! - **Queries**: Iterate components of many entities from remaining accounts
+//! - **Systems**: Validate and write back the components of `#[system]` functions
+//! - **Events**: Emit structured, packed events to the program log
+//! - **Error codes**: Non-overlapping error ranges with names and messages
+//! - **Math**: Checked arithmetic and deterministic Q32.32 fixed-point numbers
+//! - **Fuzzing**: `Arbitrary` impls and round-trip tests (`arbitrary` feature)
+
+// Lets `#[derive(ErrorCode)]` output (`golt_runtime::...`) resolve inside this crate
+extern crate self as golt_runtime;
 
 pub use pinocchio;
 pub use pinocchio_pubkey;
 pub use pinocchio_system;
 pub use ephemeral_rollups_pinocchio;
+#[cfg(feature = "arbitrary")]
+pub use arbitrary;
 
 pub mod account;
+pub mod authority;
 pub mod component;
 pub mod delegation;
 pub mod entity;
 pub mod error;
+pub mod event;
+pub mod field;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
 pub mod instruction;
+pub mod math;
 pub mod pda;
+pub mod query;
+pub mod system;
+pub mod view;
+
+// In-memory accounts, PDAs and sysvars for host tests and `fuzz`
+#[cfg(any(test, all(feature = "arbitrary", not(target_os = "solana"))))]
+#[cfg_attr(not(test), allow(dead_code))]
+mod test_utils;
 
 pub use account::*;
+pub use authority::*;
 pub use component::*;
 pub use delegation::*;
 pub use entity::*;
 pub use error::*;
+pub use event::*;
+pub use field::*;
 pub use instruction::*;
+pub use math::*;
 pub use pda::*;
+pub use query::*;
+pub use system::*;
+pub use view::*;
+
+/// Expands to the given items only with the `arbitrary` feature
+///
+/// Used by the derive macros, so crates using them need no feature of their own.
+#[doc(hidden)]
+#[cfg(feature = "arbitrary")]
+#[macro_export]
+macro_rules! __arbitrary {
+    ($($item:item)*) => { $($item)* };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "arbitrary"))]
+#[macro_export]
+macro_rules! __arbitrary {
+    ($($item:item)*) => {};
+}
 
 /// Re-export common pinocchio types
 pub mod prelude {
@@ -52,10 +104,17 @@ pub mod prelude {
     };
 
     pub use crate::account::*;
+    pub use crate::authority::*;
     pub use crate::component::*;
     pub use crate::delegation::*;
     pub use crate::entity::*;
     pub use crate::error::*;
+    pub use crate::event::*;
+    pub use crate::field::*;
     pub use crate::instruction::*;
+    pub use crate::math::*;
     pub use crate::pda::*;
+    pub use crate::query::*;
+    pub use crate::system::*;
+    pub use crate::view::*;
 }
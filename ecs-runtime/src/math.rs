@@ -0,0 +1,809 @@
+//! Checked arithmetic and deterministic fixed-point math
+//!
+//! `CheckedMath` turns integer overflow into `GoltError::Overflow` /
+//! `Underflow` so game logic can use `?` instead of `checked_*().ok_or(..)`:
+//!
+//! ```ignore
+//! health.current = health.current.try_sub(damage)?;
+//! gold.try_add_assign(reward)?;
+//! ```
+//!
+//! `f32`/`f64` are emulated in software on SBF, and reproducing their exact
+//! rounding in a client is fragile. `Fixed` is a Q32.32 number stored in an
+//! `i64`: everything, including `sqrt` and the trig functions, is integer
+//! arithmetic, so any client computes bit-identical results.
+//!
+//! ```ignore
+//! let speed = Fixed::from_ratio(3, 2).unwrap(); // 1.5
+//! let dx = speed * angle.cos();
+//! let dist = (dx * dx + dy * dy).sqrt();
+//! ```
+
+use core::fmt;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::GoltError;
+
+/// Arithmetic that reports overflow as a `GoltError`
+pub trait CheckedMath: Sized + Copy {
+    /// `self + rhs`
+    fn try_add(self, rhs: Self) -> Result<Self, GoltError>;
+
+    /// `self - rhs`
+    fn try_sub(self, rhs: Self) -> Result<Self, GoltError>;
+
+    /// `self * rhs`
+    fn try_mul(self, rhs: Self) -> Result<Self, GoltError>;
+
+    /// `self / rhs`, failing with `DivisionByZero` if `rhs` is zero
+    fn try_div(self, rhs: Self) -> Result<Self, GoltError>;
+
+    /// `*self += rhs`
+    fn try_add_assign(&mut self, rhs: Self) -> Result<(), GoltError> {
+        *self = self.try_add(rhs)?;
+        Ok(())
+    }
+
+    /// `*self -= rhs`
+    fn try_sub_assign(&mut self, rhs: Self) -> Result<(), GoltError> {
+        *self = self.try_sub(rhs)?;
+        Ok(())
+    }
+
+    /// `*self *= rhs`
+    fn try_mul_assign(&mut self, rhs: Self) -> Result<(), GoltError> {
+        *self = self.try_mul(rhs)?;
+        Ok(())
+    }
+
+    /// `*self /= rhs`
+    fn try_div_assign(&mut self, rhs: Self) -> Result<(), GoltError> {
+        *self = self.try_div(rhs)?;
+        Ok(())
+    }
+}
+
+/// `Underflow` if the out-of-range result is negative, `Overflow` otherwise
+#[inline]
+fn range_error(negative: bool) -> GoltError {
+    if negative {
+        GoltError::Underflow
+    } else {
+        GoltError::Overflow
+    }
+}
+
+macro_rules! impl_checked_unsigned {
+    ($($t:ty),*) => {$(
+        impl CheckedMath for $t {
+            #[inline]
+            fn try_add(self, rhs: Self) -> Result<Self, GoltError> {
+                self.checked_add(rhs).ok_or(GoltError::Overflow)
+            }
+
+            #[inline]
+            fn try_sub(self, rhs: Self) -> Result<Self, GoltError> {
+                self.checked_sub(rhs).ok_or(GoltError::Underflow)
+            }
+
+            #[inline]
+            fn try_mul(self, rhs: Self) -> Result<Self, GoltError> {
+                self.checked_mul(rhs).ok_or(GoltError::Overflow)
+            }
+
+            #[inline]
+            fn try_div(self, rhs: Self) -> Result<Self, GoltError> {
+                self.checked_div(rhs).ok_or(GoltError::DivisionByZero)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_checked_signed {
+    ($($t:ty),*) => {$(
+        impl CheckedMath for $t {
+            #[inline]
+            fn try_add(self, rhs: Self) -> Result<Self, GoltError> {
+                self.checked_add(rhs).ok_or_else(|| range_error(rhs < 0))
+            }
+
+            #[inline]
+            fn try_sub(self, rhs: Self) -> Result<Self, GoltError> {
+                self.checked_sub(rhs).ok_or_else(|| range_error(rhs > 0))
+            }
+
+            #[inline]
+            fn try_mul(self, rhs: Self) -> Result<Self, GoltError> {
+                self.checked_mul(rhs).ok_or_else(|| range_error((self < 0) != (rhs < 0)))
+            }
+
+            #[inline]
+            fn try_div(self, rhs: Self) -> Result<Self, GoltError> {
+                if rhs == 0 {
+                    return Err(GoltError::DivisionByZero);
+                }
+                // Only MIN / -1 overflows
+                self.checked_div(rhs).ok_or(GoltError::Overflow)
+            }
+        }
+    )*};
+}
+
+impl_checked_unsigned!(u8, u16, u32, u64, u128);
+impl_checked_signed!(i8, i16, i32, i64, i128);
+
+/// Raw value of 1.0
+const ONE_RAW: i128 = 1 << 32;
+
+/// Q32.32 multiply of raw values
+#[inline]
+fn mul_raw(a: i128, b: i128) -> i128 {
+    (a * b) >> 32
+}
+
+/// Integer square root (floor)
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    // Start above the root and walk down (Newton's method)
+    let bits = 128 - n.leading_zeros();
+    let mut x = 1u128 << bits.div_ceil(2);
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// Deterministic Q32.32 fixed-point number
+///
+/// 32 integer bits (signed) and 32 fractional bits, stored as an `i64`.
+/// The range is about ±2.1e9 with a resolution of 2^-32 (~2.3e-10).
+/// Stored little-endian in 8 bytes in components and instruction data.
+///
+/// `+`, `-`, `*`, `/` behave like integer operators and panic on overflow;
+/// use the `checked_*` methods or `CheckedMath` to handle it instead. Unary
+/// `-` and `abs` always panic on `MIN`, whose negation is out of range; use
+/// `checked_neg` / `checked_abs` there.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    /// Number of fractional bits
+    pub const FRAC_BITS: u32 = 32;
+    /// 0
+    pub const ZERO: Self = Self(0);
+    /// 1
+    pub const ONE: Self = Self(1 << 32);
+    /// 0.5
+    pub const HALF: Self = Self(1 << 31);
+    /// Smallest positive value (2^-32)
+    pub const EPSILON: Self = Self(1);
+    /// Smallest value
+    pub const MIN: Self = Self(i64::MIN);
+    /// Largest value
+    pub const MAX: Self = Self(i64::MAX);
+    /// π
+    pub const PI: Self = Self(13_493_037_705);
+    /// π / 2
+    pub const HALF_PI: Self = Self(6_746_518_852);
+    /// 2π
+    pub const TWO_PI: Self = Self(26_986_075_409);
+    /// e
+    pub const E: Self = Self(11_674_931_555);
+
+    /// From the raw Q32.32 representation
+    #[inline]
+    pub const fn from_bits(bits: i64) -> Self {
+        Self(bits)
+    }
+
+    /// The raw Q32.32 representation
+    #[inline]
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    /// From an integer
+    #[inline]
+    pub const fn from_int(value: i32) -> Self {
+        Self((value as i64) << 32)
+    }
+
+    /// `numerator / denominator`, or `None` if it is out of range or the
+    /// denominator is zero
+    pub fn from_ratio(numerator: i64, denominator: i64) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let raw = ((numerator as i128) << 32) / denominator as i128;
+        i64::try_from(raw).ok().map(Self)
+    }
+
+    /// Nearest value to `value`; for tests and off-chain use, not for
+    /// deterministic on-chain math
+    pub fn from_f64(value: f64) -> Self {
+        Self((value * ONE_RAW as f64).round() as i64)
+    }
+
+    /// Approximate `f64` value
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / ONE_RAW as f64
+    }
+
+    /// Largest integer less than or equal to `self`
+    #[inline]
+    pub const fn to_int(self) -> i64 {
+        self.0 >> 32
+    }
+
+    /// Nearest integer, rounding halves up
+    #[inline]
+    pub const fn round(self) -> i64 {
+        ((self.0 as i128 + (1 << 31)) >> 32) as i64
+    }
+
+    /// Largest integral value less than or equal to `self`
+    #[inline]
+    pub const fn floor(self) -> Self {
+        Self(self.0 & !0xFFFF_FFFF)
+    }
+
+    /// Fractional part, always in `[0, 1)`
+    #[inline]
+    pub const fn frac(self) -> Self {
+        Self(self.0 & 0xFFFF_FFFF)
+    }
+
+    /// Absolute value
+    ///
+    /// # Panics
+    ///
+    /// If `self` is `MIN`.
+    #[inline]
+    pub const fn abs(self) -> Self {
+        match self.checked_abs() {
+            Some(abs) => abs,
+            None => panic!("absolute value of Fixed::MIN"),
+        }
+    }
+
+    /// Absolute value, or `None` if `self` is `MIN`
+    #[inline]
+    pub const fn checked_abs(self) -> Option<Self> {
+        match self.0.checked_abs() {
+            Some(raw) => Some(Self(raw)),
+            None => None,
+        }
+    }
+
+    /// `-self`, or `None` if `self` is `MIN`
+    #[inline]
+    pub const fn checked_neg(self) -> Option<Self> {
+        match self.0.checked_neg() {
+            Some(raw) => Some(Self(raw)),
+            None => None,
+        }
+    }
+
+    /// Whether `self` is below zero
+    #[inline]
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Little-endian bytes, as stored in accounts
+    #[inline]
+    pub const fn to_le_bytes(self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+
+    /// From little-endian bytes
+    #[inline]
+    pub const fn from_le_bytes(bytes: [u8; 8]) -> Self {
+        Self(i64::from_le_bytes(bytes))
+    }
+
+    /// `self + rhs`, or `None` on overflow
+    #[inline]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    /// `self - rhs`, or `None` on overflow
+    #[inline]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    /// `self * rhs`, or `None` on overflow
+    #[inline]
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        i64::try_from(mul_raw(self.0 as i128, rhs.0 as i128)).ok().map(Self)
+    }
+
+    /// `self / rhs`, or `None` on overflow or division by zero
+    #[inline]
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.0 == 0 {
+            return None;
+        }
+        i64::try_from(((self.0 as i128) << 32) / rhs.0 as i128).ok().map(Self)
+    }
+
+    /// `self + rhs`, clamped to `MIN..=MAX`
+    #[inline]
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
+    /// `self - rhs`, clamped to `MIN..=MAX`
+    #[inline]
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+
+    /// `self * rhs`, clamped to `MIN..=MAX`
+    #[inline]
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        let raw = mul_raw(self.0 as i128, rhs.0 as i128);
+        Self(raw.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+
+    /// Square root, or `None` if `self` is negative
+    pub fn checked_sqrt(self) -> Option<Self> {
+        if self.0 < 0 {
+            return None;
+        }
+        // sqrt(raw / 2^32) * 2^32 == sqrt(raw * 2^32)
+        Some(Self(isqrt((self.0 as u128) << 32) as i64))
+    }
+
+    /// Square root
+    ///
+    /// # Panics
+    ///
+    /// If `self` is negative.
+    pub fn sqrt(self) -> Self {
+        self.checked_sqrt().expect("square root of a negative Fixed")
+    }
+
+    /// Sine of `self` radians, in `[-1, 1]`
+    ///
+    /// The error is below 1e-7 for angles within ±10,000; beyond that the
+    /// rounding of 2π adds up.
+    pub fn sin(self) -> Self {
+        Self(sin_raw(self.0 as i128) as i64)
+    }
+
+    /// Cosine of `self` radians, in `[-1, 1]`, as accurate as `sin`
+    pub fn cos(self) -> Self {
+        Self(sin_raw(self.0 as i128 + Self::HALF_PI.0 as i128) as i64)
+    }
+
+    /// Tangent of `self` radians, or `None` where it is out of range
+    pub fn checked_tan(self) -> Option<Self> {
+        self.sin().checked_div(self.cos())
+    }
+
+    /// Arctangent in radians, in `[-π/2, π/2]` (error below 1e-7)
+    pub fn atan(self) -> Self {
+        Self(atan_raw(self.0 as i128) as i64)
+    }
+
+    /// Angle of the point `(x, y)` in radians, in `[-π, π]`; `0` for the origin
+    pub fn atan2(y: Self, x: Self) -> Self {
+        if x.0 == 0 {
+            return match y.0.signum() {
+                1 => Self::HALF_PI,
+                -1 => -Self::HALF_PI,
+                _ => Self::ZERO,
+            };
+        }
+        let angle = atan_raw(((y.0 as i128) << 32) / x.0 as i128);
+        let angle = match (x.0 > 0, y.0 >= 0) {
+            (true, _) => angle,
+            (false, true) => angle + Self::PI.0 as i128,
+            (false, false) => angle - Self::PI.0 as i128,
+        };
+        Self(angle as i64)
+    }
+}
+
+/// Sine of a raw angle, result in raw units
+fn sin_raw(angle: i128) -> i128 {
+    let pi = Fixed::PI.0 as i128;
+    let half_pi = Fixed::HALF_PI.0 as i128;
+    let two_pi = Fixed::TWO_PI.0 as i128;
+
+    // Reduce to (-π, π], then fold into [-π/2, π/2]
+    let mut x = angle.rem_euclid(two_pi);
+    if x > pi {
+        x -= two_pi;
+    }
+    if x > half_pi {
+        x = pi - x;
+    } else if x < -half_pi {
+        x = -pi - x;
+    }
+
+    // Taylor series to x^13, in Horner form:
+    // x (1 - x²/(2·3) (1 - x²/(4·5) (1 - ... (1 - x²/(12·13)))))
+    let x2 = mul_raw(x, x);
+    let mut acc = ONE_RAW;
+    for divisor in [156, 110, 72, 42, 20, 6] {
+        acc = ONE_RAW - mul_raw(x2, acc) / divisor;
+    }
+    // Rounding can overshoot ±1 by a few ulps near ±π/2
+    mul_raw(x, acc).clamp(-ONE_RAW, ONE_RAW)
+}
+
+/// Arctangent of a raw value, result in raw units
+fn atan_raw(value: i128) -> i128 {
+    let negative = value < 0;
+    let mut x = value.abs();
+
+    // atan(x) = π/2 - atan(1/x) for x > 1
+    let invert = x > ONE_RAW;
+    if invert {
+        x = (ONE_RAW << 32) / x;
+    }
+
+    // atan(x) = 2 atan(x / (1 + sqrt(1 + x²))) brings x below tan(π/8)
+    let root = isqrt(((ONE_RAW + mul_raw(x, x)) as u128) << 32) as i128;
+    let t = (x << 32) / (ONE_RAW + root);
+
+    // Taylor series to t^15: t (1 - t²/3 + t⁴/5 - ... - t^14/15)
+    let t2 = mul_raw(t, t);
+    let mut acc = ONE_RAW / 15;
+    for k in (0..7).rev() {
+        acc = ONE_RAW / (2 * k + 1) - mul_raw(t2, acc);
+    }
+    let mut angle = 2 * mul_raw(t, acc);
+
+    if invert {
+        angle = Fixed::HALF_PI.0 as i128 - angle;
+    }
+    if negative {
+        -angle
+    } else {
+        angle
+    }
+}
+
+impl From<i32> for Fixed {
+    fn from(value: i32) -> Self {
+        Self::from_int(value)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Fixed {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::from_bits(u.arbitrary()?))
+    }
+}
+
+impl CheckedMath for Fixed {
+    fn try_add(self, rhs: Self) -> Result<Self, GoltError> {
+        self.checked_add(rhs).ok_or_else(|| range_error(rhs.0 < 0))
+    }
+
+    fn try_sub(self, rhs: Self) -> Result<Self, GoltError> {
+        self.checked_sub(rhs).ok_or_else(|| range_error(rhs.0 > 0))
+    }
+
+    fn try_mul(self, rhs: Self) -> Result<Self, GoltError> {
+        self.checked_mul(rhs)
+            .ok_or_else(|| range_error((self.0 < 0) != (rhs.0 < 0)))
+    }
+
+    fn try_div(self, rhs: Self) -> Result<Self, GoltError> {
+        if rhs.0 == 0 {
+            return Err(GoltError::DivisionByZero);
+        }
+        self.checked_div(rhs)
+            .ok_or_else(|| range_error((self.0 < 0) != (rhs.0 < 0)))
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs).expect("Fixed multiplication overflow")
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: Self) -> Self {
+        self.checked_div(rhs).expect("Fixed division overflow or by zero")
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        self.checked_neg().expect("Fixed negation overflow")
+    }
+}
+
+impl AddAssign for Fixed {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Fixed {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Fixed {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign for Fixed {
+    #[inline]
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl fmt::Debug for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fixed({})", self.to_f64())
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Largest difference from the `f64` result the trig functions allow
+    const TRIG_TOLERANCE: f64 = 1e-7;
+
+    /// Angles from -20 to 20 radians, plus the ends of the accurate range
+    fn angles() -> impl Iterator<Item = Fixed> {
+        (-2000..=2000)
+            .map(|i| Fixed::from_ratio(i, 100).unwrap())
+            .chain([Fixed::EPSILON, -Fixed::EPSILON])
+            .chain([Fixed::from_int(10_000), Fixed::from_int(-10_000)])
+    }
+
+    /// Values at the ends of the `Fixed` range
+    const EXTREMES: [Fixed; 4] = [
+        Fixed::MIN,
+        Fixed::MAX,
+        Fixed::from_bits(i64::MIN + 1),
+        Fixed::from_bits(i64::MAX - 1),
+    ];
+
+    #[test]
+    fn unsigned_errors() {
+        assert_eq!(u8::MAX.try_add(1), Err(GoltError::Overflow));
+        assert_eq!(0u8.try_sub(1), Err(GoltError::Underflow));
+        assert_eq!(u8::MAX.try_mul(2), Err(GoltError::Overflow));
+        assert_eq!(1u64.try_div(0), Err(GoltError::DivisionByZero));
+        assert_eq!(7u32.try_div(2), Ok(3));
+
+        let mut value = 250u8;
+        value.try_add_assign(5).unwrap();
+        assert_eq!(value, 255);
+        assert_eq!(value.try_add_assign(1), Err(GoltError::Overflow));
+        assert_eq!(value, 255);
+    }
+
+    #[test]
+    fn signed_errors() {
+        assert_eq!(i8::MAX.try_add(1), Err(GoltError::Overflow));
+        assert_eq!(i8::MIN.try_add(-1), Err(GoltError::Underflow));
+        assert_eq!(i8::MIN.try_sub(1), Err(GoltError::Underflow));
+        assert_eq!(i8::MAX.try_sub(-1), Err(GoltError::Overflow));
+        assert_eq!(100i8.try_mul(2), Err(GoltError::Overflow));
+        assert_eq!((-100i8).try_mul(2), Err(GoltError::Underflow));
+        assert_eq!((-100i8).try_mul(-2), Err(GoltError::Overflow));
+        assert_eq!(i8::MIN.try_div(-1), Err(GoltError::Overflow));
+        assert_eq!(1i64.try_div(0), Err(GoltError::DivisionByZero));
+        assert_eq!((-7i32).try_div(2), Ok(-3));
+    }
+
+    #[test]
+    fn fixed_errors() {
+        let two = Fixed::from_int(2);
+        assert_eq!(Fixed::MAX.try_add(Fixed::EPSILON), Err(GoltError::Overflow));
+        assert_eq!(Fixed::MIN.try_add(-Fixed::EPSILON), Err(GoltError::Underflow));
+        assert_eq!(Fixed::MIN.try_sub(Fixed::EPSILON), Err(GoltError::Underflow));
+        assert_eq!(Fixed::MAX.try_sub(-Fixed::EPSILON), Err(GoltError::Overflow));
+        assert_eq!(Fixed::MAX.try_mul(two), Err(GoltError::Overflow));
+        assert_eq!(Fixed::MAX.try_mul(-two), Err(GoltError::Underflow));
+        assert_eq!(Fixed::MIN.try_mul(-two), Err(GoltError::Overflow));
+        assert_eq!(Fixed::MAX.try_div(Fixed::HALF), Err(GoltError::Overflow));
+        assert_eq!(Fixed::MAX.try_div(-Fixed::HALF), Err(GoltError::Underflow));
+        assert_eq!(Fixed::ONE.try_div(Fixed::ZERO), Err(GoltError::DivisionByZero));
+        assert_eq!(Fixed::ONE.try_add(Fixed::ONE), Ok(two));
+
+        assert_eq!(Fixed::from_ratio(1, 0), None);
+        assert_eq!(Fixed::from_ratio(i64::MAX, 1), None);
+        assert_eq!(Fixed::MAX.saturating_mul(two), Fixed::MAX);
+        assert_eq!(Fixed::MAX.saturating_mul(-two), Fixed::MIN);
+    }
+
+    #[test]
+    fn fixed_abs_and_neg() {
+        let x = Fixed::from_ratio(-3, 2).unwrap();
+        assert_eq!(x.abs(), Fixed::from_ratio(3, 2).unwrap());
+        assert_eq!(-x, x.abs());
+        assert_eq!(Fixed::MAX.checked_neg(), Some(Fixed::from_bits(-i64::MAX)));
+        assert_eq!(Fixed::MIN.checked_abs(), None);
+        assert_eq!(Fixed::MIN.checked_neg(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "absolute value of Fixed::MIN")]
+    fn fixed_abs_min_panics() {
+        let _ = Fixed::MIN.abs();
+    }
+
+    #[test]
+    #[should_panic(expected = "Fixed negation overflow")]
+    fn fixed_neg_min_panics() {
+        let _ = -Fixed::MIN;
+    }
+
+    #[test]
+    fn mul_rounds_down() {
+        let third = Fixed::from_ratio(1, 3).unwrap();
+        assert_eq!(Fixed::from_int(3) * Fixed::HALF, Fixed::from_ratio(3, 2).unwrap());
+        // Products round toward negative infinity
+        assert_eq!(Fixed::EPSILON * Fixed::HALF, Fixed::ZERO);
+        assert_eq!(-Fixed::EPSILON * Fixed::HALF, -Fixed::EPSILON);
+        assert_eq!((third * Fixed::from_int(3)).to_bits(), Fixed::ONE.to_bits() - 1);
+    }
+
+    #[test]
+    fn div_rounds_toward_zero() {
+        let three = Fixed::from_int(3);
+        assert_eq!((Fixed::ONE / three).to_bits(), 1_431_655_765);
+        assert_eq!((-Fixed::ONE / three).to_bits(), -1_431_655_765);
+        assert_eq!(Fixed::from_int(6) / three, Fixed::from_int(2));
+        assert_eq!(Fixed::from_ratio(-1, 3), Some(-Fixed::ONE / three));
+    }
+
+    #[test]
+    fn sqrt_is_floor() {
+        assert_eq!(Fixed::from_int(4).sqrt(), Fixed::from_int(2));
+        assert_eq!(Fixed::ZERO.sqrt(), Fixed::ZERO);
+        assert_eq!(Fixed::EPSILON.sqrt(), Fixed::from_bits(1 << 16));
+        assert_eq!((-Fixed::EPSILON).checked_sqrt(), None);
+
+        for x in [Fixed::from_int(2), Fixed::from_ratio(1, 3).unwrap(), Fixed::PI, Fixed::MAX] {
+            // root² <= x < (root + ε)², in raw units scaled by 2^32
+            let root = x.sqrt().to_bits() as i128;
+            let scaled = (x.to_bits() as i128) << 32;
+            assert!(root * root <= scaled, "sqrt({:?}) too large", x);
+            assert!((root + 1) * (root + 1) > scaled, "sqrt({:?}) too small", x);
+        }
+    }
+
+    #[test]
+    fn sin_cos_bounds() {
+        assert_eq!(Fixed::ZERO.sin(), Fixed::ZERO);
+        assert_eq!(Fixed::HALF_PI.sin(), Fixed::ONE);
+        assert_eq!((-Fixed::HALF_PI).sin(), -Fixed::ONE);
+        // Near the peaks, where rounding could overshoot
+        let peaks = (-100_000..=100_000).flat_map(|i| {
+            [Fixed::HALF_PI, -Fixed::HALF_PI].map(|peak| peak + Fixed::from_bits(i))
+        });
+        for x in peaks.chain(EXTREMES) {
+            for value in [x.sin(), x.cos()] {
+                assert!(value >= -Fixed::ONE && value <= Fixed::ONE, "{:?} out of [-1, 1]", value);
+            }
+        }
+        for x in angles() {
+            for (value, expected) in [(x.sin(), x.to_f64().sin()), (x.cos(), x.to_f64().cos())] {
+                assert!(value >= -Fixed::ONE && value <= Fixed::ONE, "{:?} out of [-1, 1]", value);
+                assert!(
+                    (value.to_f64() - expected).abs() < TRIG_TOLERANCE,
+                    "sin/cos({:?}) = {:?}, expected {}",
+                    x,
+                    value,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn atan_bounds() {
+        assert_eq!(Fixed::ZERO.atan(), Fixed::ZERO);
+        for x in angles().chain(EXTREMES) {
+            let value = x.atan();
+            assert!(
+                value >= -Fixed::HALF_PI && value <= Fixed::HALF_PI,
+                "atan({:?}) = {:?}",
+                x,
+                value
+            );
+            assert!((value.to_f64() - x.to_f64().atan()).abs() < TRIG_TOLERANCE, "atan({:?})", x);
+        }
+    }
+
+    #[test]
+    fn atan2_quadrants() {
+        let one = Fixed::ONE;
+        assert_eq!(Fixed::atan2(Fixed::ZERO, Fixed::ZERO), Fixed::ZERO);
+        assert_eq!(Fixed::atan2(one, Fixed::ZERO), Fixed::HALF_PI);
+        assert_eq!(Fixed::atan2(-one, Fixed::ZERO), -Fixed::HALF_PI);
+        assert_eq!(Fixed::atan2(Fixed::ZERO, -one), Fixed::PI);
+
+        let extremes = [
+            Fixed::MIN,
+            -one,
+            -Fixed::EPSILON,
+            Fixed::ZERO,
+            Fixed::EPSILON,
+            one,
+            Fixed::MAX,
+        ];
+        for y in extremes {
+            for x in extremes {
+                let value = Fixed::atan2(y, x);
+                assert!(
+                    value >= -Fixed::PI && value <= Fixed::PI,
+                    "atan2({:?}, {:?}) = {:?}",
+                    y,
+                    x,
+                    value
+                );
+                let expected = y.to_f64().atan2(x.to_f64());
+                assert!(
+                    (value.to_f64() - expected).abs() < TRIG_TOLERANCE,
+                    "atan2({:?}, {:?})",
+                    y,
+                    x
+                );
+            }
+        }
+    }
+}
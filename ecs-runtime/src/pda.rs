@@ -1,8 +1,20 @@
 //! PDA derivation utilities
 
-use pinocchio::pubkey::{find_program_address, Pubkey};
+use pinocchio::pubkey::Pubkey;
+#[cfg(not(any(test, all(feature = "arbitrary", not(target_os = "solana")))))]
+use pinocchio::pubkey::{create_program_address, find_program_address};
+
+// Host tests and fuzzing have no PDA syscalls
+#[cfg(any(test, all(feature = "arbitrary", not(target_os = "solana"))))]
+use crate::test_utils::{create_program_address, find_program_address};
+
+/// Maximum number of seeds for a PDA, including the bump
+const MAX_SEEDS: usize = 16;
 
 /// Derive a PDA with the given seeds
+///
+/// This searches for the canonical bump on-chain and can cost thousands of
+/// compute units. Prefer `verify_pda_with_bump` when the bump is stored.
 pub fn derive_pda(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
     find_program_address(seeds, program_id)
 }
@@ -20,9 +32,130 @@ pub fn verify_pda(
     Ok(bump)
 }
 
+/// Verify a PDA using a stored canonical bump
+///
+/// Calls `create_program_address` once instead of searching for the bump.
+/// The bump must come from an earlier `find_program_address` so the address
+/// stays canonical.
+pub fn verify_pda_with_bump(
+    account_key: &Pubkey,
+    seeds: &[&[u8]],
+    bump: u8,
+    program_id: &Pubkey,
+) -> Result<(), crate::GoltError> {
+    if seeds.len() >= MAX_SEEDS {
+        return Err(crate::GoltError::InvalidPda);
+    }
+    let bump_seed = [bump];
+    let mut all_seeds: [&[u8]; MAX_SEEDS] = [&[]; MAX_SEEDS];
+    all_seeds[..seeds.len()].copy_from_slice(seeds);
+    all_seeds[seeds.len()] = &bump_seed;
+
+    let expected = create_program_address(&all_seeds[..=seeds.len()], program_id)
+        .map_err(|_| crate::GoltError::InvalidPda)?;
+    if account_key != &expected {
+        return Err(crate::GoltError::InvalidPda);
+    }
+    Ok(())
+}
+
 /// Build signer seeds with bump
 pub fn build_signer_seeds<'a>(seeds: &'a [&'a [u8]], bump: &'a [u8; 1]) -> Vec<&'a [u8]> {
     let mut all_seeds: Vec<&[u8]> = seeds.to_vec();
     all_seeds.push(bump);
     all_seeds
 }
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::Component;
+    use pinocchio_pubkey::pubkey;
+
+    use super::*;
+    use crate::test_utils::TestAccount;
+    use crate::{verify_component_pda, Component, GoltError};
+
+    const PROGRAM_ID: Pubkey = [5; 32];
+    const ENTITY: Pubkey = [3; 32];
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "health")]
+    struct Health {
+        #[entity]
+        entity: [u8; 32],
+        current: u32,
+        #[pda_bump]
+        bump: u8,
+    }
+
+    #[test]
+    fn host_derivation_matches_the_runtime() {
+        // Vectors from the Solana SDK's `create_program_address` tests
+        let loader = pubkey!("BPFLoaderUpgradeab1e11111111111111111111111");
+        assert_eq!(
+            create_program_address(&[b"", &[1]], &loader),
+            Ok(pubkey!("BwqrghZA2htAcqq8dzP1WDAhTXYTYWj7CHxF5j7TDBAe"))
+        );
+        assert_eq!(
+            create_program_address(&[b"Talking", b"Squirrels"], &loader),
+            Ok(pubkey!("2fnQrngrQT4SeLcdToJAD96phoEjNL2man2kfRLCASVk"))
+        );
+    }
+
+    #[test]
+    fn verify_with_stored_bump() {
+        let seeds: &[&[u8]] = &[b"health", &ENTITY];
+        let (address, bump) = derive_pda(seeds, &PROGRAM_ID);
+        assert_eq!(verify_pda_with_bump(&address, seeds, bump, &PROGRAM_ID), Ok(()));
+        assert_eq!(verify_pda(&address, seeds, &PROGRAM_ID), Ok(bump));
+
+        // Another bump either derives another address or is on the curve
+        let wrong = bump.wrapping_sub(1);
+        assert_eq!(
+            verify_pda_with_bump(&address, seeds, wrong, &PROGRAM_ID),
+            Err(GoltError::InvalidPda)
+        );
+        assert_eq!(
+            verify_pda_with_bump(&address, seeds, bump, &[6; 32]),
+            Err(GoltError::InvalidPda)
+        );
+
+        // No room left for the bump seed
+        let too_many: [&[u8]; MAX_SEEDS] = [b"seed"; MAX_SEEDS];
+        assert_eq!(
+            verify_pda_with_bump(&address, &too_many, bump, &PROGRAM_ID),
+            Err(GoltError::InvalidPda)
+        );
+    }
+
+    #[test]
+    fn verify_component_pda_reads_the_stored_bump() {
+        let (address, bump) = Health::derive_pda(&[&ENTITY], &PROGRAM_ID);
+        let account = |key: Pubkey, bump: u8| {
+            let mut data = vec![0; Health::SIZE];
+            Health {
+                entity: ENTITY,
+                current: 7,
+                bump,
+            }
+            .pack(&mut data);
+            TestAccount::new(key, PROGRAM_ID, &data)
+        };
+
+        let mut health = account(address, bump);
+        assert_eq!(
+            verify_component_pda::<Health>(&health.info(), &[&ENTITY], &PROGRAM_ID),
+            Ok(bump)
+        );
+        let mut wrong_bump = account(address, bump.wrapping_sub(1));
+        assert_eq!(
+            verify_component_pda::<Health>(&wrong_bump.info(), &[&ENTITY], &PROGRAM_ID),
+            Err(GoltError::InvalidPda.into())
+        );
+        let mut wrong_entity = account(address, bump);
+        assert_eq!(
+            verify_component_pda::<Health>(&wrong_entity.info(), &[&[4; 32]], &PROGRAM_ID),
+            Err(GoltError::InvalidPda.into())
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Queries over many entities' components
+//!
+//! Systems that act on N entities at once (area damage, movement ticks) pass
+//! the component accounts as remaining accounts, grouped per entity:
+//!
+//! ```text
+//! [health_0, position_0, health_1, position_1, ...]
+//! ```
+//!
+//! `Query<(&Health, &mut Position)>` walks those groups, checking each
+//! account's owner program and discriminator, and that the components of a
+//! group with an `#[entity]` field all store the same entity. `&C` yields a
+//! copy of the component, `&mut C` yields an autosaving `ComponentMut` that
+//! is written back when it goes out of scope. Each account may appear only
+//! once.
+//!
+//! # Example
+//! ```ignore
+//! let query = Query::<(&Health, &mut Position)>::new(ctx.remaining(), &[HEALTH_ID, POSITION_ID])?;
+//! for item in query.iter() {
+//!     let (health, mut position) = item?;
+//!     if health.current > 0 {
+//!         position.x += 1;
+//!     }
+//! }
+//! ```
+
+use core::marker::PhantomData;
+
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    check_component_account, load_component, load_component_autosave, Component, ComponentMut,
+    GoltError,
+};
+
+/// A single component access in a query: `&C` or `&mut C`
+pub trait QueryParam<'a> {
+    /// What the query yields for this component
+    type Item;
+
+    /// The component's `Component::ENTITY_KEY_OFFSET`
+    const ENTITY_KEY_OFFSET: Option<usize>;
+
+    /// Validate and load the component from its account
+    fn fetch(account: &'a AccountInfo, owner: &Pubkey) -> Result<Self::Item, ProgramError>;
+}
+
+impl<'a, C: Component> QueryParam<'a> for &C {
+    type Item = C;
+    const ENTITY_KEY_OFFSET: Option<usize> = C::ENTITY_KEY_OFFSET;
+
+    fn fetch(account: &'a AccountInfo, owner: &Pubkey) -> Result<Self::Item, ProgramError> {
+        check_query_account::<C>(account, owner)?;
+        load_component(account)
+    }
+}
+
+impl<'a, C: Component> QueryParam<'a> for &mut C {
+    type Item = ComponentMut<'a, C>;
+    const ENTITY_KEY_OFFSET: Option<usize> = C::ENTITY_KEY_OFFSET;
+
+    fn fetch(account: &'a AccountInfo, owner: &Pubkey) -> Result<Self::Item, ProgramError> {
+        if !account.is_writable() {
+            return Err(GoltError::AccountNotWritable.into());
+        }
+        check_query_account::<C>(account, owner)?;
+        load_component_autosave(account)
+    }
+}
+
+/// The components fetched per entity: a `QueryParam` or a tuple of them
+pub trait QueryData<'a> {
+    /// Accounts per entity group
+    const WIDTH: usize;
+
+    /// What the query yields per entity
+    type Item;
+
+    /// Load one entity's group; `accounts` and `owners` are `WIDTH` long
+    fn fetch(accounts: &'a [AccountInfo], owners: &[Pubkey]) -> Result<Self::Item, ProgramError>;
+}
+
+macro_rules! impl_query_data {
+    ($width:expr; $($param:ident $index:tt),+) => {
+        impl<'a, $($param: QueryParam<'a>),+> QueryData<'a> for ($($param,)+) {
+            const WIDTH: usize = $width;
+            type Item = ($($param::Item,)+);
+
+            fn fetch(
+                accounts: &'a [AccountInfo],
+                owners: &[Pubkey],
+            ) -> Result<Self::Item, ProgramError> {
+                let item = ($($param::fetch(&accounts[$index], &owners[$index])?,)+);
+                check_group_entity(accounts, &[$($param::ENTITY_KEY_OFFSET),+])?;
+                Ok(item)
+            }
+        }
+    };
+}
+
+impl<'a, C: Component> QueryData<'a> for &C {
+    const WIDTH: usize = 1;
+    type Item = C;
+
+    fn fetch(accounts: &'a [AccountInfo], owners: &[Pubkey]) -> Result<Self::Item, ProgramError> {
+        <&C as QueryParam<'a>>::fetch(&accounts[0], &owners[0])
+    }
+}
+
+impl<'a, C: Component> QueryData<'a> for &mut C {
+    const WIDTH: usize = 1;
+    type Item = ComponentMut<'a, C>;
+
+    fn fetch(accounts: &'a [AccountInfo], owners: &[Pubkey]) -> Result<Self::Item, ProgramError> {
+        <&mut C as QueryParam<'a>>::fetch(&accounts[0], &owners[0])
+    }
+}
+
+impl_query_data!(1; A 0);
+impl_query_data!(2; A 0, B 1);
+impl_query_data!(3; A 0, B 1, C 2);
+impl_query_data!(4; A 0, B 1, C 2, D 3);
+impl_query_data!(5; A 0, B 1, C 2, D 3, E 4);
+impl_query_data!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// Typed access to components grouped per entity
+pub struct Query<'a, D: QueryData<'a>> {
+    accounts: &'a [AccountInfo],
+    owners: &'a [Pubkey],
+    _data: PhantomData<D>,
+}
+
+impl<'a, D: QueryData<'a>> Query<'a, D> {
+    /// Create a query over `accounts`, usually `AccountContext::remaining()`
+    ///
+    /// `owners` holds the owner program of each component in `D`, in order.
+    /// Fails with `QueryMisaligned` if `owners` doesn't match `D` or the
+    /// accounts don't split into whole entity groups, and with
+    /// `DuplicateAccount` if an account appears twice. Loading a group whose
+    /// `#[entity]` components store different entities also fails with
+    /// `QueryMisaligned`.
+    pub fn new(accounts: &'a [AccountInfo], owners: &'a [Pubkey]) -> Result<Self, ProgramError> {
+        if owners.len() != D::WIDTH || !accounts.len().is_multiple_of(D::WIDTH) {
+            return Err(GoltError::QueryMisaligned.into());
+        }
+        // Each copy of a repeated account would be loaded and written back
+        // separately, the last write silently undoing the others
+        for (i, account) in accounts.iter().enumerate() {
+            if accounts[i + 1..].iter().any(|other| other.key() == account.key()) {
+                return Err(GoltError::DuplicateAccount.into());
+            }
+        }
+        Ok(Self {
+            accounts,
+            owners,
+            _data: PhantomData,
+        })
+    }
+
+    /// Number of entities in the query
+    pub fn len(&self) -> usize {
+        self.accounts.len() / D::WIDTH
+    }
+
+    /// Whether the query has no entities
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Load the components of the entity at `index`
+    pub fn get(&self, index: usize) -> Result<D::Item, ProgramError> {
+        let start = index * D::WIDTH;
+        let group = self
+            .accounts
+            .get(start..start + D::WIDTH)
+            .ok_or(GoltError::ComponentNotFound)?;
+        D::fetch(group, self.owners)
+    }
+
+    /// Iterate over the entities, loading each group as it is reached
+    pub fn iter(&self) -> QueryIter<'a, D> {
+        QueryIter {
+            groups: self.accounts.chunks_exact(D::WIDTH),
+            owners: self.owners,
+            _data: PhantomData,
+        }
+    }
+
+    /// Run `f` on every entity, stopping at the first error
+    pub fn for_each<F>(&self, mut f: F) -> Result<(), ProgramError>
+    where
+        F: FnMut(D::Item) -> Result<(), ProgramError>,
+    {
+        for item in self.iter() {
+            f(item?)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, D: QueryData<'a>> IntoIterator for &Query<'a, D> {
+    type Item = Result<D::Item, ProgramError>;
+    type IntoIter = QueryIter<'a, D>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over a query's entity groups
+pub struct QueryIter<'a, D: QueryData<'a>> {
+    groups: core::slice::ChunksExact<'a, AccountInfo>,
+    owners: &'a [Pubkey],
+    _data: PhantomData<D>,
+}
+
+impl<'a, D: QueryData<'a>> Iterator for QueryIter<'a, D> {
+    type Item = Result<D::Item, ProgramError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.groups.next().map(|group| D::fetch(group, self.owners))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.groups.size_hint()
+    }
+}
+
+/// Check that the components of one group with an `#[entity]` field all
+/// belong to the same entity
+///
+/// `offsets` holds each component's `ENTITY_KEY_OFFSET`, in group order.
+fn check_group_entity(
+    accounts: &[AccountInfo],
+    offsets: &[Option<usize>],
+) -> Result<(), ProgramError> {
+    let mut group_entity: Option<Pubkey> = None;
+    for (account, offset) in accounts.iter().zip(offsets) {
+        let Some(offset) = *offset else {
+            continue;
+        };
+        let data = account.try_borrow_data()?;
+        let entity: Pubkey = data
+            .get(offset..offset + 32)
+            .and_then(|key| key.try_into().ok())
+            .ok_or(GoltError::InvalidAccountData)?;
+        match group_entity {
+            Some(expected) if expected != entity => {
+                return Err(GoltError::QueryMisaligned.into());
+            }
+            _ => group_entity = Some(entity),
+        }
+    }
+    Ok(())
+}
+
+/// Check one query account, reporting an empty account as `ComponentNotFound`
+fn check_query_account<C: Component>(
+    account: &AccountInfo,
+    owner: &Pubkey,
+) -> Result<(), ProgramError> {
+    if account.data_is_empty() {
+        return Err(GoltError::ComponentNotFound.into());
+    }
+    check_component_account::<C>(account, owner)
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::Component;
+
+    use super::*;
+    use crate::test_utils::TestAccount;
+
+    const POSITION_ID: Pubkey = [1; 32];
+    const VELOCITY_ID: Pubkey = [2; 32];
+    const OWNERS: [Pubkey; 2] = [POSITION_ID, VELOCITY_ID];
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "position")]
+    struct Position {
+        #[entity]
+        entity: [u8; 32],
+        x: i64,
+    }
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "velocity")]
+    struct Velocity {
+        #[entity]
+        entity: [u8; 32],
+        dx: i64,
+    }
+
+    fn account<C: Component>(key: u8, owner: Pubkey, component: C) -> TestAccount {
+        let mut data = vec![0; C::SIZE];
+        component.pack(&mut data);
+        TestAccount::new([key; 32], owner, &data)
+    }
+
+    #[test]
+    fn groups_must_share_an_entity() {
+        let mut accounts = [
+            account(
+                10,
+                POSITION_ID,
+                Position {
+                    entity: [7; 32],
+                    x: 1,
+                },
+            ),
+            account(
+                11,
+                VELOCITY_ID,
+                Velocity {
+                    entity: [7; 32],
+                    dx: 2,
+                },
+            ),
+            // Entity 8's position with entity 9's velocity
+            account(
+                12,
+                POSITION_ID,
+                Position {
+                    entity: [8; 32],
+                    x: 3,
+                },
+            ),
+            account(
+                13,
+                VELOCITY_ID,
+                Velocity {
+                    entity: [9; 32],
+                    dx: 4,
+                },
+            ),
+        ];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        let query = Query::<(&Position, &mut Velocity)>::new(&infos, &OWNERS).unwrap();
+        assert_eq!(query.len(), 2);
+        let (position, velocity) = query.get(0).unwrap();
+        assert_eq!((position.x, velocity.dx), (1, 2));
+        assert_eq!(query.get(1).err(), Some(GoltError::QueryMisaligned.into()));
+    }
+
+    fn position(key: u8, x: i64) -> TestAccount {
+        account(
+            key,
+            POSITION_ID,
+            Position {
+                entity: [key; 32],
+                x,
+            },
+        )
+    }
+
+    fn velocity(key: u8, entity: u8, dx: i64) -> TestAccount {
+        account(
+            key,
+            VELOCITY_ID,
+            Velocity {
+                entity: [entity; 32],
+                dx,
+            },
+        )
+    }
+
+    fn position_data(position: Position) -> Vec<u8> {
+        let mut data = vec![0; Position::SIZE];
+        position.pack(&mut data);
+        data
+    }
+
+    #[test]
+    fn new_rejects_misaligned_and_duplicate_accounts() {
+        let mut accounts = [position(10, 1), velocity(11, 10, 2), position(12, 3)];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        // One owner per component
+        assert_eq!(
+            Query::<(&Position, &Velocity)>::new(&infos[..2], &OWNERS[..1]).err(),
+            Some(GoltError::QueryMisaligned.into())
+        );
+        assert_eq!(
+            Query::<&Position>::new(&infos[..1], &OWNERS).err(),
+            Some(GoltError::QueryMisaligned.into())
+        );
+        // Only whole groups
+        assert_eq!(
+            Query::<(&Position, &Velocity)>::new(&infos, &OWNERS).err(),
+            Some(GoltError::QueryMisaligned.into())
+        );
+
+        let repeated = [0, 1, 0, 1].map(|i| infos[i].clone());
+        assert_eq!(
+            Query::<(&Position, &Velocity)>::new(&repeated, &OWNERS).err(),
+            Some(GoltError::DuplicateAccount.into())
+        );
+    }
+
+    #[test]
+    fn missing_components() {
+        let mut accounts = [
+            position(10, 1),
+            TestAccount::new([11; 32], VELOCITY_ID, &[]),
+        ];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        let query = Query::<(&Position, &Velocity)>::new(&infos, &OWNERS).unwrap();
+        assert_eq!(
+            query.get(0).err(),
+            Some(GoltError::ComponentNotFound.into())
+        );
+        assert_eq!(
+            query.get(1).err(),
+            Some(GoltError::ComponentNotFound.into())
+        );
+
+        let query = Query::<&Position>::new(&infos[..1], &OWNERS[..1]).unwrap();
+        assert!(query.get(0).is_ok());
+        assert_eq!(
+            query.get(1).err(),
+            Some(GoltError::ComponentNotFound.into())
+        );
+    }
+
+    #[test]
+    fn components_are_checked() {
+        let mut accounts = [
+            // Owned by the velocity program
+            account(
+                10,
+                VELOCITY_ID,
+                Position {
+                    entity: [10; 32],
+                    x: 1,
+                },
+            ),
+            // A velocity where a position should be
+            account(
+                11,
+                POSITION_ID,
+                Velocity {
+                    entity: [11; 32],
+                    dx: 2,
+                },
+            ),
+            // Read-only
+            TestAccount::with_flags(
+                [12; 32],
+                POSITION_ID,
+                1,
+                false,
+                false,
+                &position_data(Position {
+                    entity: [12; 32],
+                    x: 3,
+                }),
+            ),
+        ];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        let query = Query::<&Position>::new(&infos, &OWNERS[..1]).unwrap();
+        assert_eq!(query.get(0).err(), Some(GoltError::InvalidProgramId.into()));
+        assert_eq!(
+            query.get(1).err(),
+            Some(GoltError::InvalidDiscriminator.into())
+        );
+        assert_eq!(query.get(2).unwrap().x, 3);
+
+        let query = Query::<&mut Position>::new(&infos[2..], &OWNERS[..1]).unwrap();
+        assert_eq!(
+            query.get(0).err(),
+            Some(GoltError::AccountNotWritable.into())
+        );
+    }
+
+    #[test]
+    fn mut_components_are_written_back() {
+        let mut accounts = [
+            position(10, 1),
+            velocity(11, 10, 2),
+            position(12, 3),
+            velocity(13, 12, 4),
+        ];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        let query = Query::<(&mut Position, &Velocity)>::new(&infos, &OWNERS).unwrap();
+        query
+            .for_each(|(mut position, velocity)| {
+                position.x += velocity.dx;
+                Ok(())
+            })
+            .unwrap();
+
+        // Each ComponentMut saved when it was dropped
+        let stored = |i: usize| Position::unpack(&infos[i].try_borrow_data().unwrap()).unwrap();
+        assert_eq!(stored(0).x, 3);
+        assert_eq!(stored(2).x, 7);
+    }
+}
@@ -0,0 +1,246 @@
+//! Support for `#[system]` programs
+//!
+//! A `#[system]` function takes one entity's components as `&C` / `&mut C`
+//! arguments. The generated processor checks each component account with
+//! `check_system_component`, calls the function, then hands every `&mut`
+//! component back to its program with `write_system_component`. That CPI is
+//! signed with the system's `["system_signer"]` PDA, so the component program
+//! only accepts it if the system is on its allowlist. The PDA's bump comes
+//! with the instruction data and is checked with `check_system_signer`.
+//!
+//! # Example
+//! ```ignore
+//! check_system_component::<Position>(position_account, entity, &REGISTRY_ID)?;
+//! let mut position = load_component::<Position>(position_account)?;
+//! position.x += 1;
+//!
+//! check_system_signer(system_signer, bump, program_id)?;
+//! write_system_component(&position, position_account, entity, allowlist, system_signer, bump)?;
+//! ```
+
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction, Seed, Signer},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    check_component_entity, verify_component_pda, verify_pda_with_bump, EntityComponent, GoltError,
+    OwnedComponent, SYSTEM_SIGNER_SEED, WRITE_COMPONENT_DISCRIMINATOR,
+};
+
+/// Check that an account is owned by `C`'s program, holds a `C`, belongs
+/// to `entity_account` and is that entity's `[SEED, entity]` PDA
+///
+/// `entity_account` must be an active entity of the `registry_program_id`
+/// registry (see `check_component_entity`).
+pub fn check_system_component<C: OwnedComponent + EntityComponent>(
+    component_account: &AccountInfo,
+    entity_account: &AccountInfo,
+    registry_program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    check_component_entity::<C>(
+        component_account,
+        entity_account,
+        &C::OWNER,
+        registry_program_id,
+    )?;
+    verify_component_pda::<C>(component_account, &[entity_account.key().as_ref()], &C::OWNER)?;
+    Ok(())
+}
+
+/// Check `system_signer` is this system's `["system_signer"]` PDA with bump `signer_bump`
+///
+/// Takes the bump from the caller, so only one address is derived.
+pub fn check_system_signer(
+    system_signer: &AccountInfo,
+    signer_bump: u8,
+    program_id: &Pubkey,
+) -> Result<(), ProgramError> {
+    verify_pda_with_bump(
+        system_signer.key(),
+        &[SYSTEM_SIGNER_SEED],
+        signer_bump,
+        program_id,
+    )?;
+    Ok(())
+}
+
+/// Write `component` back to its account through its component program
+///
+/// The component must still belong to `entity_account`. Calls
+/// `WRITE_COMPONENT_DISCRIMINATOR` with `[component, allowlist, system signer]`,
+/// signed with the system signer PDA (`signer_bump`, checked by `check_system_signer`).
+pub fn write_system_component<C: OwnedComponent + EntityComponent>(
+    component: &C,
+    component_account: &AccountInfo,
+    entity_account: &AccountInfo,
+    allowlist: &AccountInfo,
+    system_signer: &AccountInfo,
+    signer_bump: u8,
+) -> Result<(), ProgramError> {
+    component.validate()?;
+    let mut data = vec![0u8; 1 + C::SIZE];
+    data[0] = WRITE_COMPONENT_DISCRIMINATOR;
+    component.pack(&mut data[1..]);
+    if C::entity_key(&data[1..]) != Some(entity_account.key()) {
+        return Err(GoltError::EntityMismatch.into());
+    }
+
+    let metas = [
+        AccountMeta::writable(component_account.key()),
+        AccountMeta::readonly(allowlist.key()),
+        AccountMeta::readonly_signer(system_signer.key()),
+    ];
+    let instruction = Instruction {
+        program_id: &C::OWNER,
+        data: &data,
+        accounts: &metas,
+    };
+    let bump = [signer_bump];
+    let seeds = [Seed::from(SYSTEM_SIGNER_SEED), Seed::from(&bump[..])];
+    invoke_signed(
+        &instruction,
+        &[component_account, allowlist, system_signer],
+        &[Signer::from(&seeds[..])],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::{system, Component};
+
+    use super::*;
+    use crate::test_utils::TestAccount;
+    use crate::{derive_pda, derive_system_signer, Component, ENTITY_DISCRIMINATOR};
+
+    const SYSTEM_ID: Pubkey = [4; 32];
+    const COMPONENT_ID: Pubkey = [5; 32];
+    const REGISTRY_ID: Pubkey = [1; 32];
+    const ENTITY: Pubkey = [3; 32];
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "health")]
+    struct Health {
+        #[entity]
+        entity: [u8; 32],
+        current: u32,
+        #[pda_bump]
+        bump: u8,
+    }
+
+    impl OwnedComponent for Health {
+        const OWNER: Pubkey = COMPONENT_ID;
+    }
+
+    #[system(registry = REGISTRY_ID)]
+    fn heal(health: &mut Health, amount: u32) {
+        health.current += amount;
+    }
+
+    fn entity(owner: Pubkey, active: bool) -> TestAccount {
+        let mut data = vec![0; 56];
+        data[0..8].copy_from_slice(&ENTITY_DISCRIMINATOR);
+        data[16..48].copy_from_slice(&[2; 32]);
+        data[48] = active as u8;
+        TestAccount::new(ENTITY, owner, &data)
+    }
+
+    fn health(entity: Pubkey) -> TestAccount {
+        let (address, bump) = derive_pda(&[b"health", &entity], &COMPONENT_ID);
+        let mut data = vec![0; Health::SIZE];
+        Health {
+            entity,
+            current: 7,
+            bump,
+        }
+        .pack(&mut data);
+        TestAccount::new(address, COMPONENT_ID, &data)
+    }
+
+    #[test]
+    fn component_must_belong_to_an_active_entity() {
+        let check = |mut health: TestAccount, mut entity: TestAccount| {
+            check_system_component::<Health>(&health.info(), &entity.info(), &REGISTRY_ID)
+        };
+        assert_eq!(check(health(ENTITY), entity(REGISTRY_ID, true)), Ok(()));
+        assert_eq!(
+            check(health(ENTITY), entity(REGISTRY_ID, false)),
+            Err(GoltError::EntityNotActive.into())
+        );
+        // An "entity" account anyone could have written
+        assert_eq!(
+            check(health(ENTITY), entity([9; 32], true)),
+            Err(GoltError::InvalidProgramId.into())
+        );
+        assert_eq!(
+            check(health([6; 32]), entity(REGISTRY_ID, true)),
+            Err(GoltError::EntityMismatch.into())
+        );
+        // Right data at another address
+        let mut data = vec![0; Health::SIZE];
+        Health::unpack(&health(ENTITY).info().try_borrow_data().unwrap())
+            .unwrap()
+            .pack(&mut data);
+        assert_eq!(
+            check(
+                TestAccount::new([8; 32], COMPONENT_ID, &data),
+                entity(REGISTRY_ID, true)
+            ),
+            Err(GoltError::InvalidPda.into())
+        );
+    }
+
+    #[test]
+    fn signer_bump() {
+        let (signer, bump) = derive_system_signer(&SYSTEM_ID);
+        let mut account = TestAccount::new(signer, [0; 32], &[]);
+        let account = account.info();
+        assert_eq!(check_system_signer(&account, bump, &SYSTEM_ID), Ok(()));
+        assert_eq!(
+            check_system_signer(&account, bump.wrapping_sub(1), &SYSTEM_ID),
+            Err(GoltError::InvalidPda.into())
+        );
+        assert_eq!(
+            check_system_signer(&account, bump, &[6; 32]),
+            Err(GoltError::InvalidPda.into())
+        );
+    }
+
+    #[test]
+    fn generated_processor() {
+        let (signer, bump) = derive_system_signer(&SYSTEM_ID);
+        let run = |entity: TestAccount, signer_bump: u8| {
+            let mut accounts = [
+                entity,
+                health(ENTITY),
+                TestAccount::new(signer, [0; 32], &[]),
+                TestAccount::new([10; 32], COMPONENT_ID, &[]),
+                TestAccount::new(COMPONENT_ID, [0; 32], &[]),
+            ];
+            let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+            let data = HealInstruction::Heal {
+                system_signer_bump: signer_bump,
+                amount: 3,
+            }
+            .pack();
+            process_instruction(&SYSTEM_ID, &infos, &data)
+        };
+
+        assert_eq!(run(entity(REGISTRY_ID, true), bump), Ok(()));
+        assert_eq!(
+            run(entity(REGISTRY_ID, false), bump),
+            Err(GoltError::EntityNotActive.into())
+        );
+        assert_eq!(
+            run(entity([9; 32], true), bump),
+            Err(GoltError::InvalidProgramId.into())
+        );
+        assert_eq!(
+            run(entity(REGISTRY_ID, true), bump.wrapping_sub(1)),
+            Err(GoltError::InvalidPda.into())
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! In-memory accounts, PDAs and sysvars for unit tests and fuzzing
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use pinocchio::{
+    account_info::{AccountInfo, MAX_PERMITTED_DATA_INCREASE},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::rent::Rent,
+};
+use sha2::{Digest, Sha256};
+
+/// Bytes before the data in the runtime's account layout
+const HEADER_LEN: usize = 88;
+
+/// Account backing an `AccountInfo`, laid out as the entrypoint passes it
+pub struct TestAccount {
+    // u64 words keep the header's lamports and length aligned
+    buffer: Vec<u64>,
+}
+
+impl TestAccount {
+    /// Writable, non-signer account owned by `owner` holding `data`
+    ///
+    /// Like the entrypoint's accounts, the data is followed by room to grow
+    /// by `MAX_PERMITTED_DATA_INCREASE` bytes with `realloc`.
+    pub fn new(key: Pubkey, owner: Pubkey, data: &[u8]) -> Self {
+        Self::with_flags(key, owner, 1_000_000, false, true, data)
+    }
+
+    /// Account with the given lamports and signer / writable flags
+    pub fn with_flags(
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        is_signer: bool,
+        is_writable: bool,
+        data: &[u8],
+    ) -> Self {
+        let mut bytes = vec![0u8; HEADER_LEN + data.len() + MAX_PERMITTED_DATA_INCREASE];
+        bytes[1] = is_signer as u8;
+        bytes[2] = is_writable as u8;
+        bytes[8..40].copy_from_slice(&key);
+        bytes[40..72].copy_from_slice(&owner);
+        bytes[72..80].copy_from_slice(&lamports.to_le_bytes());
+        bytes[80..88].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes[HEADER_LEN..HEADER_LEN + data.len()].copy_from_slice(data);
+
+        let mut buffer = vec![0u64; bytes.len().div_ceil(8)];
+        for (word, chunk) in buffer.iter_mut().zip(bytes.chunks(8)) {
+            let mut padded = [0; 8];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            *word = u64::from_le_bytes(padded);
+        }
+        Self { buffer }
+    }
+
+    /// Mark the account as a signer
+    pub fn signer(mut self) -> Self {
+        self.buffer[0] |= 1 << 8;
+        self
+    }
+
+    /// `AccountInfo` pointing at this account, valid while it is alive
+    pub fn info(&mut self) -> AccountInfo {
+        // `AccountInfo` is a `repr(C)` wrapper around a pointer to the header
+        unsafe { core::mem::transmute::<*mut u64, AccountInfo>(self.buffer.as_mut_ptr()) }
+    }
+}
+
+/// Default cluster rent, standing in for the Rent sysvar
+pub fn rent() -> Result<Rent, ProgramError> {
+    Ok(Rent {
+        lamports_per_byte_year: 3480,
+        exemption_threshold: 2.0,
+        burn_percent: 50,
+    })
+}
+
+/// `create_program_address` as the runtime computes it
+pub fn create_program_address(
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    let mut hasher = Sha256::new();
+    for seed in seeds {
+        hasher.update(seed);
+    }
+    hasher.update(program_id);
+    hasher.update(b"ProgramDerivedAddress");
+    let address: Pubkey = hasher.finalize().into();
+    // A PDA must not be a valid ed25519 public key
+    if CompressedEdwardsY(address).decompress().is_some() {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(address)
+}
+
+/// `find_program_address` as the runtime computes it
+pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+    (0..=u8::MAX)
+        .rev()
+        .find_map(|bump| {
+            let bump_seed = [bump];
+            let mut all_seeds = seeds.to_vec();
+            all_seeds.push(&bump_seed);
+            create_program_address(&all_seeds, program_id)
+                .ok()
+                .map(|address| (address, bump))
+        })
+        .expect("no valid bump")
+}
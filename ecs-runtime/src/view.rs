@@ -0,0 +1,348 @@
+//! Zero-copy component views
+//!
+//! `load_component` copies the whole account into a `C` and `ComponentMut`
+//! writes it all back. For large components that costs compute units on every
+//! call, so these views borrow the account data instead and read or write
+//! single fields in place.
+//!
+//! Field accessors are generated by `#[derive(Component)]` as two traits,
+//! `{Name}View` (getters) and `{Name}ViewMut` (setters). They decode
+//! little-endian bytes at fixed offsets, so no alignment is assumed. Each
+//! accessor wraps a `Name::read_{field}` / `Name::write_{field}` function
+//! that works on a plain byte slice.
+//!
+//! # Example
+//! ```ignore
+//! use health::{Health, HealthView, HealthViewMut};
+//!
+//! let mut health = load_component_ref_mut::<Health>(account, program_id)?;
+//! let current = health.current();
+//! health.set_current(current.saturating_sub(amount));
+//! ```
+
+use core::marker::PhantomData;
+
+use pinocchio::{
+    account_info::{AccountInfo, Ref, RefMut},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{Component, GoltError};
+
+/// Raw byte access used by generated read accessors
+pub trait ComponentBytes {
+    /// The component's account data, starting with the discriminator
+    fn bytes(&self) -> &[u8];
+}
+
+/// Raw byte access used by generated write accessors
+pub trait ComponentBytesMut: ComponentBytes {
+    /// The component's account data, starting with the discriminator
+    fn bytes_mut(&mut self) -> &mut [u8];
+}
+
+/// Borrowed, read-only view of a component's account data
+pub struct ComponentRef<'a, C: Component> {
+    data: Ref<'a, [u8]>,
+    _component: PhantomData<C>,
+}
+
+impl<'a, C: Component> ComponentRef<'a, C> {
+    /// Borrow an account's data as a `C` view, checking owner, size, discriminator and version
+    pub fn new(account: &'a AccountInfo, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        check_owner(account, program_id)?;
+        let data = account.try_borrow_data()?;
+        check_component_data::<C>(&data)?;
+        Ok(Self {
+            data,
+            _component: PhantomData,
+        })
+    }
+
+    /// Copy the full component out of the account
+    pub fn to_component(&self) -> Option<C> {
+        C::unpack(&self.data)
+    }
+}
+
+impl<'a, C: Component> ComponentBytes for ComponentRef<'a, C> {
+    #[inline]
+    fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Borrowed, mutable view of a component's account data
+///
+/// Writes go straight to the account, there is nothing to save.
+pub struct ComponentRefMut<'a, C: Component> {
+    data: RefMut<'a, [u8]>,
+    _component: PhantomData<C>,
+}
+
+impl<'a, C: Component> ComponentRefMut<'a, C> {
+    /// Mutably borrow an account's data as a `C` view, checking owner, size, discriminator
+    /// and version
+    pub fn new(account: &'a AccountInfo, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        check_owner(account, program_id)?;
+        let data = account.try_borrow_mut_data()?;
+        check_component_data::<C>(&data)?;
+        Ok(Self {
+            data,
+            _component: PhantomData,
+        })
+    }
+
+    /// Copy the full component out of the account
+    pub fn to_component(&self) -> Option<C> {
+        C::unpack(&self.data)
+    }
+
+    /// Overwrite the whole component
+    pub fn store(&mut self, component: &C) -> Result<(), GoltError> {
+        component.validate()?;
+        component.pack(&mut self.data);
+        Ok(())
+    }
+}
+
+impl<'a, C: Component> ComponentBytes for ComponentRefMut<'a, C> {
+    #[inline]
+    fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<'a, C: Component> ComponentBytesMut for ComponentRefMut<'a, C> {
+    #[inline]
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
+
+/// Borrow a component owned by `program_id` without copying it
+pub fn load_component_ref<'a, C: Component>(
+    account: &'a AccountInfo,
+    program_id: &Pubkey,
+) -> Result<ComponentRef<'a, C>, ProgramError> {
+    ComponentRef::new(account, program_id)
+}
+
+/// Mutably borrow a component owned by `program_id` without copying it
+pub fn load_component_ref_mut<'a, C: Component>(
+    account: &'a AccountInfo,
+    program_id: &Pubkey,
+) -> Result<ComponentRefMut<'a, C>, ProgramError> {
+    ComponentRefMut::new(account, program_id)
+}
+
+/// Check that the account is owned by the component program
+fn check_owner(account: &AccountInfo, program_id: &Pubkey) -> Result<(), GoltError> {
+    if unsafe { account.owner() } != program_id {
+        return Err(GoltError::InvalidProgramId);
+    }
+    Ok(())
+}
+
+/// Check that account data is large enough and carries the component's
+/// discriminator and current version
+fn check_component_data<C: Component>(data: &[u8]) -> Result<(), GoltError> {
+    if data.len() < C::SIZE {
+        return Err(GoltError::InvalidAccountData);
+    }
+    if !C::verify_discriminator(data) {
+        return Err(GoltError::InvalidDiscriminator);
+    }
+    if C::VERSION != 0 && (data[8] != C::VERSION || C::LEGACY_SIZE == Some(data.len())) {
+        return Err(GoltError::UnsupportedVersion);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use golt_macros::Component;
+
+    use super::*;
+    use crate::test_utils::TestAccount;
+
+    const PROGRAM_ID: Pubkey = [1; 32];
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "health")]
+    struct Health {
+        #[entity]
+        entity: [u8; 32],
+        current: u32,
+    }
+
+    fn health_account(owner: Pubkey) -> TestAccount {
+        let mut data = vec![0; Health::SIZE];
+        Health {
+            entity: [2; 32],
+            current: 7,
+        }
+        .pack(&mut data);
+        TestAccount::new([3; 32], owner, &data)
+    }
+
+    #[test]
+    fn views_check_owner() {
+        let mut account = health_account(PROGRAM_ID);
+        let info = account.info();
+        assert_eq!(
+            load_component_ref::<Health>(&info, &PROGRAM_ID)
+                .unwrap()
+                .current(),
+            7
+        );
+        assert!(load_component_ref_mut::<Health>(&info, &PROGRAM_ID).is_ok());
+
+        let mut foreign = health_account([4; 32]);
+        let info = foreign.info();
+        assert_eq!(
+            load_component_ref::<Health>(&info, &PROGRAM_ID).err(),
+            Some(GoltError::InvalidProgramId.into())
+        );
+        assert_eq!(
+            load_component_ref_mut::<Health>(&info, &PROGRAM_ID).err(),
+            Some(GoltError::InvalidProgramId.into())
+        );
+    }
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(seed = "stats", version = 2)]
+    struct Stats {
+        #[entity]
+        entity: [u8; 32],
+        owner: Pubkey,
+        #[max_len(4)]
+        title: String,
+        level: u16,
+        #[padding(3)]
+        xp: i64,
+        alive: bool,
+        mood: i8,
+        speed: f32,
+        tag: [u8; 4],
+    }
+
+    fn stats() -> Stats {
+        Stats {
+            entity: [2; 32],
+            owner: [4; 32],
+            title: "sir".to_string(),
+            level: 12,
+            xp: -40,
+            alive: true,
+            mood: -3,
+            speed: 1.5,
+            tag: *b"npc0",
+        }
+    }
+
+    fn packed(stats: &Stats) -> Vec<u8> {
+        let mut data = vec![0; Stats::SIZE];
+        stats.pack(&mut data);
+        data
+    }
+
+    #[test]
+    fn raw_accessors_read_and_write_in_place() {
+        let mut data = packed(&stats());
+        assert_eq!(Stats::read_entity(&data), &[2; 32]);
+        assert_eq!(Stats::read_owner(&data), &[4; 32]);
+        assert_eq!(Stats::read_level(&data), 12);
+        assert_eq!(Stats::read_xp(&data), -40);
+        assert!(Stats::read_alive(&data));
+        assert_eq!(Stats::read_mood(&data), -3);
+        assert_eq!(Stats::read_speed(&data), 1.5);
+        assert_eq!(Stats::read_tag(&data), b"npc0");
+
+        // Fields after a `#[max_len]` region and padding are at fixed offsets,
+        // and a write only touches the field's own bytes
+        let before = data.clone();
+        Stats::write_xp(&mut data, i64::MIN + 1);
+        let changed: Vec<usize> = (0..data.len()).filter(|&i| data[i] != before[i]).collect();
+        assert_eq!(changed, (86..94).collect::<Vec<_>>());
+
+        Stats::write_owner(&mut data, &[5; 32]);
+        Stats::write_level(&mut data, u16::MAX);
+        Stats::write_alive(&mut data, false);
+        Stats::write_mood(&mut data, i8::MIN);
+        Stats::write_speed(&mut data, -0.25);
+        Stats::write_tag(&mut data, b"boss");
+        assert_eq!(
+            Stats::unpack(&data),
+            Some(Stats {
+                owner: [5; 32],
+                level: u16::MAX,
+                xp: i64::MIN + 1,
+                alive: false,
+                mood: i8::MIN,
+                speed: -0.25,
+                tag: *b"boss",
+                ..stats()
+            })
+        );
+    }
+
+    #[test]
+    fn views_read_and_write_the_account() {
+        let mut account = TestAccount::new([3; 32], PROGRAM_ID, &packed(&stats()));
+        let info = account.info();
+
+        let view = Stats::view(&info, &PROGRAM_ID).unwrap();
+        assert_eq!(view.level(), 12);
+        assert_eq!(view.owner(), &[4; 32]);
+        assert_eq!(view.to_component(), Some(stats()));
+        drop(view);
+
+        let mut view = Stats::view_mut(&info, &PROGRAM_ID).unwrap();
+        view.set_level(13);
+        view.set_alive(false);
+        view.set_tag(b"hero");
+        assert_eq!(view.level(), 13);
+        // The mutable view holds the borrow until dropped
+        assert!(Stats::view(&info, &PROGRAM_ID).is_err());
+        drop(view);
+
+        assert_eq!(
+            Stats::unpack(&info.try_borrow_data().unwrap()),
+            Some(Stats {
+                level: 13,
+                alive: false,
+                tag: *b"hero",
+                ..stats()
+            })
+        );
+    }
+
+    #[test]
+    fn views_check_size_discriminator_and_version() {
+        let check = |data: &[u8]| {
+            let mut account = TestAccount::new([3; 32], PROGRAM_ID, data);
+            let info = account.info();
+            let err = Stats::view(&info, &PROGRAM_ID).err();
+            assert_eq!(Stats::view_mut(&info, &PROGRAM_ID).err(), err);
+            err
+        };
+
+        let data = packed(&stats());
+        assert_eq!(check(&data), None);
+        assert_eq!(
+            check(&data[..Stats::SIZE - 1]),
+            Some(GoltError::InvalidAccountData.into())
+        );
+        let mut foreign = data.clone();
+        foreign[0] ^= 1;
+        assert_eq!(
+            check(&foreign),
+            Some(GoltError::InvalidDiscriminator.into())
+        );
+        let mut old = data;
+        old[8] = 1;
+        assert_eq!(check(&old), Some(GoltError::UnsupportedVersion.into()));
+    }
+}